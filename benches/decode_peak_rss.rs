@@ -0,0 +1,96 @@
+// Peak RSS of decoding a large OTLP export whole vs one `ResourceMetrics` at a time, as
+// `otlp::stream::for_each_resource` does
+//
+// da-reader is a binary crate, so this reproduces the per-resource walk with the same
+// prost calls rather than calling it. Linux only: reads VmHWM, reset through
+// /proc/self/clear_refs.
+//
+// Run with `cargo bench --bench decode_peak_rss`.
+
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::metrics::v1::{
+    metric, number_data_point, Gauge, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics,
+};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use prost::bytes::Buf;
+use prost::encoding::{check_wire_type, decode_key, skip_field, DecodeContext, WireType};
+use prost::{DecodeError, Message};
+
+/// One resource per node, each exporting `per_resource` gauges
+fn multi_resource_request(resources: usize, per_resource: usize) -> ExportMetricsServiceRequest {
+    let resource_metrics = (0..resources)
+        .map(|r| ResourceMetrics {
+            resource: Some(Resource {
+                attributes: vec![KeyValue {
+                    key: "service.instance.id".to_string(),
+                    value: Some(AnyValue { value: Some(any_value::Value::StringValue(format!("node-{}", r))) }),
+                }],
+                ..Default::default()
+            }),
+            scope_metrics: vec![ScopeMetrics {
+                metrics: (0..per_resource)
+                    .map(|m| Metric {
+                        name: format!("node_metric_{}", m),
+                        data: Some(metric::Data::Gauge(Gauge {
+                            data_points: vec![NumberDataPoint {
+                                value: Some(number_data_point::Value::AsInt((r * per_resource + m) as i64)),
+                                ..Default::default()
+                            }],
+                        })),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+        .collect();
+    ExportMetricsServiceRequest { resource_metrics }
+}
+
+/// Decode `body` one resource at a time, dropping each before the next
+fn per_resource(mut body: &[u8]) -> Result<usize, DecodeError> {
+    let mut resources = 0;
+    while body.has_remaining() {
+        let (tag, wire_type) = decode_key(&mut body)?;
+        if tag == 1 {
+            check_wire_type(WireType::LengthDelimited, wire_type)?;
+            drop(ResourceMetrics::decode_length_delimited(&mut body)?);
+            resources += 1;
+        } else {
+            skip_field(wire_type, tag, &mut body, DecodeContext::default())?;
+        }
+    }
+    Ok(resources)
+}
+
+#[cfg(target_os = "linux")]
+fn status_kb(field: &str) -> u64 {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap();
+    let line = status.lines().find(|l| l.starts_with(field)).unwrap();
+    line.split_whitespace().nth(1).unwrap().parse().unwrap()
+}
+
+/// Peak RSS growth while `work` runs, in KiB
+#[cfg(target_os = "linux")]
+fn peak_rss_kb<T>(work: impl FnOnce() -> T) -> u64 {
+    std::fs::write("/proc/self/clear_refs", "5").unwrap();
+    let before = status_kb("VmRSS:");
+    drop(work());
+    status_kb("VmHWM:") - before
+}
+
+#[cfg(target_os = "linux")]
+fn main() {
+    let body = multi_resource_request(20_000, 10).encode_to_vec();
+    // Per-resource first: memory freed by the whole-request decode would otherwise be reused
+    let streamed = peak_rss_kb(|| per_resource(&body).unwrap());
+    let whole = peak_rss_kb(|| ExportMetricsServiceRequest::decode(body.as_slice()).unwrap());
+    println!("{} KiB body: whole-request decode peaks at +{} KiB, per-resource at +{} KiB", body.len() / 1024, whole, streamed);
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    println!("decode_peak_rss reads /proc/self/status and only runs on Linux");
+}
@@ -0,0 +1,68 @@
+// Allocations and time per OTLP success response: encoded once and cloned, as
+// `otlp::handlers::success_response` does, vs encoded on every request
+//
+// da-reader is a binary crate, so this reproduces the handler's encoding with the same
+// crates rather than calling it. Owns the allocator, so it counts only its own work.
+//
+// Run with `cargo bench --bench success_response`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use axum::body::Bytes;
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceResponse;
+use prost::Message;
+
+const CALLS: u32 = 100_000;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+fn encode(is_json: bool) -> Bytes {
+    let resp = ExportMetricsServiceResponse { partial_success: None };
+    if is_json {
+        Bytes::from(serde_json::to_vec(&resp).unwrap())
+    } else {
+        Bytes::from(resp.encode_to_vec())
+    }
+}
+
+static JSON_SUCCESS: LazyLock<Bytes> = LazyLock::new(|| encode(true));
+static PROTOBUF_SUCCESS: LazyLock<Bytes> = LazyLock::new(|| encode(false));
+
+/// Allocations and mean time per call of `work`, after one warm-up call
+fn measure(work: impl Fn() -> Bytes) -> (u64, Duration) {
+    drop(work());
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    for _ in 0..CALLS {
+        drop(black_box(work()));
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - allocations, started.elapsed() / CALLS)
+}
+
+fn main() {
+    for (name, is_json, cached) in [("json", true, &JSON_SUCCESS), ("protobuf", false, &PROTOBUF_SUCCESS)] {
+        let (cached_allocs, cached_time) = measure(|| Bytes::clone(cached));
+        let (fresh_allocs, fresh_time) = measure(|| encode(is_json));
+        println!("{}: cached {} allocs, {:?}/call; encoded per request {} allocs, {:?}/call",
+                 name, cached_allocs, cached_time, fresh_allocs, fresh_time);
+    }
+}
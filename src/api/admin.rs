@@ -0,0 +1,394 @@
+use axum::{extract::State, http::{HeaderMap, StatusCode}, Json};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+use crate::config::Config;
+use crate::metrics::{generate_batch, threshold_granularity_warning, AlertState};
+use crate::types::{AppState, Batch, DasMetrics, Sample, SampleBit};
+use crate::utils::window_size;
+
+/// Most recent samples included in a snapshot
+const SNAPSHOT_MAX_SAMPLES: usize = 100;
+
+/// Reject the request unless it carries `Authorization: Bearer <server.admin_token>`
+///
+/// Admin endpoints are disabled entirely when no token is configured.
+pub fn require_admin(headers: &HeaderMap, state: &AppState) -> Result<(), (StatusCode, String)> {
+    let Some(expected) = state.config.server.admin_token.as_deref() else {
+        return Err((StatusCode::FORBIDDEN, "Admin endpoints are disabled (no server.admin_token configured)".to_string()));
+    };
+
+    let provided = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+        _ => Err((StatusCode::UNAUTHORIZED, "Missing or invalid admin token".to_string())),
+    }
+}
+
+/// Compare secrets without short-circuiting on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// POST /admin/generate-batch: compute a batch over the current ring buffer now
+///
+/// The batch is returned only; it is not written to disk, posted to DA, or counted
+/// toward threshold alerts.
+pub async fn handle_generate_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Batch>, (StatusCode, String)> {
+    require_admin(&headers, &state)?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    match generate_batch(&state, now) {
+        Some((batch, _bitmap)) => {
+            info!("📦 On-demand batch generated: n={}, good={}", batch.n, batch.good);
+            Ok(Json(batch))
+        }
+        None => Err((StatusCode::CONFLICT, "No countable samples in ring buffer yet".to_string())),
+    }
+}
+
+/// POST /admin/reload-key: re-read the Celestia credentials and swap the DA signing
+/// key without restarting
+///
+/// A running process never sees edits to its environment, so `.env` is re-read first
+/// and the process environment is the fallback. The current key is kept (422) if the
+/// new credentials don't derive a valid key.
+pub async fn handle_reload_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_admin(&headers, &state)?;
+    let dotenv: HashMap<String, String> = dotenvy::dotenv_iter()
+        .map(|iter| iter.flatten().collect())
+        .unwrap_or_default();
+    reload_signing_key(&state, |name| dotenv.get(name).cloned().or_else(|| std::env::var(name).ok()))
+}
+
+fn reload_signing_key(
+    state: &AppState,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    match state.da_signer.reload(&state.config.celestia, lookup) {
+        Ok(public_key) => {
+            info!("🔑 DA signing key reloaded (public key {})", public_key);
+            Ok(Json(serde_json::json!({ "public_key": public_key })))
+        }
+        Err(e) => Err((StatusCode::UNPROCESSABLE_ENTITY, format!("Key not reloaded: {}", e))),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThresholdRequest {
+    pub threshold_percent: f64,
+}
+
+/// POST /admin/threshold: change `proofs.threshold_percent` for the batches computed
+/// from now on
+///
+/// The override lives in memory only; a restart returns to the configured value. The
+/// value must lie in (0, 1] and stay below `proofs.warn_threshold_percent` (422 otherwise).
+/// Like at startup, a value the window's sample count can't represent exactly is warned
+/// about, and the warning is returned as `granularity_warning`.
+pub async fn handle_set_threshold(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ThresholdRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_admin(&headers, &state)?;
+
+    let percent = request.threshold_percent;
+    if !(percent > 0.0 && percent <= 1.0) {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, format!("threshold_percent must be in (0, 1], got {}", percent)));
+    }
+    if let Some(warn) = state.config.proofs.warn_threshold_percent {
+        if percent >= warn {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("threshold_percent must stay below proofs.warn_threshold_percent ({}), got {}", warn, percent),
+            ));
+        }
+    }
+
+    let previous = state.threshold_percent();
+    *state.threshold_override.lock().unwrap() = Some(percent);
+    info!("🎯 Threshold changed at runtime: {:.1}% → {:.1}% (not persisted)", previous * 100.0, percent * 100.0);
+
+    let config = &state.config;
+    let granularity_warning = window_size(config.batching.window_secs, config.sampling.tick_secs)
+        .ok()
+        .and_then(|size| threshold_granularity_warning(size, percent));
+    if let Some(warning) = &granularity_warning {
+        warn!("⚠️  {}", warning);
+    }
+
+    Ok(Json(serde_json::json!({
+        "threshold_percent": percent,
+        "previous": previous,
+        "persisted": false,
+        "granularity_warning": granularity_warning,
+    })))
+}
+
+/// Live in-memory state, for bug reports
+#[derive(Debug, Serialize)]
+pub struct Snapshot {
+    pub taken_at: u64,
+    /// Running configuration; secrets are never serialized
+    pub config: Config,
+    pub das_metrics: DasMetrics,
+    pub ring_buffer: Vec<SampleBit>,
+    /// The most recent samples (at most `SNAPSHOT_MAX_SAMPLES`)
+    pub recent_samples: Vec<Sample>,
+    pub counters: SnapshotCounters,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SnapshotCounters {
+    pub samples_total: usize,
+    pub ring_buffer_len: usize,
+    pub alerts: AlertState,
+}
+
+/// GET /admin/snapshot: dump the full in-memory state as JSON
+pub async fn handle_snapshot(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Snapshot>, (StatusCode, String)> {
+    require_admin(&headers, &state)?;
+
+    let taken_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let das_metrics = state.das_metrics.lock().unwrap().clone();
+    let ring_buffer: Vec<SampleBit> = state.ring_buffer.lock().unwrap().iter().cloned().collect();
+    let (samples_total, recent_samples) = {
+        let samples = state.samples.lock().unwrap();
+        let skip = samples.len().saturating_sub(SNAPSHOT_MAX_SAMPLES);
+        (samples.len(), samples[skip..].to_vec())
+    };
+    let alerts = state.alert_state.lock().unwrap().clone();
+
+    Ok(Json(Snapshot {
+        taken_at,
+        config: (*state.config).clone(),
+        das_metrics,
+        counters: SnapshotCounters {
+            samples_total,
+            ring_buffer_len: ring_buffer.len(),
+            alerts,
+        },
+        ring_buffer,
+        recent_samples,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::config::Config;
+    use crate::types::SampleBit;
+
+    fn admin_state() -> AppState {
+        let mut config = Config::for_tests();
+        config.server.admin_token = Some("s3cret".to_string());
+        AppState::new(Arc::new(config))
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", format!("Bearer {}", token).parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_generate_batch_returns_batch() {
+        let state = admin_state();
+        {
+            let mut ring_buffer = state.ring_buffer.lock().unwrap();
+            for (i, ok) in [true, true, false, true].into_iter().enumerate() {
+                ring_buffer.push(SampleBit { timestamp: 100 + i as u64 * 30, ok, ..Default::default() }, usize::MAX);
+            }
+        }
+
+        let Json(batch) = handle_generate_batch(State(state), bearer("s3cret")).await.unwrap();
+        assert_eq!(batch.n, 4);
+        assert_eq!(batch.good, 3);
+        assert_eq!(batch.window.start, 100);
+        assert_eq!(batch.window.end, 190);
+    }
+
+    #[tokio::test]
+    async fn test_generate_batch_requires_token() {
+        let state = admin_state();
+        let err = handle_generate_batch(State(state.clone()), bearer("wrong")).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::UNAUTHORIZED);
+        let err = handle_generate_batch(State(state), HeaderMap::new()).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::UNAUTHORIZED);
+
+        let disabled = AppState::new(Arc::new(Config::for_tests()));
+        let err = handle_generate_batch(State(disabled), bearer("s3cret")).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_sections_without_secrets() {
+        let state = admin_state();
+        state.das_metrics.lock().unwrap().head = Some(42);
+        {
+            let mut samples = state.samples.lock().unwrap();
+            for t in 0..150 {
+                samples.push(Sample { timestamp: t, ok: true, ..Default::default() });
+            }
+        }
+        state.ring_buffer.lock().unwrap().push(SampleBit { timestamp: 149, ok: true, ..Default::default() }, usize::MAX);
+
+        let err = handle_snapshot(State(state.clone()), HeaderMap::new()).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::UNAUTHORIZED);
+
+        let Json(snapshot) = handle_snapshot(State(state.clone()), bearer("s3cret")).await.unwrap();
+        let json = serde_json::to_value(&snapshot).unwrap();
+        for section in ["config", "das_metrics", "ring_buffer", "recent_samples", "counters"] {
+            assert!(json.get(section).is_some(), "missing {}", section);
+        }
+        assert_eq!(json["das_metrics"]["head"], 42);
+        assert_eq!(json["counters"]["samples_total"], 150);
+        assert_eq!(json["counters"]["ring_buffer_len"], 1);
+        assert_eq!(snapshot.recent_samples.len(), SNAPSHOT_MAX_SAMPLES);
+        assert_eq!(snapshot.recent_samples[0].timestamp, 50);
+
+        let text = json.to_string();
+        assert!(!text.contains("s3cret"));
+        assert!(!text.contains(state.config.celestia.private_key_hex.as_deref().unwrap()));
+        assert!(json["config"]["celestia"].get("private_key_hex").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reload_key_swaps_signer_for_later_posts() {
+        let state = admin_state();
+        let old_key = state.da_signer.public_key_hex().unwrap();
+        crate::da::post_manifest_to_da(&state, 100).await.unwrap();
+
+        let new_private_key = "11".repeat(32);
+        let env = |name: &str| (name == "CELESTIA_PRIVATE_KEY").then(|| new_private_key.clone());
+        let Json(body) = reload_signing_key(&state, env).unwrap();
+        let new_key = body["public_key"].as_str().unwrap().to_string();
+        assert_ne!(new_key, old_key);
+        // The "real" poster pays for blobs with this key, so it moves to the new account too
+        assert_eq!(state.da_signer.private_key_hex(), Some(new_private_key.clone()));
+
+        crate::da::post_manifest_to_da(&state, 200).await.unwrap();
+        let crate::da::DaPoster::Mock(mock) = state.da_poster.as_ref() else {
+            panic!("tests use the mock poster");
+        };
+        let signers: Vec<_> = mock.submitted().into_iter().map(|b| b.signer.unwrap()).collect();
+        assert_eq!(signers, vec![old_key, new_key.clone()]);
+
+        // A bad key is rejected and the current one kept
+        let err = reload_signing_key(&state, |_| Some("not-hex".to_string())).unwrap_err();
+        assert_eq!(err.0, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(state.da_signer.public_key_hex(), Some(new_key));
+    }
+
+    #[tokio::test]
+    async fn test_reload_key_falls_back_to_mnemonic_like_startup() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let env = |name: &str| match name {
+            "CELESTIA_PRIVATE_KEY" => Some("not-hex".to_string()),
+            "CELESTIA_MNEMONIC" => Some(mnemonic.to_string()),
+            _ => None,
+        };
+        let from_mnemonic = crate::crypto::mnemonic_to_private_key_hex(mnemonic).unwrap();
+
+        let mut config = Config::for_tests();
+        config.server.admin_token = Some("s3cret".to_string());
+        config.celestia.key_fallback_to_mnemonic = true;
+        let state = AppState::new(Arc::new(config));
+        let Json(body) = reload_signing_key(&state, env).unwrap();
+        assert!(body["public_key"].is_string());
+        assert_eq!(state.da_signer.private_key_hex(), Some(from_mnemonic));
+
+        // Without the fallback the invalid private key is an error, as at startup
+        let state = admin_state();
+        assert_eq!(reload_signing_key(&state, env).unwrap_err().0, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_reload_key_requires_token() {
+        let err = handle_reload_key(State(admin_state()), HeaderMap::new()).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_set_threshold_applies_to_next_batch() {
+        let state = admin_state();
+        {
+            let mut ring_buffer = state.ring_buffer.lock().unwrap();
+            for i in 0..10 {
+                ring_buffer.push(SampleBit { timestamp: 100 + i * 30, ok: true, ..Default::default() }, usize::MAX);
+            }
+        }
+        let (before, _) = generate_batch(&state, 400).unwrap();
+
+        let Json(body) = handle_set_threshold(
+            State(state.clone()),
+            bearer("s3cret"),
+            Json(ThresholdRequest { threshold_percent: 0.5 }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["threshold_percent"], 0.5);
+        assert_eq!(body["persisted"], false);
+        assert!(body["granularity_warning"].is_null());
+
+        let (after, _) = generate_batch(&state, 400).unwrap();
+        assert_eq!(after.threshold, 5);
+        // The batch records the threshold it was judged against, not the configured one
+        assert_eq!(after.threshold_percent, Some(0.5));
+        assert_eq!(before.threshold_percent, Some(state.config.proofs.threshold_percent));
+
+        let Json(body) = handle_set_threshold(State(state.clone()), bearer("s3cret"), Json(ThresholdRequest { threshold_percent: 0.999 }))
+            .await
+            .unwrap();
+        assert!(body["granularity_warning"].as_str().unwrap().contains("granularity"));
+        assert!(after.threshold < before.threshold);
+        // The configured value is untouched
+        assert_ne!(state.config.proofs.threshold_percent, 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_set_threshold_rejects_invalid_values() {
+        let state = admin_state();
+        for percent in [0.0, -0.1, 1.5, f64::NAN] {
+            let err = handle_set_threshold(State(state.clone()), bearer("s3cret"), Json(ThresholdRequest { threshold_percent: percent }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.0, StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        let err = handle_set_threshold(State(state.clone()), bearer("wrong"), Json(ThresholdRequest { threshold_percent: 0.5 }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::UNAUTHORIZED);
+        assert!(state.threshold_override.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_generate_batch_empty_ring_buffer() {
+        let err = handle_generate_batch(State(admin_state()), bearer("s3cret")).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+    }
+}
@@ -0,0 +1,89 @@
+use axum::{extract::{Query, State}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use crate::metrics::verify_batch_against_samples;
+use crate::types::{AppState, Batch};
+use super::namespaces::require_allowed_namespace;
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    /// Namespace the batch was posted to (default: `celestia.namespace`)
+    pub namespace: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchAudit {
+    /// Whether the recorded samples rebuild to the batch's `bitmap_hash`
+    pub matches: bool,
+    pub window_start: u64,
+    pub window_end: u64,
+    pub bitmap_hash: String,
+}
+
+/// POST /audit/batch: check a batch (e.g. decoded from a DA blob) against the recorded samples
+///
+/// 403 if the namespace isn't served by this reader (`server.allowed_namespaces`), 422 if
+/// no recorded sample falls inside the batch window.
+pub async fn handle_audit_batch(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+    Json(batch): Json<Batch>,
+) -> Result<Json<BatchAudit>, (StatusCode, String)> {
+    let namespace = query.namespace.as_deref().unwrap_or(&state.config.celestia.namespace);
+    require_allowed_namespace(namespace, &state.config)?;
+    let samples = state.samples.lock().unwrap().clone();
+    let matches = verify_batch_against_samples(&batch, &samples, &state.config)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    Ok(Json(BatchAudit {
+        matches,
+        window_start: batch.window.start,
+        window_end: batch.window.end,
+        bitmap_hash: batch.bitmap_hash,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+    use crate::config::Config;
+    use crate::types::Sample;
+
+    #[tokio::test]
+    async fn test_audit_batch_endpoint() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        state.samples.lock().unwrap().extend((0..3).map(|i| Sample { timestamp: 100 + i * 30, ok: true, ..Default::default() }));
+        let app = crate::api::router(state);
+
+        let batch = serde_json::json!({
+            "n": 3, "good": 3, "threshold": 3,
+            "bitmap_hash": blake3::hash(&[1, 1, 1]).to_hex().to_string(),
+            "window": { "start": 100, "end": 160 },
+        });
+        let request = Request::post("/audit/batch")
+            .header("content-type", "application/json")
+            .body(Body::from(batch.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let audit: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(audit["matches"], true);
+    }
+
+    #[tokio::test]
+    async fn test_audit_batch_rejects_foreign_namespace() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let app = crate::api::router(state);
+
+        let batch = serde_json::to_string(&Batch::sample()).unwrap();
+        let request = Request::post("/audit/batch?namespace=0xdeadbeef")
+            .header("content-type", "application/json")
+            .body(Body::from(batch))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,65 @@
+use axum::{extract::State, Json};
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::types::{AppState, DasMetrics};
+use crate::utils::is_stale;
+
+/// What the reader currently knows about the DAS node
+#[derive(Debug, Serialize)]
+pub struct DasState {
+    pub head: Option<i64>,
+    pub headers: Option<i64>,
+    pub last_update: Option<u64>,
+    /// Seconds since `last_update` (`None` if no metrics have arrived yet)
+    pub staleness_secs: Option<u64>,
+    pub max_staleness_secs: u64,
+    /// Same rule as the sampler: never-updated or older than `max_staleness_secs`
+    pub is_stale: bool,
+}
+
+/// GET /metrics/das: live head/headers and their freshness, for operators and dashboards
+pub async fn handle_das_state(State(state): State<AppState>) -> Json<DasState> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let metrics = state.das_metrics.lock().unwrap().clone();
+    Json(das_state(&metrics, now, state.config.sampling.max_staleness_secs))
+}
+
+fn das_state(metrics: &DasMetrics, now: u64, max_staleness_secs: u64) -> DasState {
+    DasState {
+        head: metrics.head,
+        headers: metrics.headers,
+        last_update: metrics.last_update,
+        staleness_secs: metrics.last_update.map(|u| now.saturating_sub(u)),
+        max_staleness_secs,
+        is_stale: is_stale(metrics.last_update, now, max_staleness_secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_das_state_reports_staleness() {
+        let metrics = DasMetrics { head: Some(8549697), headers: Some(8549700), last_update: Some(1000), ..Default::default() };
+
+        let fresh = das_state(&metrics, 1030, 120);
+        assert_eq!(fresh.staleness_secs, Some(30));
+        assert!(!fresh.is_stale);
+        assert_eq!(fresh.head, Some(8549697));
+
+        let stale = das_state(&metrics, 1121, 120);
+        assert_eq!(stale.staleness_secs, Some(121));
+        assert!(stale.is_stale);
+
+        let empty = das_state(&DasMetrics::default(), 1030, 120);
+        assert_eq!(empty.staleness_secs, None);
+        assert!(empty.is_stale);
+        let json = serde_json::to_value(&empty).unwrap();
+        assert_eq!(json["max_staleness_secs"], 120);
+        assert!(json["head"].is_null());
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicU64;
+use std::time::{SystemTime, UNIX_EPOCH};
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use crate::telemetry::Liveness;
+use crate::types::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubsystemHealth {
+    pub status: HealthStatus,
+    /// Unix seconds of the last progress, if any
+    pub last: Option<u64>,
+    /// Seconds since the last progress (or since startup if there was none)
+    pub age_secs: u64,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DetailedHealth {
+    /// Worst subsystem status
+    pub status: HealthStatus,
+    pub timestamp: u64,
+    pub subsystems: BTreeMap<&'static str, SubsystemHealth>,
+}
+
+/// GET /healthz/detailed: per-subsystem status, 503 if any subsystem failed
+pub async fn handle_detailed_health(State(state): State<AppState>) -> (StatusCode, Json<DetailedHealth>) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let health = detailed_health(&state, now);
+    let code = if health.status == HealthStatus::Failed {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (code, Json(health))
+}
+
+/// Judge each subsystem from its liveness timestamps
+///
+/// Periodic subsystems are ok within 2 periods of their last progress and failed after 5
+/// (ingestion: within `max_staleness_secs`, failed after 3x that).
+pub fn detailed_health(state: &AppState, now: u64) -> DetailedHealth {
+    let live = &state.liveness;
+    let config = &state.config;
+    let tick = config.sampling.tick_secs;
+    let window = config.batching.window_secs;
+    let staleness = config.sampling.max_staleness_secs;
+
+    let mut subsystems = BTreeMap::new();
+    subsystems.insert("ingestion", by_age(live, &live.last_ingest, now, staleness, 3 * staleness, "OTLP request"));
+    subsystems.insert("sampler", by_age(live, &live.last_tick, now, 2 * tick, 5 * tick, "tick"));
+    subsystems.insert("batch_generator", by_age(live, &live.last_batch, now, 2 * window, 5 * window, "batch"));
+
+    let da = if config.da_posting.enabled {
+        let mut da = by_age(live, &live.last_da_success, now, 2 * window, 5 * window, "successful DA post");
+        if let Some(failure) = Liveness::get(&live.last_da_failure).filter(|&f| da.last.is_none_or(|s| f > s)) {
+            da.status = da.status.max(HealthStatus::Degraded);
+            da.detail = format!("last DA post failed {}s ago", now.saturating_sub(failure));
+        }
+        da
+    } else {
+        SubsystemHealth {
+            status: HealthStatus::Ok,
+            last: None,
+            age_secs: 0,
+            detail: "disabled (da_posting.enabled = false)".to_string(),
+        }
+    };
+    subsystems.insert("da_posting", da);
+
+    let last_write = Liveness::get(&live.last_storage_write);
+    let storage = match Liveness::get(&live.last_storage_failure) {
+        Some(failure) if last_write.is_none_or(|w| failure > w) => SubsystemHealth {
+            status: HealthStatus::Failed,
+            last: last_write,
+            age_secs: now.saturating_sub(last_write.unwrap_or(live.started_at)),
+            detail: format!("last data/ write failed {}s ago", now.saturating_sub(failure)),
+        },
+        _ => SubsystemHealth {
+            status: HealthStatus::Ok,
+            last: last_write,
+            age_secs: now.saturating_sub(last_write.unwrap_or(live.started_at)),
+            detail: if last_write.is_some() { "last write ok" } else { "no writes yet" }.to_string(),
+        },
+    };
+    subsystems.insert("storage", storage);
+
+    DetailedHealth {
+        status: subsystems.values().map(|s| s.status).max().unwrap_or(HealthStatus::Ok),
+        timestamp: now,
+        subsystems,
+    }
+}
+
+/// Status from the age of `field`: ok within `ok_within` secs, failed beyond `failed_after`
+fn by_age(live: &Liveness, field: &AtomicU64, now: u64, ok_within: u64, failed_after: u64, what: &str) -> SubsystemHealth {
+    let last = Liveness::get(field);
+    let age_secs = now.saturating_sub(last.unwrap_or(live.started_at));
+    let status = if age_secs <= ok_within {
+        HealthStatus::Ok
+    } else if age_secs <= failed_after {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Failed
+    };
+    let detail = match last {
+        Some(_) => format!("last {} {}s ago", what, age_secs),
+        None => format!("no {} since startup {}s ago", what, age_secs),
+    };
+    SubsystemHealth { status, last, age_secs, detail }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering;
+    use crate::config::Config;
+
+    #[test]
+    fn test_subsystem_statuses_reflect_liveness() {
+        let mut config = Config::for_tests();
+        config.da_posting.enabled = true;
+        let state = AppState::new(Arc::new(config));
+        let live = &state.liveness;
+        let now = live.started_at + 10_000;
+
+        // Fresh ingest, sampler wedged for 3 ticks, last batch 6 windows ago
+        live.last_ingest.store(now - 5, Ordering::Relaxed);
+        live.last_tick.store(now - 90, Ordering::Relaxed);
+        live.last_batch.store(now - 6 * 600, Ordering::Relaxed);
+        // DA post failed after the last success; storage write failed after the last good one
+        live.last_da_success.store(now - 600, Ordering::Relaxed);
+        live.last_da_failure.store(now - 10, Ordering::Relaxed);
+        live.last_storage_write.store(now - 60, Ordering::Relaxed);
+        live.last_storage_failure.store(now - 30, Ordering::Relaxed);
+
+        let health = detailed_health(&state, now);
+        let status = |name| health.subsystems[name].status;
+        assert_eq!(status("ingestion"), HealthStatus::Ok);
+        assert_eq!(status("sampler"), HealthStatus::Degraded);
+        assert_eq!(status("batch_generator"), HealthStatus::Failed);
+        assert_eq!(status("da_posting"), HealthStatus::Degraded);
+        assert_eq!(status("storage"), HealthStatus::Failed);
+        assert_eq!(health.status, HealthStatus::Failed);
+        assert_eq!(health.subsystems["sampler"].age_secs, 90);
+
+        // Recovery: everything progressed just now
+        for field in [&live.last_tick, &live.last_batch, &live.last_da_success, &live.last_storage_write] {
+            field.store(now, Ordering::Relaxed);
+        }
+        let health = detailed_health(&state, now);
+        assert_eq!(health.status, HealthStatus::Ok);
+    }
+
+    #[test]
+    fn test_never_seen_subsystem_aged_from_startup() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let started = state.liveness.started_at;
+
+        let health = detailed_health(&state, started + 10);
+        assert_eq!(health.subsystems["ingestion"].status, HealthStatus::Ok);
+        assert_eq!(health.subsystems["ingestion"].last, None);
+        assert_eq!(health.subsystems["da_posting"].detail, "disabled (da_posting.enabled = false)");
+
+        let health = detailed_health(&state, started + 1000);
+        assert_eq!(health.subsystems["ingestion"].status, HealthStatus::Failed);
+    }
+}
@@ -0,0 +1,54 @@
+use axum::{http::{Method, StatusCode, Uri}, Json};
+use serde_json::{json, Value};
+
+/// Endpoints served by the reader, shown to clients that hit the wrong method or path
+const ENDPOINTS: &[(&str, &str)] = &[
+    ("POST /v1/metrics", "OTLP/HTTP metrics export (application/x-protobuf or application/json)"),
+    ("GET /metrics", "Reader self-metrics (Prometheus text format)"),
+    ("GET /metrics/das", "Current head, headers, last update and staleness of the DAS metrics (JSON)"),
+    ("GET /export/opentelemetry", "Derived health (window uptime, telemetry lag, head rate, sample counts) as OTLP; protobuf with Accept: application/x-protobuf, else JSON"),
+    ("GET /simulate", "Threshold calculator: ?n=20&good=19&threshold_percent=0.95"),
+    ("GET /reliability", "MTBF/MTTR computed from recorded samples"),
+    ("GET /identity", "Signing public key, reader_id, namespace and manifest"),
+    ("GET /uptime", "Uptime over ?from=&to= (unix secs), including downsampled history"),
+    ("GET /proof/{window_start}/{window_end}", "Exported proof (hex) and public inputs of that batch window"),
+    ("GET /accumulator/proof/{index}", "Inclusion proof of the index-th batch against the current accumulator root"),
+    ("POST /audit/batch", "Rebuild a batch's bitmap_hash from recorded samples and report whether it matches"),
+    ("GET /healthz/detailed", "Per-subsystem status (ingestion, sampler, batches, DA posting, storage); 503 if any failed"),
+    ("GET /samples", "Recorded samples: ?from=<unix secs>&label.network=mocha-4 (lookback capped by server.max_query_age_secs); page with ?limit=<n>&after=<next_cursor>"),
+    ("POST /admin/generate-batch", "Compute a batch now (requires admin bearer token)"),
+    ("GET /admin/snapshot", "Dump in-memory state as JSON (requires admin bearer token)"),
+    ("POST /admin/reload-key", "Re-read Celestia credentials from env and swap the DA signing key (requires admin bearer token)"),
+    ("POST /admin/threshold", "Change proofs.threshold_percent until restart (requires admin bearer token)"),
+];
+
+fn endpoint_list() -> Value {
+    ENDPOINTS
+        .iter()
+        .map(|(route, description)| json!({ "route": route, "description": description }))
+        .collect()
+}
+
+/// Any method other than POST on /v1/metrics: explain how to export instead of a bare 405
+pub async fn handle_metrics_method_not_allowed(method: Method) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        Json(json!({
+            "error": format!("{} is not supported on /v1/metrics", method),
+            "hint": "Point your OTLP/HTTP exporter here; metrics must be sent with POST \
+                     (Content-Type application/x-protobuf or application/json)",
+            "endpoints": endpoint_list(),
+        })),
+    )
+}
+
+/// Unknown paths: list the endpoints that do exist
+pub async fn handle_not_found(method: Method, uri: Uri) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": format!("No route for {} {}", method, uri.path()),
+            "endpoints": endpoint_list(),
+        })),
+    )
+}
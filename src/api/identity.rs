@@ -0,0 +1,57 @@
+use axum::{extract::State, Json};
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::da::{build_manifest_payload, DaManifestPayload};
+use crate::types::AppState;
+
+/// Who this reader is, for verifiers checking its blobs off-chain
+#[derive(Debug, Serialize)]
+pub struct Identity {
+    pub reader_id: String,
+    pub namespace: String,
+    /// Hex ed25519 public key of the current DA signing key (`None` if no key could be derived)
+    pub public_key: Option<String>,
+    pub key_type: &'static str,
+    /// The manifest this reader would post now (contains no secrets)
+    pub manifest: DaManifestPayload,
+}
+
+/// GET /identity: signing public key, reader_id, namespace and current manifest
+pub async fn handle_identity(State(state): State<AppState>) -> Json<Identity> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    Json(Identity {
+        reader_id: state.config.da_posting.reader_id.clone(),
+        namespace: state.config.celestia.namespace.clone(),
+        public_key: state.da_signer.public_key_hex(),
+        key_type: "ed25519",
+        manifest: build_manifest_payload(&state.config, now),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::config::Config;
+    use crate::crypto::{public_key_hex, signing_key_from_hex};
+
+    #[tokio::test]
+    async fn test_public_key_matches_configured_key() {
+        let config = Config::for_tests();
+        let private_key = config.celestia.private_key_hex.clone().unwrap();
+        let state = AppState::new(Arc::new(config));
+
+        let Json(identity) = handle_identity(State(state)).await;
+        let expected = public_key_hex(&signing_key_from_hex(&private_key).unwrap());
+        assert_eq!(identity.public_key, Some(expected));
+        assert_eq!(identity.reader_id, "test-reader");
+        assert_eq!(identity.manifest.reader_id, "test-reader");
+
+        let json = serde_json::to_string(&identity).unwrap();
+        assert!(!json.contains(&private_key));
+    }
+}
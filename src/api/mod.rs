@@ -0,0 +1,163 @@
+// HTTP routes
+//
+// - POST /v1/metrics: OTLP ingestion (other methods get a JSON hint instead of a bare 405);
+//   not served in `mode = "replica"`
+// - GET /metrics: the reader's own metrics in Prometheus text format
+// - GET /metrics/das: current head/headers, last update and staleness as JSON
+// - GET /export/opentelemetry: derived health (uptime, lag, head rate, sample counts) as OTLP
+// - GET /simulate: stateless threshold calculator for config planning
+// - GET /reliability: MTBF/MTTR from the recorded samples
+// - GET /samples: recorded samples, filterable by label (`metrics.sample_labels`)
+// - GET /uptime: ok/total over a range, from downsampled summaries and raw samples
+// - GET /identity: signing public key, reader_id, namespace and manifest for verifiers
+// - GET /proof/{window_start}/{window_end}: exported proof and public inputs of a batch window
+// - GET /accumulator/proof/{index}: inclusion proof of a batch against the accumulator root
+// - POST /audit/batch: check a batch's bitmap_hash against the recorded samples (namespace-guarded)
+// - GET /healthz/detailed: ok/degraded/failed per subsystem, from `AppState::liveness`
+// - POST /admin/*: operator actions (including a runtime threshold override), protected by
+//   `server.admin_token`
+// - anything else: JSON 404 listing the endpoints above
+//
+// Responses of the read endpoints (GET routes other than /admin) are gzipped for
+// clients sending `Accept-Encoding: gzip` (`server.compress_responses`).
+
+mod simulate;
+mod admin;
+mod help;
+mod namespaces;
+mod reliability;
+mod identity;
+mod samples;
+mod uptime;
+mod health;
+mod audit;
+mod proof;
+mod accumulator;
+mod das;
+mod otel_export;
+
+use axum::{routing::{get, post}, Router};
+use tower_http::compression::CompressionLayer;
+use crate::config::ReaderMode;
+use crate::otlp::handle_metrics;
+use crate::telemetry::handle_prometheus;
+use crate::types::AppState;
+
+pub use simulate::handle_simulate;
+pub use reliability::handle_reliability;
+pub use identity::handle_identity;
+pub use samples::handle_samples;
+pub use uptime::handle_uptime;
+pub use health::handle_detailed_health;
+pub use audit::handle_audit_batch;
+pub use proof::handle_proof;
+pub use accumulator::{handle_accumulator_proof, AccumulatorProofResponse};
+pub use das::handle_das_state;
+pub use otel_export::handle_otel_export;
+pub use admin::{handle_generate_batch, handle_reload_key, handle_set_threshold, handle_snapshot};
+use help::{handle_metrics_method_not_allowed, handle_not_found};
+
+/// Build the HTTP router for the reader service
+pub fn router(state: AppState) -> Router {
+    let reads = Router::new()
+        .route("/metrics", get(handle_prometheus))
+        .route("/metrics/das", get(handle_das_state))
+        .route("/export/opentelemetry", get(handle_otel_export))
+        .route("/simulate", get(handle_simulate))
+        .route("/reliability", get(handle_reliability))
+        .route("/identity", get(handle_identity))
+        .route("/samples", get(handle_samples))
+        .route("/uptime", get(handle_uptime))
+        .route("/healthz/detailed", get(handle_detailed_health))
+        .route("/proof/:window_start/:window_end", get(handle_proof))
+        .route("/accumulator/proof/:index", get(handle_accumulator_proof));
+    let reads = if state.config.server.compress_responses {
+        reads.layer(CompressionLayer::new().gzip(true))
+    } else {
+        reads
+    };
+
+    // A replica rebuilds its state from DA blobs and takes no OTLP
+    let ingest = Router::new();
+    let ingest = if state.config.mode == ReaderMode::Primary {
+        ingest.route(
+            "/v1/metrics",
+            post(handle_metrics).fallback(handle_metrics_method_not_allowed),
+        )
+    } else {
+        ingest
+    };
+
+    ingest
+        .merge(reads)
+        .route("/audit/batch", post(handle_audit_batch))
+        .route("/admin/generate-batch", post(handle_generate_batch))
+        .route("/admin/snapshot", get(handle_snapshot))
+        .route("/admin/reload-key", post(handle_reload_key))
+        .route("/admin/threshold", post(handle_set_threshold))
+        .fallback(handle_not_found)
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+    use crate::config::Config;
+
+    async fn call(method: &str, uri: &str) -> (StatusCode, serde_json::Value) {
+        let app = router(AppState::new(Arc::new(Config::for_tests())));
+        let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_get_metrics_path_explains_post() {
+        let (status, body) = call("GET", "/v1/metrics").await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert!(body["error"].as_str().unwrap().contains("GET"));
+        assert!(body["hint"].as_str().unwrap().contains("POST"));
+    }
+
+    async fn get_gzip(config: Config, uri: &str) -> axum::response::Response {
+        let app = router(AppState::new(Arc::new(config)));
+        let request = Request::get(uri).header("accept-encoding", "gzip").body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_read_endpoint_gzipped_when_accepted() {
+        use std::io::Read;
+
+        let response = get_gzip(Config::for_tests(), "/identity").await;
+        assert_eq!(response.headers()["content-encoding"], "gzip");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut json = String::new();
+        flate2::read::GzDecoder::new(&body[..]).read_to_string(&mut json).unwrap();
+        let identity: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(identity["reader_id"], "test-reader");
+
+        let mut config = Config::for_tests();
+        config.server.compress_responses = false;
+        let response = get_gzip(config, "/identity").await;
+        assert!(response.headers().get("content-encoding").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_path_lists_endpoints() {
+        let (status, body) = call("GET", "/nope").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "No route for GET /nope");
+        assert!(body["endpoints"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|e| e["route"] == "POST /v1/metrics"));
+    }
+}
@@ -0,0 +1,46 @@
+use axum::http::StatusCode;
+use crate::config::Config;
+
+/// Reject namespaces this reader won't process on public verification endpoints
+///
+/// Allowed are `server.allowed_namespaces`, or only `celestia.namespace` when that
+/// list is empty, so the reader isn't a free verifier for arbitrary data.
+pub fn require_allowed_namespace(namespace: &str, config: &Config) -> Result<(), (StatusCode, String)> {
+    let allowed = &config.server.allowed_namespaces;
+    let permitted = if allowed.is_empty() {
+        namespace == config.celestia.namespace
+    } else {
+        allowed.iter().any(|n| n == namespace)
+    };
+
+    if permitted {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, format!("Namespace {} is not served by this reader", namespace)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_configured_namespace_allowed_by_default() {
+        let config = Config::for_tests();
+        assert!(require_allowed_namespace(&config.celestia.namespace, &config).is_ok());
+
+        let (status, _) = require_allowed_namespace("0xdeadbeef", &config).unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_allowed_namespaces_list_replaces_default() {
+        let mut config = Config::for_tests();
+        config.server.allowed_namespaces = vec!["0xaaaa".to_string(), "0xbbbb".to_string()];
+        assert!(require_allowed_namespace("0xbbbb", &config).is_ok());
+        assert_eq!(
+            require_allowed_namespace(&config.celestia.namespace.clone(), &config).unwrap_err().0,
+            StatusCode::FORBIDDEN
+        );
+    }
+}
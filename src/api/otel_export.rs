@@ -0,0 +1,198 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, InstrumentationScope, KeyValue};
+use opentelemetry_proto::tonic::metrics::v1::{
+    metric::Data, number_data_point, AggregationTemporality, Gauge, Metric, NumberDataPoint, ResourceMetrics,
+    ScopeMetrics, Sum,
+};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use prost::Message;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::types::{AppState, Sample};
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+const PROTOBUF: &str = "application/x-protobuf";
+
+/// GET /export/opentelemetry: the reader's derived health as an OTLP metrics export
+///
+/// Protobuf for clients accepting `application/x-protobuf`, OTLP/JSON otherwise, so the
+/// payload can be replayed into the same OTel pipeline the reader ingests from.
+pub async fn handle_otel_export(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let request = export_request(&state, now);
+    let wants_protobuf = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains(PROTOBUF));
+    if wants_protobuf {
+        ([(header::CONTENT_TYPE, PROTOBUF)], request.encode_to_vec()).into_response()
+    } else {
+        Json(request).into_response()
+    }
+}
+
+/// Build the export: window uptime ratio, telemetry lag, head rate, current head and
+/// cumulative sample outcome counts, under a `service.name = da-reader` resource
+fn export_request(state: &AppState, now: u64) -> ExportMetricsServiceRequest {
+    let time = now * NANOS_PER_SEC;
+    let mut metrics = Vec::new();
+
+    let (good, total) = state.ring_buffer.lock().unwrap().counts();
+    if total > 0 {
+        metrics.push(gauge("da_reader.window.uptime_ratio", "1", "Share of ok samples in the current batch window",
+                           number_data_point::Value::AsDouble(good as f64 / total as f64), time));
+    }
+    let das = state.das_metrics.lock().unwrap().clone();
+    if let Some(last_update) = das.last_update {
+        metrics.push(gauge("da_reader.telemetry.lag", "s", "Seconds since DAS metrics were last received",
+                           number_data_point::Value::AsInt(now.saturating_sub(last_update) as i64), time));
+    }
+    if let Some(head) = das.head {
+        metrics.push(gauge("da_reader.head", "{block}", "DAS sampled chain head last received",
+                           number_data_point::Value::AsInt(head), time));
+    }
+    let since = now.saturating_sub(state.config.batching.window_secs);
+    if let Some(rate) = head_rate(&state.samples.lock().unwrap(), since) {
+        metrics.push(gauge("da_reader.head.rate", "{block}/s", "Head advance per second over the current batch window",
+                           number_data_point::Value::AsDouble(rate), time));
+    }
+
+    let (samples_total, samples_ok) = state.telemetry.sample_counts();
+    let start = state.liveness.started_at * NANOS_PER_SEC;
+    let outcome = |outcome: &str, count: u64| NumberDataPoint {
+        attributes: vec![string_attribute("outcome", outcome)],
+        start_time_unix_nano: start,
+        time_unix_nano: time,
+        value: Some(number_data_point::Value::AsInt(count as i64)),
+        ..Default::default()
+    };
+    metrics.push(Metric {
+        name: "da_reader.samples".to_string(),
+        description: "Samples recorded since startup, by outcome".to_string(),
+        unit: "{sample}".to_string(),
+        data: Some(Data::Sum(Sum {
+            data_points: vec![outcome("ok", samples_ok), outcome("fail", samples_total.saturating_sub(samples_ok))],
+            aggregation_temporality: AggregationTemporality::Cumulative as i32,
+            is_monotonic: true,
+        })),
+        ..Default::default()
+    });
+
+    ExportMetricsServiceRequest {
+        resource_metrics: vec![ResourceMetrics {
+            resource: Some(Resource {
+                attributes: vec![
+                    string_attribute("service.name", "da-reader"),
+                    string_attribute("service.instance.id", &state.config.da_posting.reader_id),
+                ],
+                ..Default::default()
+            }),
+            scope_metrics: vec![ScopeMetrics {
+                scope: Some(InstrumentationScope {
+                    name: "da-reader".to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    ..Default::default()
+                }),
+                metrics,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+/// Blocks per second between the first and last sample with a head after `since`
+fn head_rate(samples: &[Sample], since: u64) -> Option<f64> {
+    let mut with_head = samples
+        .iter()
+        .filter(|sample| sample.timestamp > since)
+        .filter_map(|sample| sample.head.map(|head| (sample.timestamp, head)));
+    let (first_at, first_head) = with_head.next()?;
+    let (last_at, last_head) = with_head.next_back()?;
+    (last_at > first_at).then(|| (last_head - first_head) as f64 / (last_at - first_at) as f64)
+}
+
+fn gauge(name: &str, unit: &str, description: &str, value: number_data_point::Value, time: u64) -> Metric {
+    Metric {
+        name: name.to_string(),
+        description: description.to_string(),
+        unit: unit.to_string(),
+        data: Some(Data::Gauge(Gauge {
+            data_points: vec![NumberDataPoint { time_unix_nano: time, value: Some(value), ..Default::default() }],
+        })),
+        ..Default::default()
+    }
+}
+
+fn string_attribute(key: &str, value: &str) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue { value: Some(any_value::Value::StringValue(value.to_string())) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+    use crate::config::Config;
+    use crate::otlp::normalize_metrics;
+    use crate::types::{MetricValue, SampleBit};
+
+    #[tokio::test]
+    async fn test_export_carries_derived_health() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        for (i, ok) in [true, true, true, false].into_iter().enumerate() {
+            let sample = Sample { timestamp: now - 90 + i as u64 * 30, ok, head: Some(1000 + i as i64 * 6), ..Default::default() };
+            state.ring_buffer.lock().unwrap().push(SampleBit::from(&sample), 20);
+            state.telemetry.record_sample(&sample);
+            state.samples.lock().unwrap().push(sample);
+        }
+        {
+            let mut das = state.das_metrics.lock().unwrap();
+            das.head = Some(1018);
+            das.last_update = Some(now - 12);
+        }
+
+        // Fetched as protobuf, and read back through the reader's own OTLP normalization
+        let app = crate::api::router(state.clone());
+        let request = Request::get("/export/opentelemetry").header("accept", PROTOBUF).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], PROTOBUF);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let decoded = ExportMetricsServiceRequest::decode(body).unwrap();
+        let metrics = normalize_metrics(decoded, 16);
+
+        let value = |name: &str, outcome: Option<&str>| {
+            metrics
+                .iter()
+                .find(|m| m.name == name && outcome.is_none_or(|o| m.attributes.get("outcome").map(String::as_str) == Some(o)))
+                .map(|m| (m.metric_type.as_str(), m.value.clone()))
+                .unwrap_or_else(|| panic!("{} missing", name))
+        };
+        assert!(matches!(value("da_reader.window.uptime_ratio", None), ("Gauge", MetricValue::Double(r)) if r == 0.75));
+        assert!(matches!(value("da_reader.telemetry.lag", None), ("Gauge", MetricValue::Int(12))));
+        assert!(matches!(value("da_reader.head", None), ("Gauge", MetricValue::Int(1018))));
+        // 18 blocks over 90s
+        assert!(matches!(value("da_reader.head.rate", None), ("Gauge", MetricValue::Double(r)) if (r - 0.2).abs() < 1e-9));
+        assert!(matches!(value("da_reader.samples", Some("ok")), ("Sum", MetricValue::Int(3))));
+        assert!(matches!(value("da_reader.samples", Some("fail")), ("Sum", MetricValue::Int(1))));
+        assert_eq!(metrics[0].resource_attributes["service.instance.id"], "test-reader");
+    }
+
+    #[test]
+    fn test_head_rate_needs_two_samples_in_window() {
+        let sample = |timestamp, head| Sample { timestamp, head, ..Default::default() };
+        assert_eq!(head_rate(&[sample(100, Some(10))], 0), None);
+        assert_eq!(head_rate(&[sample(50, Some(0)), sample(100, Some(10)), sample(110, None), sample(120, Some(14))], 60), Some(0.2));
+    }
+}
@@ -0,0 +1,61 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use serde::Serialize;
+use crate::storage::{load_proof, ProofPublicInputs};
+use crate::types::{AppState, TimeWindow};
+
+#[derive(Debug, Serialize)]
+pub struct ProofResponse {
+    pub public_inputs: ProofPublicInputs,
+    /// Proof bytes, hex-encoded
+    pub proof: String,
+}
+
+/// GET /proof/{window_start}/{window_end}: the exported proof of that batch window
+///
+/// 404 if no proof was exported for that window.
+pub async fn handle_proof(
+    State(state): State<AppState>,
+    Path((start, end)): Path<(u64, u64)>,
+) -> Result<Json<ProofResponse>, (StatusCode, String)> {
+    let dir = std::path::Path::new(&state.config.proofs.export_dir);
+    match load_proof(dir, &TimeWindow { start, end }) {
+        Ok(Some(stored)) => Ok(Json(ProofResponse {
+            public_inputs: stored.public_inputs,
+            proof: hex::encode(stored.proof),
+        })),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("No proof exported for window {}-{}", start, end))),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load proof: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+    use crate::config::Config;
+    use crate::storage::save_proof;
+    use crate::types::Batch;
+
+    #[tokio::test]
+    async fn test_generated_proof_is_served_by_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::for_tests();
+        config.proofs.export_dir = dir.path().to_string_lossy().into_owned();
+        let batch = Batch { good: 20, window: TimeWindow { start: 1000, end: 1600 }, ..Batch::sample() };
+        save_proof(dir.path(), &batch, &[0xde, 0xad], &config.storage).unwrap();
+        let app = crate::api::router(AppState::new(Arc::new(config)));
+
+        let response = app.clone().oneshot(Request::get("/proof/1000/1600").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["proof"], "dead");
+        assert_eq!(body["public_inputs"]["threshold"], 19);
+        assert_eq!(body["public_inputs"]["window"]["start"], 1000);
+
+        let response = app.oneshot(Request::get("/proof/1600/2200").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,9 @@
+use axum::{extract::State, Json};
+use crate::metrics::{compute_reliability, Reliability};
+use crate::types::AppState;
+
+/// GET /reliability: MTBF/MTTR over all recorded samples
+pub async fn handle_reliability(State(state): State<AppState>) -> Json<Reliability> {
+    let samples = state.samples.lock().unwrap();
+    Json(compute_reliability(&samples))
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use axum::{extract::{Query, State}, Json};
+use serde::{Deserialize, Serialize};
+use crate::types::{AppState, Sample};
+
+/// Query parameters naming a label, e.g. `?label.network=mocha-4`
+const LABEL_PREFIX: &str = "label.";
+/// Largest page a `limit` may ask for
+const MAX_PAGE_LIMIT: usize = 10_000;
+
+/// Body of GET /samples
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SamplesResponse {
+    pub samples: Vec<Sample>,
+    /// Set when `server.max_query_age_secs` hid samples the query asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Pass as `after` to fetch the next page; absent on the last page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<u64>,
+}
+
+/// GET /samples: recorded samples since `from` (unix seconds), filtered by
+/// `label.<name>=<value>` parameters
+///
+/// All label filters must match; other query parameters are ignored. `from` is clamped
+/// to `server.max_query_age_secs` ago. With `limit` (at most `MAX_PAGE_LIMIT`) the response
+/// is one page in timestamp order plus a `next_cursor`; pass it back as `after` (exclusive)
+/// for the next page.
+pub async fn handle_samples(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Json<SamplesResponse> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let requested_from = params.get("from").and_then(|f| f.parse().ok()).unwrap_or(0);
+    let from = clamp_from(requested_from, now, state.config.server.max_query_age_secs);
+    let after = params.get("after").and_then(|a| a.parse::<u64>().ok());
+    let limit = params.get("limit").and_then(|l| l.parse::<usize>().ok()).map(|l| l.clamp(1, MAX_PAGE_LIMIT));
+    let filters: Vec<(&str, &str)> = params
+        .iter()
+        .filter_map(|(key, value)| key.strip_prefix(LABEL_PREFIX).map(|label| (label, value.as_str())))
+        .collect();
+
+    let samples = state.samples.lock().unwrap();
+    let clamped = from > requested_from && samples.iter().any(|s| (requested_from..from).contains(&s.timestamp));
+    let note = clamped.then(|| {
+        format!(
+            "from clamped to {} (server.max_query_age_secs = {}); older samples omitted",
+            from, state.config.server.max_query_age_secs
+        )
+    });
+    let mut selected: Vec<&Sample> = samples
+        .iter()
+        .filter(|sample| sample.timestamp >= from && after.is_none_or(|after| sample.timestamp > after))
+        .filter(|sample| matches_labels(sample, &filters))
+        .collect();
+    let next_cursor = match limit {
+        Some(limit) => {
+            selected.sort_by_key(|sample| sample.timestamp);
+            paginate(&mut selected, limit)
+        }
+        None => None,
+    };
+    Json(SamplesResponse {
+        samples: selected.into_iter().cloned().collect(),
+        note,
+        next_cursor,
+    })
+}
+
+/// Cut a timestamp-sorted series down to one page of about `limit` samples, returning the
+/// cursor of the next page if anything was left out
+///
+/// Samples sharing the last timestamp all stay on the page, since the next page starts
+/// strictly after that timestamp.
+fn paginate(sorted: &mut Vec<&Sample>, limit: usize) -> Option<u64> {
+    if sorted.len() <= limit {
+        return None;
+    }
+    let last = sorted[limit - 1].timestamp;
+    let end = sorted.partition_point(|sample| sample.timestamp <= last);
+    sorted.truncate(end);
+    Some(last)
+}
+
+/// Earliest timestamp a query may reach: `from`, but no more than `max_age_secs` before `now`
+fn clamp_from(from: u64, now: u64, max_age_secs: u64) -> u64 {
+    from.max(now.saturating_sub(max_age_secs))
+}
+
+fn matches_labels(sample: &Sample, filters: &[(&str, &str)]) -> bool {
+    filters
+        .iter()
+        .all(|(label, value)| sample.labels.get(*label).is_some_and(|v| v == value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+    use crate::config::Config;
+
+    async fn get(state: &AppState, uri: String) -> SamplesResponse {
+        let app = crate::api::router(state.clone());
+        let response = app.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn timestamps(response: SamplesResponse) -> Vec<u64> {
+        response.samples.iter().map(|s| s.timestamp).collect()
+    }
+
+    #[tokio::test]
+    async fn test_samples_filtered_by_label() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        for (age, network) in [(90, "mocha-4"), (60, "celestia"), (30, "mocha-4")] {
+            let labels = [("network".to_string(), network.to_string())].into();
+            state.samples.lock().unwrap().push(Sample { timestamp: now - age, ok: true, labels, ..Default::default() });
+        }
+        state.samples.lock().unwrap().push(Sample { timestamp: now, ..Default::default() });
+
+        let mocha = timestamps(get(&state, "/samples?label.network=mocha-4".to_string()).await);
+        assert_eq!(mocha, [now - 90, now - 30]);
+        let none = timestamps(get(&state, "/samples?label.network=mocha-4&label.region=eu".to_string()).await);
+        assert!(none.is_empty());
+        assert_eq!(get(&state, "/samples".to_string()).await.samples.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_too_old_from_is_clamped() {
+        let mut config = Config::for_tests();
+        config.server.max_query_age_secs = 3600;
+        let state = AppState::new(Arc::new(config));
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        for age in [7200, 1800, 60] {
+            state.samples.lock().unwrap().push(Sample { timestamp: now - age, ..Default::default() });
+        }
+
+        let response = get(&state, format!("/samples?from={}", now - 10_000)).await;
+        assert!(response.note.as_deref().unwrap().contains("max_query_age_secs = 3600"));
+        assert_eq!(timestamps(response), [now - 1800, now - 60]);
+
+        // A `from` within the limit is honored without a note
+        let response = get(&state, format!("/samples?from={}", now - 600)).await;
+        assert!(response.note.is_none());
+        assert_eq!(timestamps(response), [now - 60]);
+    }
+
+    #[tokio::test]
+    async fn test_paging_covers_series_without_duplicates() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let series: Vec<u64> = (0..23).map(|i| now - 1000 + i * 30).collect();
+        {
+            let mut samples = state.samples.lock().unwrap();
+            // Recorded out of order (e.g. after a clock jump); pages still come sorted
+            for &timestamp in series.iter().rev() {
+                samples.push(Sample { timestamp, ..Default::default() });
+            }
+        }
+
+        let mut seen = Vec::new();
+        let mut pages = 0;
+        let mut cursor = format!("/samples?from={}&limit=5", now - 1000);
+        loop {
+            let page = get(&state, cursor).await;
+            pages += 1;
+            assert!(page.samples.len() <= 5);
+            let next = page.next_cursor;
+            seen.extend(timestamps(page));
+            match next {
+                Some(after) => cursor = format!("/samples?after={}&limit=5", after),
+                None => break,
+            }
+        }
+        assert_eq!(pages, 5);
+        assert_eq!(seen, series);
+
+        // Without a limit the whole series comes back, with no cursor
+        let all = get(&state, "/samples".to_string()).await;
+        assert!(all.next_cursor.is_none());
+        assert_eq!(all.samples.len(), 23);
+    }
+
+    #[test]
+    fn test_page_keeps_samples_sharing_last_timestamp() {
+        let samples: Vec<Sample> = [10, 20, 20, 30].iter().map(|&timestamp| Sample { timestamp, ..Default::default() }).collect();
+        let mut page: Vec<&Sample> = samples.iter().collect();
+        assert_eq!(paginate(&mut page, 2), Some(20));
+        assert_eq!(page.iter().map(|s| s.timestamp).collect::<Vec<_>>(), [10, 20, 20]);
+    }
+
+    #[test]
+    fn test_clamp_from() {
+        assert_eq!(clamp_from(0, 10_000, 3600), 6400);
+        assert_eq!(clamp_from(9000, 10_000, 3600), 9000);
+        assert_eq!(clamp_from(0, 100, 3600), 0);
+    }
+}
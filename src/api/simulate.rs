@@ -0,0 +1,90 @@
+use axum::{extract::Query, http::StatusCode, Json};
+use serde::{Serialize, Deserialize};
+use crate::metrics::threshold_for;
+
+/// Query parameters for GET /simulate
+#[derive(Debug, Deserialize)]
+pub struct SimulateParams {
+    pub n: usize,
+    pub good: usize,
+    pub threshold_percent: f64,
+}
+
+/// Outcome of a hypothetical batch window
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SimulateResult {
+    pub n: usize,
+    pub good: usize,
+    pub threshold_percent: f64,
+    pub threshold: usize,
+    pub uptime_percent: f64,
+    pub meets_threshold: bool,
+}
+
+/// Compute whether a window with `good` of `n` ok samples would pass
+pub fn simulate_threshold(n: usize, good: usize, threshold_percent: f64) -> anyhow::Result<SimulateResult> {
+    if n == 0 {
+        anyhow::bail!("n must be greater than 0");
+    }
+    if good > n {
+        anyhow::bail!("good ({}) cannot exceed n ({})", good, n);
+    }
+    if !(threshold_percent > 0.0 && threshold_percent <= 1.0) {
+        anyhow::bail!("threshold_percent must be in (0, 1], got {}", threshold_percent);
+    }
+
+    let threshold = threshold_for(n, threshold_percent);
+    Ok(SimulateResult {
+        n,
+        good,
+        threshold_percent,
+        threshold,
+        uptime_percent: (good as f64 / n as f64) * 100.0,
+        meets_threshold: good >= threshold,
+    })
+}
+
+/// GET /simulate?n=..&good=..&threshold_percent=..
+pub async fn handle_simulate(
+    Query(params): Query<SimulateParams>,
+) -> Result<Json<SimulateResult>, (StatusCode, String)> {
+    simulate_threshold(params.n, params.good, params.threshold_percent)
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate_exactly_at_threshold() {
+        // ceil(20 * 0.95) = 19
+        let result = simulate_threshold(20, 19, 0.95).unwrap();
+        assert_eq!(result.threshold, 19);
+        assert!(result.meets_threshold);
+    }
+
+    #[test]
+    fn test_simulate_one_below_threshold() {
+        let result = simulate_threshold(20, 18, 0.95).unwrap();
+        assert_eq!(result.threshold, 19);
+        assert!(!result.meets_threshold);
+    }
+
+    #[test]
+    fn test_simulate_rounds_threshold_up() {
+        // ceil(21 * 0.95) = ceil(19.95) = 20
+        let result = simulate_threshold(21, 19, 0.95).unwrap();
+        assert_eq!(result.threshold, 20);
+        assert!(!result.meets_threshold);
+    }
+
+    #[test]
+    fn test_simulate_rejects_invalid_input() {
+        assert!(simulate_threshold(0, 0, 0.95).is_err());
+        assert!(simulate_threshold(10, 11, 0.95).is_err());
+        assert!(simulate_threshold(10, 5, 0.0).is_err());
+        assert!(simulate_threshold(10, 5, 1.5).is_err());
+    }
+}
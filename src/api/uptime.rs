@@ -0,0 +1,93 @@
+use axum::{extract::{Query, State}, Json};
+use serde::{Deserialize, Serialize};
+use crate::types::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct UptimeQuery {
+    /// Unix seconds, inclusive (default: everything recorded)
+    pub from: Option<u64>,
+    /// Unix seconds, exclusive (default: now)
+    pub to: Option<u64>,
+}
+
+/// Uptime over a time range
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Uptime {
+    pub ok: usize,
+    pub total: usize,
+    /// `ok / total`; absent when nothing was recorded in the range
+    pub uptime: Option<f64>,
+    /// Downsampled buckets counted (see `storage.downsample_after_secs`)
+    pub summarized_buckets: usize,
+    /// Raw samples counted
+    pub raw_samples: usize,
+}
+
+/// GET /uptime: share of ok samples in `[from, to)`
+///
+/// Old ranges are read from the downsampled summaries, recent ones from raw samples.
+/// A summary bucket counts when it starts inside the range. Warmup samples are excluded.
+pub async fn handle_uptime(State(state): State<AppState>, Query(query): Query<UptimeQuery>) -> Json<Uptime> {
+    let from = query.from.unwrap_or(0);
+    let to = query.to.unwrap_or(u64::MAX);
+    let in_range = |t: u64| t >= from && t < to;
+
+    let (mut ok, mut total, mut summarized_buckets, mut raw_samples) = (0, 0, 0, 0);
+    for bucket in state.sample_summaries.lock().unwrap().values().filter(|b| in_range(b.start)) {
+        ok += bucket.ok;
+        total += bucket.total;
+        summarized_buckets += 1;
+    }
+    for sample in state.samples.lock().unwrap().iter().filter(|s| !s.warmup && in_range(s.timestamp)) {
+        ok += usize::from(sample.ok);
+        total += 1;
+        raw_samples += 1;
+    }
+
+    Json(Uptime {
+        ok,
+        total,
+        uptime: (total > 0).then(|| ok as f64 / total as f64),
+        summarized_buckets,
+        raw_samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+    use crate::config::Config;
+    use crate::types::{Sample, SampleSummary};
+
+    #[tokio::test]
+    async fn test_uptime_combines_summaries_and_raw_samples() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        state.sample_summaries.lock().unwrap().insert(0, SampleSummary { start: 0, secs: 3600, ok: 90, total: 120 });
+        for i in 0..10 {
+            state.samples.lock().unwrap().push(Sample { timestamp: 3600 + i * 30, ok: i != 0, ..Default::default() });
+        }
+
+        let get = |uri: &'static str| {
+            let app = crate::api::router(state.clone());
+            async move {
+                let response = app.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<Uptime>(&body).unwrap()
+            }
+        };
+
+        let all = get("/uptime").await;
+        assert_eq!((all.ok, all.total, all.summarized_buckets, all.raw_samples), (99, 130, 1, 10));
+
+        let recent = get("/uptime?from=3600").await;
+        assert_eq!((recent.ok, recent.total, recent.summarized_buckets), (9, 10, 0));
+        assert_eq!(recent.uptime, Some(0.9));
+
+        let empty = get("/uptime?from=100000").await;
+        assert_eq!(empty.uptime, None);
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt::Write;
+use crate::types::Sample;
+use crate::utils::is_valid_label_name;
+
+/// Series name of the exported sample verdicts
+const METRIC: &str = "da_reader_sample_ok";
+
+/// The samples to export: those between `from` and `to` (inclusive), oldest first
+///
+/// Warmup samples are skipped since they never count toward uptime.
+pub fn select_backfill(samples: &[Sample], from: Option<u64>, to: Option<u64>) -> Vec<&Sample> {
+    let mut in_range: Vec<&Sample> = samples
+        .iter()
+        .filter(|s| !s.warmup)
+        .filter(|s| from.is_none_or(|f| s.timestamp >= f) && to.is_none_or(|t| s.timestamp <= t))
+        .collect();
+    // Backfill rejects out-of-order samples within a series
+    in_range.sort_by_key(|s| s.timestamp);
+    in_range
+}
+
+/// Render samples picked by `select_backfill` as OpenMetrics text for
+/// `promtool tsdb create-blocks-from openmetrics`
+///
+/// One `da_reader_sample_ok{<sample labels>} <0|1> <unix secs>` line per sample, terminated
+/// by `# EOF`. The reason isn't a label: it changes from tick to tick, and as a label it
+/// would split the verdicts into one short series per reason. Labels whose name isn't a
+/// valid label name (recorded before `metrics.sample_labels` was validated) are dropped.
+pub fn render_backfill(samples: &[&Sample]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# HELP {} Whether the reader judged DA sampling healthy (1) or not (0)", METRIC);
+    let _ = writeln!(out, "# TYPE {} gauge", METRIC);
+    for sample in samples {
+        let labels: Vec<String> = sample
+            .labels
+            .iter()
+            .filter(|(name, _)| is_valid_label_name(name))
+            .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+            .collect();
+        let series = if labels.is_empty() { METRIC.to_string() } else { format!("{}{{{}}}", METRIC, labels.join(",")) };
+        let _ = writeln!(out, "{} {} {}", series, sample.ok as u8, sample.timestamp);
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Escape a label value per the OpenMetrics text format
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SampleReason;
+
+    /// Minimal OpenMetrics check: metadata lines, then `name{labels} value timestamp`
+    /// samples with well-formed labels, and a final `# EOF`
+    fn assert_valid_openmetrics(text: &str) {
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.last(), Some(&"# EOF"));
+        for line in &lines[..lines.len() - 1] {
+            if line.starts_with("# HELP ") || line.starts_with("# TYPE ") {
+                continue;
+            }
+            let (series, rest) = match line.split_once("} ") {
+                Some((series, rest)) => (series, rest),
+                None => line.split_once(' ').unwrap(),
+            };
+            let (name, labels) = series.split_once('{').unwrap_or((series, ""));
+            assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'), "{}", line);
+            for pair in labels.split("\",").filter(|pair| !pair.is_empty()) {
+                let (key, value) = pair.split_once("=\"").unwrap_or_else(|| panic!("bad label in {}", line));
+                assert!(is_valid_label_name(key), "{}", line);
+                assert!(!value.trim_end_matches('"').contains('\n'));
+            }
+            let parts: Vec<&str> = rest.split(' ').collect();
+            assert_eq!(parts.len(), 2, "{}", line);
+            parts[0].parse::<f64>().unwrap();
+            parts[1].parse::<u64>().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_backfill_lines_are_valid_openmetrics() {
+        let mut labelled = Sample { timestamp: 1060, ok: false, reason_code: SampleReason::StuckHead, ..Default::default() };
+        labelled.labels.insert("network".to_string(), "mocha \"4\"".to_string());
+        labelled.labels.insert("node.id".to_string(), "dropped".to_string());
+        let samples = vec![
+            Sample { timestamp: 1030, ok: true, reason_code: SampleReason::Advancing, ..Default::default() },
+            labelled,
+            Sample { timestamp: 1000, ok: true, warmup: true, ..Default::default() },
+            Sample { timestamp: 1090, ok: true, reason_code: SampleReason::Advancing, ..Default::default() },
+        ];
+
+        let selected = select_backfill(&samples, None, Some(1060));
+        let text = render_backfill(&selected);
+        assert_valid_openmetrics(&text);
+        // No reason label, so a sample's series doesn't change with its reason
+        assert!(text.contains("\nda_reader_sample_ok 1 1030\n"));
+        assert!(text.contains("\nda_reader_sample_ok{network=\"mocha \\\"4\\\"\"} 0 1060\n"));
+        // Warmup and out-of-range samples are left out
+        assert_eq!(selected.iter().map(|s| s.timestamp).collect::<Vec<_>>(), [1030, 1060]);
+        assert_eq!(text.lines().filter(|l| !l.starts_with('#')).count(), 2);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::types::Sample;
+use crate::utils::format_timestamp;
+
+/// Default number of columns in the chart
+pub const DEFAULT_WIDTH: usize = 60;
+
+/// Block characters from 0% ok (lowest) to 100% ok (full block)
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Render an ASCII block chart of ok/failed samples over time
+///
+/// Samples are bucketed by timestamp into `width` columns between `from` and `to`
+/// (defaulting to the first/last sample). Each column shows the share of ok samples
+/// in its bucket; columns without samples are left blank.
+pub fn render_chart(samples: &[Sample], from: Option<u64>, to: Option<u64>, width: usize) -> String {
+    let start = from.or_else(|| samples.iter().map(|s| s.timestamp).min());
+    let end = to.or_else(|| samples.iter().map(|s| s.timestamp).max());
+
+    let (start, end) = match (start, end) {
+        (Some(start), Some(end)) if start <= end => (start, end),
+        _ => return "No samples to chart\n".to_string(),
+    };
+
+    let in_range: Vec<&Sample> = samples
+        .iter()
+        .filter(|s| s.timestamp >= start && s.timestamp <= end)
+        .collect();
+
+    if in_range.is_empty() {
+        return format!(
+            "No samples between {} and {}\n",
+            format_timestamp(start),
+            format_timestamp(end)
+        );
+    }
+
+    // Never use more columns than there are distinct seconds in the range
+    let span = end - start + 1;
+    let width = width.max(1).min(span as usize);
+
+    let mut ok_counts = vec![0usize; width];
+    let mut totals = vec![0usize; width];
+    for sample in &in_range {
+        let col = ((sample.timestamp - start) as u128 * width as u128 / span as u128) as usize;
+        totals[col] += 1;
+        if sample.ok {
+            ok_counts[col] += 1;
+        }
+    }
+
+    let bars: String = ok_counts
+        .iter()
+        .zip(totals.iter())
+        .map(|(&ok, &total)| {
+            (ok * (LEVELS.len() - 1) + total / 2)
+                .checked_div(total)
+                .map_or(' ', |idx| LEVELS[idx])
+        })
+        .collect();
+
+    let good = in_range.iter().filter(|s| s.ok).count();
+    let n = in_range.len();
+    let uptime_percent = (good as f64 / n as f64) * 100.0;
+
+    let mut out = String::new();
+    out.push_str(&format!(
+        "📈 Uptime chart: {} → {}\n",
+        format_timestamp(start),
+        format_timestamp(end)
+    ));
+    out.push_str(&format!("   |{}|\n", bars));
+    out.push_str(&format!(
+        "   Samples: {} | OK: {} | Failed: {} | Uptime: {:.2}%\n",
+        n,
+        good,
+        n - good,
+        uptime_percent
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64, ok: bool) -> Sample {
+        Sample {
+            timestamp,
+            head: Some(1),
+            headers: Some(1),
+            ok,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_chart_known_series() {
+        let samples = vec![
+            sample(0, true),
+            sample(1, true),
+            sample(2, false),
+            sample(3, true),
+        ];
+
+        let chart = render_chart(&samples, None, None, 4);
+
+        assert!(chart.contains("|██▁█|"));
+        assert!(chart.contains("Samples: 4 | OK: 3 | Failed: 1 | Uptime: 75.00%"));
+        assert!(chart.contains(&format_timestamp(0)));
+        assert!(chart.contains(&format_timestamp(3)));
+    }
+
+    #[test]
+    fn test_render_chart_buckets_and_range() {
+        let samples = vec![
+            sample(0, true),
+            sample(1, false),
+            sample(2, true),
+            sample(3, true),
+            sample(10, false),
+        ];
+
+        // Two columns over [0, 3]: half ok, then fully ok; sample at 10 is out of range
+        let chart = render_chart(&samples, Some(0), Some(3), 2);
+        assert!(chart.contains("|▅█|"));
+        assert!(chart.contains("Samples: 4"));
+
+        assert_eq!(render_chart(&[], None, None, 10), "No samples to chart\n");
+    }
+}
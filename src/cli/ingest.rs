@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use crate::config::Config;
+use crate::otlp::ingest_payload;
+use crate::types::{AppState, DasMetrics, NormalizedMetric};
+
+/// Payload encoding for ingest-stdin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadFormat {
+    Json,
+    Protobuf,
+}
+
+impl PayloadFormat {
+    /// Guess the format from the payload: OTLP/JSON starts with `{`, anything else is protobuf
+    pub fn detect(payload: &[u8]) -> Self {
+        match payload.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{') => PayloadFormat::Json,
+            _ => PayloadFormat::Protobuf,
+        }
+    }
+}
+
+/// Run a captured payload through normalization and DAS extraction against fresh state
+pub fn ingest_captured(
+    config: Config,
+    payload: &[u8],
+    format: PayloadFormat,
+) -> anyhow::Result<(DasMetrics, Vec<NormalizedMetric>)> {
+    let state = AppState::new(Arc::new(config));
+    let outcome = ingest_payload(&state, payload, format == PayloadFormat::Json);
+
+    let normalized = outcome
+        .normalized
+        .ok_or_else(|| anyhow::anyhow!("Payload could not be decoded as OTLP {:?}", format))?;
+    let das_metrics = state.das_metrics.lock().unwrap().clone();
+    Ok((das_metrics, normalized))
+}
+
+/// Print the outcome of ingest-stdin
+pub fn print_ingest_result(das_metrics: &DasMetrics, normalized: &[NormalizedMetric]) -> anyhow::Result<()> {
+    println!("📥 Normalized metrics ({}):", normalized.len());
+    println!("{}", serde_json::to_string_pretty(normalized)?);
+    println!("\n📊 DAS metrics:");
+    println!("   Head:        {:?}", das_metrics.head);
+    println!("   Headers:     {:?}", das_metrics.headers);
+    println!("   Last update: {:?}", das_metrics.last_update);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ingest_captured_json_payload() {
+        let payload = br#"
+            {"resourceMetrics":[{"scopeMetrics":[{"metrics":[
+                {"name":"das_sampled_chain_head","gauge":{"dataPoints":[{"asInt":1234}]}},
+                {"name":"das_total_sampled_headers","sum":{"dataPoints":[{"asInt":567}]}},
+                {"name":"unrelated","gauge":{"dataPoints":[{"asDouble":1.5}]}}
+            ]}]}]}"#;
+
+        assert_eq!(PayloadFormat::detect(payload), PayloadFormat::Json);
+        let (das, normalized) = ingest_captured(Config::for_tests(), payload, PayloadFormat::Json).unwrap();
+
+        assert_eq!(das.head, Some(1234));
+        assert_eq!(das.headers, Some(567));
+        assert_eq!(normalized.len(), 3);
+    }
+
+    #[test]
+    fn test_ingest_captured_rejects_garbage() {
+        let payload = b"\xff\xff\xff";
+        assert_eq!(PayloadFormat::detect(payload), PayloadFormat::Protobuf);
+        assert!(ingest_captured(Config::for_tests(), payload, PayloadFormat::Protobuf).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+// Command-line subcommands
+//
+// Running the binary without arguments starts the reader service.
+// Any other first argument is treated as a subcommand:
+// - chart: render an ASCII uptime chart from data/samples.json
+// - backfill: print data/samples.json as OpenMetrics for promtool tsdb backfilling
+// - ingest-stdin: run a captured OTLP payload through normalization/extraction
+// - decode-blob: parse a DA blob (framed or legacy JSON) from stdin
+// - verify-chain: check signatures, links and gaps of a list of sample payloads
+// - verify-accumulator-proof: check a GET /accumulator/proof response against a posted root
+
+mod backfill;
+mod chart;
+mod ingest;
+
+use std::io::Read;
+use std::path::Path;
+use crate::config::Config;
+use crate::storage::{load_samples_from, SAMPLES_PATH};
+use crate::utils::format_timestamp;
+use ingest::PayloadFormat;
+
+/// Run a subcommand by name
+pub fn run(command: &str, args: &[String]) -> anyhow::Result<()> {
+    match command {
+        "chart" => run_chart(args),
+        "backfill" => run_backfill(args),
+        "ingest-stdin" => run_ingest_stdin(args),
+        "decode-blob" => run_decode_blob(),
+        "verify-chain" => run_verify_chain(args),
+        "verify-accumulator-proof" => run_verify_accumulator_proof(args),
+        "help" | "--help" | "-h" => {
+            print_usage();
+            Ok(())
+        }
+        other => {
+            print_usage();
+            anyhow::bail!("Unknown command: {}", other)
+        }
+    }
+}
+
+fn print_usage() {
+    println!("Usage: da-reader [COMMAND]");
+    println!();
+    println!("Without a command, starts the OTLP reader service.");
+    println!();
+    println!("Commands:");
+    println!("  chart [--from <unix_ts>] [--to <unix_ts>] [--width <cols>] [--file <path>]");
+    println!("        Render an ASCII uptime chart from recorded samples");
+    println!("  backfill [--from <unix_ts>] [--to <unix_ts>] [--file <path>]");
+    println!("        Print recorded samples as OpenMetrics for `promtool tsdb create-blocks-from openmetrics`");
+    println!("  ingest-stdin [--json | --protobuf] [--config <path>]");
+    println!("        Decode an OTLP payload from stdin and print the extracted DAS metrics");
+    println!("  decode-blob");
+    println!("        Parse a DA blob from stdin and print its payload as JSON");
+    println!("  verify-chain --public-key <hex>");
+    println!("        Verify a JSON array of sample payloads (stdin) as a signed sample chain");
+    println!("  verify-accumulator-proof --root <hex>");
+    println!("        Verify a GET /accumulator/proof response (stdin) against a batch's accumulator_root");
+}
+
+fn run_verify_accumulator_proof(args: &[String]) -> anyhow::Result<()> {
+    let root = flag_value(args, "--root")
+        .ok_or_else(|| anyhow::anyhow!("--root is required (a posted batch's accumulator_root)"))?;
+    let mut input = Vec::new();
+    std::io::stdin().read_to_end(&mut input)?;
+    let response: crate::api::AccumulatorProofResponse = serde_json::from_slice(&input)?;
+
+    let leaf = &response.leaf;
+    if !response.proof.verify(leaf, root) {
+        anyhow::bail!("proof of batch {} does not match root {}", response.proof.index, root);
+    }
+    println!("✅ Window {}-{} (bitmap_hash {}) is batch {} of {} under root {}",
+             leaf.window.start, leaf.window.end, leaf.bitmap_hash, response.proof.index, response.proof.leaf_count, root);
+    Ok(())
+}
+
+fn run_verify_chain(args: &[String]) -> anyhow::Result<()> {
+    let public_key = flag_value(args, "--public-key")
+        .ok_or_else(|| anyhow::anyhow!("--public-key is required (see GET /identity)"))?;
+    let mut input = Vec::new();
+    std::io::stdin().read_to_end(&mut input)?;
+    let samples: Vec<_> = serde_json::from_slice(&input)?;
+
+    let result = crate::da::verify_sample_chain(&samples, public_key)?;
+    println!("✅ {} samples verified", result.verified);
+    for gap in &result.gaps {
+        println!("⚠️  Gap: samples {}..{} missing", gap.after + 1, gap.next - 1);
+    }
+    Ok(())
+}
+
+fn run_decode_blob() -> anyhow::Result<()> {
+    let mut blob = Vec::new();
+    std::io::stdin().read_to_end(&mut blob)?;
+    let decoded = crate::da::decode_blob(&blob)?;
+    println!("{}", serde_json::to_string_pretty(&decoded)?);
+    Ok(())
+}
+
+fn run_chart(args: &[String]) -> anyhow::Result<()> {
+    let from = parse_flag::<u64>(args, "--from")?;
+    let to = parse_flag::<u64>(args, "--to")?;
+    let width = parse_flag::<usize>(args, "--width")?.unwrap_or(chart::DEFAULT_WIDTH);
+    let file = flag_value(args, "--file").unwrap_or(SAMPLES_PATH);
+
+    let samples = load_samples_from(Path::new(file))?;
+    print!("{}", chart::render_chart(&samples, from, to, width));
+    Ok(())
+}
+
+fn run_backfill(args: &[String]) -> anyhow::Result<()> {
+    let from = parse_flag::<u64>(args, "--from")?;
+    let to = parse_flag::<u64>(args, "--to")?;
+    let file = flag_value(args, "--file").unwrap_or(SAMPLES_PATH);
+
+    let samples = load_samples_from(Path::new(file))?;
+    let selected = backfill::select_backfill(&samples, from, to);
+    print!("{}", backfill::render_backfill(&selected));
+    // Progress goes to stderr so stdout stays a clean OpenMetrics file
+    match (selected.first(), selected.last()) {
+        (Some(first), Some(last)) => eprintln!("📈 Exported {} samples from {} to {}",
+                                               selected.len(), format_timestamp(first.timestamp), format_timestamp(last.timestamp)),
+        _ => eprintln!("📈 No samples in range, exported nothing"),
+    }
+    Ok(())
+}
+
+fn run_ingest_stdin(args: &[String]) -> anyhow::Result<()> {
+    let config_path = flag_value(args, "--config").unwrap_or("config.toml");
+    let config = Config::from_file(config_path)?;
+
+    let mut payload = Vec::new();
+    std::io::stdin().read_to_end(&mut payload)?;
+
+    let format = if args.iter().any(|a| a == "--json") {
+        PayloadFormat::Json
+    } else if args.iter().any(|a| a == "--protobuf") {
+        PayloadFormat::Protobuf
+    } else {
+        PayloadFormat::detect(&payload)
+    };
+
+    let (das_metrics, normalized) = ingest::ingest_captured(config, &payload, format)?;
+    ingest::print_ingest_result(&das_metrics, &normalized)
+}
+
+/// Find the value following a `--flag` argument
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|s| s.as_str())
+}
+
+fn parse_flag<T: std::str::FromStr>(args: &[String], flag: &str) -> anyhow::Result<Option<T>> {
+    match flag_value(args, flag) {
+        Some(v) => v
+            .parse::<T>()
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("Invalid value for {}: {}", flag, v)),
+        None => Ok(None),
+    }
+}
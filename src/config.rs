@@ -1,61 +1,753 @@
-use serde::Deserialize;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::env;
+use crate::proofs::HashAlgo;
+use crate::utils::is_valid_label_name;
+use crate::types::SampleReason;
+use crate::da::{namespace_from_hex, namespace_from_reader_id, namespace_to_hex, AUTO_NAMESPACE};
 
 /// Configuration loaded from config.toml
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Reject unknown keys (e.g. a typo like `threshhold_percent`) instead of warning
+    #[serde(default)]
+    pub strict: bool,
+    /// "primary" ingests OTLP and posts to DA; "replica" rebuilds state from a primary's DA blobs
+    #[serde(default)]
+    pub mode: ReaderMode,
     pub sampling: SamplingConfig,
     pub metrics: MetricsConfig,
     pub da_posting: DaPostingConfig,
     pub batching: BatchingConfig,
     pub celestia: CelestiaConfig,
     pub proofs: ProofsConfig,
+    #[serde(default)]
+    pub debug: DebugConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub secondary_check: SecondaryCheckConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub replica: ReplicaConfig,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReaderMode {
+    #[default]
+    Primary,
+    Replica,
+}
+
+/// Where a replica (`mode = "replica"`) reads the primary's blobs from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaConfig {
+    /// Only follow blobs stamped with this reader_id (empty = every reader in the namespace)
+    #[serde(default)]
+    pub source_reader_id: String,
+    /// The followed reader's ed25519 public key as hex (its `GET /identity`); blobs that
+    /// aren't signed with it are rejected
+    #[serde(default)]
+    pub source_public_key: String,
+    /// First DA height to read
+    #[serde(default = "default_replica_start_height")]
+    pub start_height: u64,
+    /// Seconds between checks for new DA heights
+    #[serde(default = "default_replica_poll_secs")]
+    pub poll_secs: u64,
+}
+
+fn default_replica_start_height() -> u64 {
+    1
+}
+
+fn default_replica_poll_secs() -> u64 {
+    12
+}
+
+impl Default for ReplicaConfig {
+    fn default() -> Self {
+        Self {
+            source_reader_id: String::new(),
+            source_public_key: String::new(),
+            start_height: default_replica_start_height(),
+            poll_secs: default_replica_poll_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SamplingConfig {
     pub tick_secs: u64,
     pub max_staleness_secs: u64,
     pub grace_period_secs: u64,
+    /// How ticks with no DAS metrics at all count toward batch uptime
+    #[serde(default)]
+    pub no_data_policy: NoDataPolicy,
+    /// Seconds after the first DAS data arrives during which samples are excluded from batches
+    #[serde(default)]
+    pub warmup_secs: u64,
+    /// Whether data exactly `grace_period_secs` old still counts as within the grace period
+    #[serde(default)]
+    pub grace_boundary: GraceBoundary,
+    /// Delay the first tick so ticks land on multiples of `tick_secs` since the Unix epoch
+    #[serde(default)]
+    pub align_to_wallclock: bool,
+    /// Count a stuck head as ok while headers keep advancing (sampling still progressing)
+    #[serde(default)]
+    pub head_stuck_headers_advancing_ok: bool,
+    /// Skip (and log) any sample whose timestamp isn't after the previous one, e.g. after the
+    /// clock went backward, so the recorded series stays strictly increasing
+    #[serde(default)]
+    pub enforce_monotonic: bool,
+    /// Within this many seconds of a fail→ok recovery, keep a single bad tick ok (0 = off)
+    #[serde(default)]
+    pub recovery_cooldown_secs: u64,
+    /// What the sampler and batch tickers do after falling behind (e.g. a stalled runtime)
+    #[serde(default)]
+    pub missed_ticks: MissedTicks,
+}
+
+/// Catch-up policy of the sampler and batch tickers when ticks were missed
+///
+/// Neither fires the missed ticks back to back (tokio's default burst), which would record
+/// samples bunched at almost the same timestamp.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissedTicks {
+    /// Fire once now, then resume on the original schedule
+    #[default]
+    Skip,
+    /// Fire once now, then every period from now on
+    Delay,
+}
+
+impl MissedTicks {
+    pub fn behavior(self) -> tokio::time::MissedTickBehavior {
+        match self {
+            MissedTicks::Skip => tokio::time::MissedTickBehavior::Skip,
+            MissedTicks::Delay => tokio::time::MissedTickBehavior::Delay,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Boundary semantics for the grace period check
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraceBoundary {
+    /// `data_age <= grace_period_secs`
+    #[default]
+    Inclusive,
+    /// `data_age < grace_period_secs`
+    Exclusive,
+}
+
+/// Treatment of no-data ticks (e.g. collector outage) in batch counting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoDataPolicy {
+    /// Count as failed samples (downtime)
+    #[default]
+    Fail,
+    /// Exclude from the batch entirely
+    Neutral,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DaPostingConfig {
     pub enabled: bool,
+    /// Legacy layer switches, used only when `policies` is empty (see `Config::posting_policies`)
     pub post_every_sample: bool,
+    /// Only post a sample when its ok value differs from the previous sample
+    #[serde(default)]
+    pub on_change_only: bool,
+    /// Skip a sample blob identical (same ok and reason) to the last posted one; the next
+    /// differing blob records how many were skipped
+    #[serde(default)]
+    pub dedup_consecutive: bool,
+    /// Prefix blobs with a magic byte, format version and type tag so consumers can dispatch
+    #[serde(default = "default_blob_header")]
+    pub blob_header: bool,
+    /// Include each sample's reason string in its DA blob (reasons are always kept locally)
+    #[serde(default = "default_include_reason")]
+    pub include_reason: bool,
+    /// Identifier stamped into every sample and batch blob (required when posting)
+    #[serde(default)]
+    pub reader_id: String,
+    /// Batches awaiting a successful DA post beyond this many are dropped, oldest first
+    #[serde(default = "default_pending_max_len")]
+    pub pending_max_len: usize,
+    /// Batches awaiting a successful DA post for longer than this are dropped
+    #[serde(default = "default_pending_max_age_secs")]
+    pub pending_max_age_secs: u64,
+    /// Record the height and block time of the DA block that included each batch blob
+    /// (`Batch::chain_anchor`)
+    #[serde(default)]
+    pub chain_time: bool,
+    /// Give up waiting on a single sample blob submission after this long
+    #[serde(default = "default_submit_timeout_secs")]
+    pub submit_timeout_secs: u64,
+    /// DA layers to post to, each with its own namespace, cadence and payload format
+    #[serde(default)]
+    pub policies: Vec<PostingPolicy>,
+}
+
+/// One DA layer: what to post, how often, and into which namespace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostingPolicy {
+    /// Label for logs; unique among policies
+    pub name: String,
+    /// Namespace to post into (default: `celestia.namespace`)
+    #[serde(default)]
+    pub namespace: Option<String>,
+    pub cadence: PostingCadence,
+    /// Post every Nth sample (`cadence = "every_n"`)
+    #[serde(default)]
+    pub every_n: Option<u64>,
+    pub format: PayloadFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostingCadence {
+    EverySample,
+    /// The first sample and every `every_n`th after it
+    EveryN,
+    /// The first sample and each ok→fail / fail→ok transition
+    OnChange,
+    /// Once per batch window
+    PerWindow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadFormat {
+    /// One blob per sample, with its reason
+    Sample,
+    /// One blob per sample, timestamp and ok only
+    SampleCompact,
+    /// Signed batch bundle (`cadence = "per_window"` only)
+    Batch,
+}
+
+fn default_pending_max_len() -> usize {
+    144
+}
+
+fn default_pending_max_age_secs() -> u64 {
+    24 * 60 * 60
 }
 
-#[derive(Debug, Clone, Deserialize)]
+fn default_submit_timeout_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchingConfig {
     pub window_secs: u64,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricsConfig {
-    pub head_metric: String,
-    pub headers_metric: String,
+    /// Name of the chain head metric, or a list of aliases (any of them is accepted)
+    pub head_metric: MetricNames,
+    /// Name of the sampled headers metric, or a list of aliases
+    pub headers_metric: MetricNames,
+    /// Only read the head from a metric of this type (unset = any type with a usable value)
+    #[serde(default)]
+    pub head_metric_type: Option<MetricType>,
+    /// Only read the headers from a metric of this type
+    #[serde(default)]
+    pub headers_metric_type: Option<MetricType>,
     pub min_increment: i64,
+    /// Minimum headers increase per tick for headers to count as advancing
+    #[serde(default = "default_min_headers_increment")]
+    pub min_headers_increment: i64,
+    /// Quantile to read the head from when it is only exported as a Summary (e.g. 0.99)
+    pub head_summary_quantile: Option<f64>,
+    /// Resource attributes (e.g. "service.instance.id") copied into each persisted sample
+    #[serde(default)]
+    pub sample_resource_attributes: Vec<String>,
+    /// Metric attribute key → sample label name (e.g. `{ "network" = "network" }`); data point
+    /// attributes take precedence over resource attributes with the same key
+    #[serde(default)]
+    pub sample_labels: BTreeMap<String, String>,
+    /// Cap on distinct metric names whose previous value is remembered (LRU-evicted)
+    #[serde(default = "default_max_tracked_metric_names")]
+    pub max_tracked_metric_names: usize,
+    /// Per-metric weights for a fractional sample score (unset = binary ok/fail only)
+    #[serde(default)]
+    pub weights: Option<MetricWeights>,
+    /// What to do with head/headers data points that carry no resource attributes
+    #[serde(default)]
+    pub resourceless_metrics: ResourcelessPolicy,
+    /// Fail a tick whose head trails the DA node's network head by more than this many blocks
+    /// (unset = lag not evaluated)
+    #[serde(default)]
+    pub max_head_lag: Option<u64>,
+    /// Seconds after the first DAS data during which lag failures are suppressed, while a
+    /// restarted node catches up (independent of `sampling.warmup_secs`)
+    #[serde(default)]
+    pub lag_warmup_secs: u64,
+}
+
+/// OTLP metric data type, for telling apart metrics that share a name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricType {
+    Gauge,
+    Sum,
+    Histogram,
+    Summary,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl MetricType {
+    /// Whether a normalized metric's `metric_type` is this type
+    pub fn matches(self, metric_type: &str) -> bool {
+        let name = match self {
+            MetricType::Gauge => "Gauge",
+            MetricType::Sum => "Sum",
+            MetricType::Histogram => "Histogram",
+            MetricType::Summary => "Summary",
+        };
+        name == metric_type
+    }
+}
+
+/// Treatment of DAS metrics exported without resource attributes (e.g. minimal exporters)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourcelessPolicy {
+    /// Use them; resource-based sample attributes/labels are simply absent
+    #[default]
+    Accept,
+    /// Ignore them, so only identified sources drive samples
+    Reject,
+}
+
+/// Relative weight of each metric's health signal; normalized by their sum
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricWeights {
+    pub head: f64,
+    pub headers: f64,
+}
+
+/// One metric name or a list of aliases, e.g. for names that changed across node versions
+///
+/// Serialized back as a plain string when there is a single name.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "OneOrMany")]
+pub struct MetricNames(Vec<String>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<OneOrMany> for MetricNames {
+    fn from(names: OneOrMany) -> Self {
+        match names {
+            OneOrMany::One(name) => MetricNames(vec![name]),
+            OneOrMany::Many(names) => MetricNames(names),
+        }
+    }
+}
+
+impl Serialize for MetricNames {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0.as_slice() {
+            [name] => serializer.serialize_str(name),
+            names => names.serialize(serializer),
+        }
+    }
+}
+
+impl MetricNames {
+    /// Whether `name` is one of the accepted names
+    pub fn matches(&self, name: &str) -> bool {
+        self.0.iter().any(|n| n == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.0.iter()
+    }
+}
+
+fn default_min_headers_increment() -> i64 {
+    1
+}
+
+fn default_max_tracked_metric_names() -> usize {
+    1024
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CelestiaConfig {
     pub rpc_url: String,
     pub grpc_url: String,
     pub namespace: String,
-    pub poster_mode: String,
+    pub poster_mode: PosterMode,
     /// Mnemonic phrase (24 words) - will be converted to private key
     /// Either provide this OR private_key_hex (not both)
+    #[serde(skip_serializing)]
     pub mnemonic: Option<String>,
     /// Direct private key in hex format (64 characters)
     /// Either provide this OR mnemonic (not both)
+    #[serde(skip_serializing)]
     pub private_key_hex: Option<String>,
+    /// Light node JSON-RPC gateway used by `poster_mode = "light-gateway"`
+    #[serde(default = "default_gateway_url")]
+    pub gateway_url: String,
+    /// Node auth token (JWT), sent as a bearer token on node RPC calls (prefer CELESTIA_NODE_AUTH_TOKEN)
+    #[serde(default, skip_serializing)]
+    pub auth_token: Option<String>,
+    /// Account address (`celestia1...`) the configured key must derive to; startup fails otherwise
+    #[serde(default)]
+    pub expected_address: Option<String>,
+    /// Allow both credentials, falling back to the mnemonic (with a warning) if the private key is invalid
+    #[serde(default)]
+    pub key_fallback_to_mnemonic: bool,
+}
+
+/// Where DA blobs are submitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PosterMode {
+    /// Keep blobs in memory (development)
+    Mock,
+    /// Sign PayForBlobs with the reader's key via `rpc_url`/`grpc_url`
+    Real,
+    /// Submit through a light node's `blob.Submit` at `gateway_url`
+    LightGateway,
+}
+
+impl std::fmt::Display for PosterMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PosterMode::Mock => "mock",
+            PosterMode::Real => "real",
+            PosterMode::LightGateway => "light-gateway",
+        })
+    }
+}
+
+fn default_blob_header() -> bool {
+    true
+}
+
+fn default_include_reason() -> bool {
+    true
+}
+
+fn default_gateway_url() -> String {
+    "http://localhost:26658".to_string()
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProofsConfig {
     pub enabled: bool,
     pub threshold_percent: f64,
+    /// Batches meeting `threshold_percent` but below this are "degraded" (early warning)
+    #[serde(default)]
+    pub warn_threshold_percent: Option<f64>,
+    /// Algorithm for the batch bitmap_hash ("blake3", "poseidon" or "sha256")
+    #[serde(default)]
+    pub hash_algo: HashAlgo,
+    /// Algorithms whose bitmap hashes batches also carry in `bitmap_hashes`, e.g. while
+    /// verifiers migrate from one `hash_algo` to another (empty = `bitmap_hash` only)
+    #[serde(default)]
+    pub hash_algos: Vec<HashAlgo>,
+    /// Also commit to a 2-bit-per-sample failure-category bitmap (`reason_bitmap_hash`)
+    #[serde(default)]
+    pub reason_bitmap: bool,
+    /// Per-reason contribution (0-1) of a sample to `weighted_good`, which then decides
+    /// health; unlisted reasons count 1 if ok, else 0
+    #[serde(default)]
+    pub reason_weights: HashMap<SampleReason, f64>,
+    /// Directory receiving one `<window_start>_<window_end>.proof` (+ `.json` public inputs) per batch
+    #[serde(default = "default_proof_export_dir")]
+    pub export_dir: String,
+}
+
+fn default_proof_export_dir() -> String {
+    "data/proofs".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Bearer token for /admin endpoints (prefer DA_READER_ADMIN_TOKEN); admin is disabled if unset
+    #[serde(skip_serializing)]
+    pub admin_token: Option<String>,
+    /// Idle time before TCP keepalive probes start (also the probe interval)
+    #[serde(default = "default_tcp_keepalive_secs")]
+    pub tcp_keepalive_secs: u64,
+    /// Close connections that send no request headers for this long
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// Decode attempt order for non-JSON /v1/metrics bodies
+    #[serde(default)]
+    pub decode_order: DecodeOrder,
+    /// Namespaces accepted by verification endpoints (empty = only `celestia.namespace`)
+    #[serde(default)]
+    pub allowed_namespaces: Vec<String>,
+    /// Histogram buckets / summary quantiles kept per data point; extras are dropped
+    #[serde(default = "default_max_buckets_per_point")]
+    pub max_buckets_per_point: usize,
+    /// How far back query endpoints (GET /samples) reach, whatever `from` asks for
+    #[serde(default = "default_max_query_age_secs")]
+    pub max_query_age_secs: u64,
+    /// Gzip read endpoint responses for clients that accept it (never the OTLP ingest path)
+    #[serde(default = "default_compress_responses")]
+    pub compress_responses: bool,
+    /// Export the latest batch's reason breakdown as `da_reader_batch_reason_count` gauges
+    #[serde(default = "default_export_batch_reasons")]
+    pub export_batch_reasons: bool,
+}
+
+/// How /v1/metrics bodies are decoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DecodeOrder {
+    /// Protobuf, then JSON if that fails (JSON content types are decoded as JSON only)
+    #[default]
+    ProtobufFirst,
+    /// JSON, then protobuf, for non-JSON content types
+    JsonFirst,
+    /// Only the format declared by Content-Type; anything else is rejected with 400
+    Strict,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            admin_token: None,
+            tcp_keepalive_secs: default_tcp_keepalive_secs(),
+            idle_timeout_secs: default_idle_timeout_secs(),
+            decode_order: DecodeOrder::default(),
+            allowed_namespaces: Vec::new(),
+            max_buckets_per_point: default_max_buckets_per_point(),
+            max_query_age_secs: default_max_query_age_secs(),
+            compress_responses: default_compress_responses(),
+            export_batch_reasons: default_export_batch_reasons(),
+        }
+    }
+}
+
+fn default_tcp_keepalive_secs() -> u64 {
+    60
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    120
+}
+
+fn default_max_buckets_per_point() -> usize {
+    1024
+}
+
+fn default_compress_responses() -> bool {
+    true
+}
+
+fn default_export_batch_reasons() -> bool {
+    true
+}
+
+fn default_max_query_age_secs() -> u64 {
+    7 * 24 * 3600
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// gzip level (0 = store only, 9 = smallest) for compressed files and blobs
+    #[serde(default = "default_compression_level")]
+    pub compression_level: u32,
+    /// Write samples.json every N sampler ticks instead of every tick (always on shutdown)
+    #[serde(default = "default_flush_every_n_samples")]
+    pub flush_every_n_samples: usize,
+    /// Extra attempts for a data/ file write that fails with a transient error
+    #[serde(default = "default_write_retries")]
+    pub write_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    #[serde(default = "default_write_retry_backoff_ms")]
+    pub write_retry_backoff_ms: u64,
+    /// Collapse raw samples older than this into per-bucket ok/total counts (unset = keep all raw)
+    #[serde(default)]
+    pub downsample_after_secs: Option<u64>,
+    /// Width of a downsampled bucket (60 = per minute, 3600 = per hour)
+    #[serde(default = "default_downsample_bucket_secs")]
+    pub downsample_bucket_secs: u64,
+    /// Skip data/ writes when the target filesystem has less free space than this (0 = no check)
+    #[serde(default)]
+    pub min_free_bytes: u64,
+    /// Where the sampler persists samples
+    #[serde(default)]
+    pub backend: StorageBackend,
+    /// With `backend = "sqlite"`, samples older than this are dropped from memory (the API's
+    /// history) and stay only in samples.db; also how far back startup loads
+    #[serde(default = "default_sqlite_memory_secs")]
+    pub sqlite_memory_secs: u64,
+}
+
+impl StorageConfig {
+    /// How long samples stay in memory, `None` = for good (`backend = "json"`, whose
+    /// samples.json is the in-memory history)
+    ///
+    /// Never shorter than what compaction still has to summarize.
+    pub fn memory_retention_secs(&self) -> Option<u64> {
+        (self.backend == StorageBackend::Sqlite).then(|| match self.downsample_after_secs {
+            Some(after) => self.sqlite_memory_secs.max(after + self.downsample_bucket_secs),
+            None => self.sqlite_memory_secs,
+        })
+    }
+}
+
+/// Persistence of the sample history
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Rewrite data/samples.json (every `flush_every_n_samples` ticks)
+    #[default]
+    Json,
+    /// Insert each sample into data/samples.db
+    Sqlite,
+}
+
+fn default_compression_level() -> u32 {
+    6
+}
+
+fn default_flush_every_n_samples() -> usize {
+    1
+}
+
+fn default_write_retries() -> u32 {
+    3
+}
+
+fn default_write_retry_backoff_ms() -> u64 {
+    50
+}
+
+fn default_downsample_bucket_secs() -> u64 {
+    3600
+}
+
+fn default_sqlite_memory_secs() -> u64 {
+    86400
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            compression_level: default_compression_level(),
+            flush_every_n_samples: default_flush_every_n_samples(),
+            write_retries: default_write_retries(),
+            write_retry_backoff_ms: default_write_retry_backoff_ms(),
+            downsample_after_secs: None,
+            downsample_bucket_secs: default_downsample_bucket_secs(),
+            min_free_bytes: 0,
+            backend: StorageBackend::default(),
+            sqlite_memory_secs: default_sqlite_memory_secs(),
+        }
+    }
+}
+
+/// Ask the node's own RPC for its head when OTLP telemetry goes stale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecondaryCheckConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// celestia-node JSON-RPC endpoint (plain HTTP)
+    #[serde(default = "default_node_rpc_url")]
+    pub node_rpc_url: String,
+    #[serde(default = "default_secondary_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_node_rpc_url() -> String {
+    "http://localhost:26658".to_string()
+}
+
+fn default_secondary_timeout_secs() -> u64 {
+    5
+}
+
+impl Default for SecondaryCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            node_rpc_url: default_node_rpc_url(),
+            timeout_secs: default_secondary_timeout_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    /// Consecutive failing (or passing) windows required before alerting (or recovering)
+    #[serde(default = "default_hysteresis_windows")]
+    pub hysteresis_windows: u32,
+}
+
+fn default_hysteresis_windows() -> u32 {
+    1
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            hysteresis_windows: default_hysteresis_windows(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugConfig {
+    /// Directory where undecodable OTLP request bodies are dumped (disabled if unset)
+    pub dump_failed_requests_dir: Option<String>,
+    /// Keep at most this many dump files (oldest deleted first)
+    #[serde(default = "default_dump_max_files")]
+    pub dump_max_files: usize,
+    /// Keep the dump files within this many bytes (oldest deleted first)
+    #[serde(default = "default_dump_max_bytes")]
+    pub dump_max_bytes: u64,
+    /// Append every ingest's normalized metrics to this file as JSONL (disabled if unset)
+    pub normalized_metrics_file: Option<String>,
+    /// Rotate the normalized metrics file to `<file>.1` beyond this many bytes
+    #[serde(default = "default_dump_max_bytes")]
+    pub normalized_metrics_max_bytes: u64,
+    /// At startup, feed a synthetic OTLP export through ingestion to check the metric names
+    #[serde(default)]
+    pub self_test: bool,
+}
+
+fn default_dump_max_files() -> usize {
+    100
+}
+
+fn default_dump_max_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            dump_failed_requests_dir: None,
+            dump_max_files: default_dump_max_files(),
+            dump_max_bytes: default_dump_max_bytes(),
+            normalized_metrics_file: None,
+            normalized_metrics_max_bytes: default_dump_max_bytes(),
+            self_test: false,
+        }
+    }
 }
 
 impl Config {
@@ -63,8 +755,7 @@ impl Config {
         // Load .env file if it exists (silently fail if not found)
         let _ = dotenvy::dotenv();
         
-        let content = fs::read_to_string("config.toml")?;
-        let mut config: Config = toml::from_str(&content)?;
+        let mut config = Self::from_file("config.toml")?;
         
         // Load from environment variables (takes precedence over config.toml)
         config.load_from_env()?;
@@ -72,6 +763,79 @@ impl Config {
         // Validate after loading from env
         config.validate()?;
         
+        config.resolve_namespace();
+        
+        Ok(config)
+    }
+
+    /// DA layers in effect, with namespaces filled in
+    ///
+    /// Without `da_posting.policies`, the legacy switches map onto a sample layer
+    /// (`post_every_sample`, `on_change_only`, `include_reason`) plus a per-window batch layer.
+    pub fn posting_policies(&self) -> Vec<PostingPolicy> {
+        let da = &self.da_posting;
+        let mut policies = da.policies.clone();
+        if policies.is_empty() {
+            if da.post_every_sample {
+                policies.push(PostingPolicy {
+                    name: "samples".to_string(),
+                    namespace: None,
+                    cadence: if da.on_change_only { PostingCadence::OnChange } else { PostingCadence::EverySample },
+                    every_n: None,
+                    format: if da.include_reason { PayloadFormat::Sample } else { PayloadFormat::SampleCompact },
+                });
+            }
+            policies.push(PostingPolicy {
+                name: "batches".to_string(),
+                namespace: None,
+                cadence: PostingCadence::PerWindow,
+                every_n: None,
+                format: PayloadFormat::Batch,
+            });
+        }
+        for policy in &mut policies {
+            policy.namespace.get_or_insert_with(|| self.celestia.namespace.clone());
+        }
+        policies
+    }
+
+    /// Replace `celestia.namespace = "auto"` with the namespace derived from `reader_id`
+    fn resolve_namespace(&mut self) {
+        if self.celestia.namespace == AUTO_NAMESPACE {
+            let namespace = namespace_to_hex(&namespace_from_reader_id(&self.da_posting.reader_id));
+            tracing::info!(
+                "🏷️  Derived namespace {} from reader_id {:?}",
+                namespace,
+                self.da_posting.reader_id
+            );
+            self.celestia.namespace = namespace;
+        }
+    }
+
+    /// Parse a config file as-is, without env overrides or credential validation
+    pub fn from_file(path: &str) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    /// Parse config TOML, reporting keys that no setting reads
+    ///
+    /// Unknown keys are an error with `strict = true` and a warning otherwise.
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let mut unknown = Vec::new();
+        let config: Self = serde_ignored::deserialize(toml::Deserializer::new(content), |path| {
+            unknown.push(path.to_string())
+        })?;
+
+        if config.strict && !unknown.is_empty() {
+            anyhow::bail!(
+                "unknown config key(s): {} (strict = true rejects keys no setting reads; check for typos)",
+                unknown.join(", ")
+            );
+        }
+        for key in &unknown {
+            tracing::warn!("⚠️  Ignoring unknown config key `{}` (typo?)", key);
+        }
         Ok(config)
     }
 
@@ -81,8 +845,10 @@ impl Config {
             if !mnemonic.trim().is_empty() {
                 tracing::info!("🔑 Loaded CELESTIA_MNEMONIC from environment");
                 self.celestia.mnemonic = Some(mnemonic.trim().to_string());
-                // Clear private_key_hex if mnemonic is set via env
-                self.celestia.private_key_hex = None;
+                // Clear private_key_hex if mnemonic is set via env, unless it's kept as the primary key
+                if !self.celestia.key_fallback_to_mnemonic {
+                    self.celestia.private_key_hex = None;
+                }
             }
         }
         
@@ -91,8 +857,26 @@ impl Config {
             if !private_key.trim().is_empty() {
                 tracing::info!("🔑 Loaded CELESTIA_PRIVATE_KEY from environment");
                 self.celestia.private_key_hex = Some(private_key.trim().to_string());
-                // Clear mnemonic if private_key is set via env
-                self.celestia.mnemonic = None;
+                // Clear mnemonic if private_key is set via env, unless it's kept as the fallback
+                if !self.celestia.key_fallback_to_mnemonic {
+                    self.celestia.mnemonic = None;
+                }
+            }
+        }
+        
+        // Check for node auth token in environment
+        if let Ok(token) = env::var("CELESTIA_NODE_AUTH_TOKEN") {
+            if !token.trim().is_empty() {
+                tracing::info!("🔑 Loaded CELESTIA_NODE_AUTH_TOKEN from environment");
+                self.celestia.auth_token = Some(token.trim().to_string());
+            }
+        }
+        
+        // Check for admin token in environment
+        if let Ok(token) = env::var("DA_READER_ADMIN_TOKEN") {
+            if !token.trim().is_empty() {
+                tracing::info!("🔑 Loaded DA_READER_ADMIN_TOKEN from environment");
+                self.server.admin_token = Some(token.trim().to_string());
             }
         }
         
@@ -100,6 +884,163 @@ impl Config {
     }
 
     fn validate(&self) -> anyhow::Result<()> {
+        if self.da_posting.enabled && self.da_posting.reader_id.trim().is_empty() {
+            anyhow::bail!(
+                "da_posting.reader_id must be set when DA posting is enabled, \
+                so blobs from multiple readers in one namespace can be told apart"
+            );
+        }
+
+        crate::utils::window_size(self.batching.window_secs, self.sampling.tick_secs)?;
+
+        for (key, names) in [("head_metric", &self.metrics.head_metric), ("headers_metric", &self.metrics.headers_metric)] {
+            if names.iter().next().is_none() || names.iter().any(|n| n.trim().is_empty()) {
+                anyhow::bail!("metrics.{} needs at least one non-empty metric name", key);
+            }
+        }
+
+        if let Some(name) = self.metrics.sample_labels.values().find(|name| !is_valid_label_name(name)) {
+            anyhow::bail!(
+                "metrics.sample_labels: {:?} is not a valid label name ([a-zA-Z_][a-zA-Z0-9_]*), exports would reject it",
+                name
+            );
+        }
+
+        if let Some(q) = self.metrics.head_summary_quantile {
+            if !(0.0..=1.0).contains(&q) {
+                anyhow::bail!("metrics.head_summary_quantile must be between 0 and 1, got {}", q);
+            }
+        }
+
+        if let Some(w) = self.metrics.weights {
+            if ![w.head, w.headers].iter().all(|x| x.is_finite() && *x >= 0.0) || w.head + w.headers <= 0.0 {
+                anyhow::bail!(
+                    "metrics.weights must be non-negative with a positive sum, got head={} headers={}",
+                    w.head, w.headers
+                );
+            }
+        }
+
+        if self.celestia.namespace == AUTO_NAMESPACE && self.da_posting.reader_id.trim().is_empty() {
+            anyhow::bail!("celestia.namespace = \"auto\" requires da_posting.reader_id to be set");
+        }
+
+        if self.storage.compression_level > 9 {
+            anyhow::bail!("storage.compression_level must be 0-9, got {}", self.storage.compression_level);
+        }
+
+        if let Some((reason, weight)) = self
+            .proofs
+            .reason_weights
+            .iter()
+            .find(|(_, w)| !(0.0..=1.0).contains(*w))
+        {
+            anyhow::bail!("proofs.reason_weights must be between 0 and 1, got {:?} = {}", reason, weight);
+        }
+
+        if let Some(warn) = self.proofs.warn_threshold_percent {
+            if !(warn > self.proofs.threshold_percent && warn <= 1.0) {
+                anyhow::bail!(
+                    "proofs.warn_threshold_percent must be above threshold_percent ({}) and at most 1, got {}",
+                    self.proofs.threshold_percent,
+                    warn
+                );
+            }
+        }
+
+        if self.mode == ReaderMode::Replica && self.da_posting.enabled {
+            anyhow::bail!("mode = \"replica\" reads another reader's blobs; set da_posting.enabled = false");
+        }
+
+        if self.mode == ReaderMode::Replica {
+            crate::da::parse_public_key(&self.replica.source_public_key).map_err(|e| {
+                anyhow::anyhow!("mode = \"replica\" needs replica.source_public_key, the primary's signing key from GET /identity: {}", e)
+            })?;
+        }
+
+        let mut names = std::collections::HashSet::new();
+        let mut namespaces = std::collections::HashSet::new();
+        for policy in &self.da_posting.policies {
+            let name = &policy.name;
+            if name.trim().is_empty() || !names.insert(name) {
+                anyhow::bail!("da_posting.policies need unique, non-empty names, got {:?}", name);
+            }
+            match (policy.cadence, policy.every_n) {
+                (PostingCadence::EveryN, Some(n)) if n >= 1 => {}
+                (PostingCadence::EveryN, _) => {
+                    anyhow::bail!("da_posting policy {:?}: cadence \"every_n\" needs every_n >= 1", name)
+                }
+                (_, Some(_)) => anyhow::bail!("da_posting policy {:?}: every_n only applies to cadence \"every_n\"", name),
+                _ => {}
+            }
+            if (policy.format == PayloadFormat::Batch) != (policy.cadence == PostingCadence::PerWindow) {
+                anyhow::bail!(
+                    "da_posting policy {:?}: format \"batch\" goes with cadence \"per_window\" and sample formats with per-sample cadences",
+                    name
+                );
+            }
+            if let Some(namespace) = &policy.namespace {
+                namespace_from_hex(namespace)
+                    .map_err(|e| anyhow::anyhow!("da_posting policy {:?}: namespace can't be posted to: {}", name, e))?;
+            }
+            // Posted batch windows are tracked per namespace, and two sample chains in one
+            // namespace would interleave, so two layers of a kind can't share one
+            let namespace = policy.namespace.as_deref().unwrap_or(&self.celestia.namespace);
+            if !namespaces.insert((policy.format == PayloadFormat::Batch, namespace)) {
+                anyhow::bail!(
+                    "da_posting policy {:?}: another {} policy already posts to namespace {}",
+                    name,
+                    if policy.format == PayloadFormat::Batch { "batch" } else { "sample" },
+                    namespace
+                );
+            }
+        }
+
+        if self.storage.flush_every_n_samples == 0 {
+            anyhow::bail!("storage.flush_every_n_samples must be at least 1");
+        }
+
+        if self.storage.downsample_bucket_secs == 0 {
+            anyhow::bail!("storage.downsample_bucket_secs must be at least 1");
+        }
+
+        if self.secondary_check.enabled {
+            let uri: axum::http::Uri = self.secondary_check.node_rpc_url.parse().map_err(|e| {
+                anyhow::anyhow!("secondary_check.node_rpc_url is not a valid URL: {}", e)
+            })?;
+            if uri.scheme_str() != Some("http") {
+                anyhow::bail!("secondary_check.node_rpc_url must be an http:// URL, got {}", uri);
+            }
+        }
+
+        if self.celestia.poster_mode != PosterMode::Mock
+            && self.celestia.auth_token.as_deref().is_none_or(|t| t.trim().is_empty())
+        {
+            anyhow::bail!(
+                "poster_mode = \"{}\" requires celestia.auth_token (the node's JWT); \
+                set CELESTIA_NODE_AUTH_TOKEN in .env or environment",
+                self.celestia.poster_mode
+            );
+        }
+
+        if self.celestia.poster_mode == PosterMode::LightGateway {
+            let uri: axum::http::Uri = self.celestia.gateway_url.parse().map_err(|e| {
+                anyhow::anyhow!("celestia.gateway_url is not a valid URL: {}", e)
+            })?;
+            if uri.scheme_str() != Some("http") {
+                anyhow::bail!("celestia.gateway_url must be an http:// URL, got {}", uri);
+            }
+        }
+
+        if self.celestia.poster_mode != PosterMode::Mock
+            && self.celestia.namespace != AUTO_NAMESPACE
+        {
+            namespace_from_hex(&self.celestia.namespace)
+                .map_err(|e| anyhow::anyhow!("celestia.namespace can't be posted to: {}", e))?;
+        }
+
+        self.celestia.check_expected_address()?;
+
         // Validate Celestia authentication config
         match (&self.celestia.mnemonic, &self.celestia.private_key_hex) {
             (None, None) => {
@@ -109,9 +1050,14 @@ impl Config {
                     See docs/ENV_SETUP.md for instructions."
                 );
             }
+            (Some(_), Some(_)) if self.celestia.key_fallback_to_mnemonic => {
+                tracing::info!("✅ Using direct private key authentication (mnemonic as fallback)");
+                Ok(())
+            }
             (Some(_), Some(_)) => {
                 anyhow::bail!(
-                    "Celestia configuration error: Provide only ONE of 'mnemonic' or 'private_key_hex', not both"
+                    "Celestia configuration error: Provide only ONE of 'mnemonic' or 'private_key_hex', not both \
+                    (or set celestia.key_fallback_to_mnemonic = true to use the mnemonic if the key is invalid)"
                 );
             }
             (Some(_), None) => {
@@ -128,11 +1074,19 @@ impl Config {
 
 impl CelestiaConfig {
     /// Get the private key hex, deriving it from mnemonic if necessary
+    ///
+    /// With `key_fallback_to_mnemonic`, an invalid private key falls back to the mnemonic.
     pub fn get_private_key_hex(&self) -> anyhow::Result<String> {
         if let Some(hex) = &self.private_key_hex {
             // Validate the hex key
-            crate::crypto::validate_private_key_hex(hex)?;
-            Ok(hex.clone())
+            match (crate::crypto::validate_private_key_hex(hex), &self.mnemonic) {
+                (Ok(()), _) => Ok(hex.clone()),
+                (Err(e), Some(mnemonic)) if self.key_fallback_to_mnemonic => {
+                    tracing::warn!("⚠️  Invalid private_key_hex ({}); deriving the key from the mnemonic instead", e);
+                    crate::crypto::mnemonic_to_private_key_hex(mnemonic)
+                }
+                (Err(e), _) => Err(e),
+            }
         } else if let Some(mnemonic) = &self.mnemonic {
             // Derive from mnemonic
             crate::crypto::mnemonic_to_private_key_hex(mnemonic)
@@ -140,5 +1094,238 @@ impl CelestiaConfig {
             anyhow::bail!("No authentication method provided")
         }
     }
+
+    /// Fail if `expected_address` is set and the configured key derives to another account,
+    /// e.g. because the mnemonic was derived with the wrong path or key type
+    pub fn check_expected_address(&self) -> anyhow::Result<()> {
+        let Some(expected) = self.expected_address.as_deref().map(str::trim) else {
+            return Ok(());
+        };
+        let derived = crate::crypto::celestia_address_from_hex(&self.get_private_key_hex()?)?;
+        if derived != expected {
+            anyhow::bail!(
+                "celestia.expected_address is {} but the configured key derives {}; \
+                check the mnemonic/private key (derivation path and key type) before posting",
+                expected,
+                derived
+            );
+        }
+        tracing::info!("✅ Celestia key matches expected address {}", derived);
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+impl Config {
+    /// Config mirroring config.toml with a throwaway private key, for tests
+    pub fn for_tests() -> Self {
+        toml::from_str(TEST_CONFIG_TOML).expect("test config must parse")
+    }
 }
 
+#[cfg(test)]
+const TEST_CONFIG_TOML: &str = r#"
+            [sampling]
+            tick_secs = 30
+            max_staleness_secs = 120
+            grace_period_secs = 45
+
+            [da_posting]
+            enabled = false
+            post_every_sample = true
+            reader_id = "test-reader"
+
+            [batching]
+            window_secs = 600
+
+            [metrics]
+            head_metric = "das_sampled_chain_head"
+            headers_metric = "das_total_sampled_headers"
+            min_increment = 1
+
+            [celestia]
+            rpc_url = "ws://localhost:26658"
+            grpc_url = "http://localhost:9090"
+            namespace = "0x2N1CE"
+            poster_mode = "mock"
+            private_key_hex = "393fdb5def075819de55756b45c9e2c8531a8c78dd6eede483d3440e9457d839"
+
+            [proofs]
+            enabled = false
+            threshold_percent = 0.95
+            "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strict_mode_rejects_unknown_key() {
+        let with_typo = TEST_CONFIG_TOML.replace(
+            "threshold_percent = 0.95",
+            "threshold_percent = 0.95\nthreshhold_percent = 0.9",
+        );
+
+        let err = Config::parse(&format!("strict = true\n{}", with_typo)).unwrap_err().to_string();
+        assert!(err.contains("unknown config key(s): proofs.threshhold_percent"), "{}", err);
+
+        // Without strict mode the key is only warned about
+        let config = Config::parse(&with_typo).unwrap();
+        assert_eq!(config.proofs.threshold_percent, 0.95);
+    }
+
+    #[test]
+    fn test_invalid_key_falls_back_to_mnemonic_when_enabled() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mut config = Config::for_tests();
+        config.celestia.private_key_hex = Some("not-hex".to_string());
+        config.celestia.mnemonic = Some(mnemonic.to_string());
+
+        // Strict by default: both credentials are rejected, and the bad key isn't papered over
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("only ONE"), "{}", err);
+        assert!(config.celestia.get_private_key_hex().is_err());
+
+        config.celestia.key_fallback_to_mnemonic = true;
+        config.validate().unwrap();
+        let expected = crate::crypto::mnemonic_to_private_key_hex(mnemonic).unwrap();
+        assert_eq!(config.celestia.get_private_key_hex().unwrap(), expected);
+
+        // A valid key still wins over the mnemonic
+        config.celestia.private_key_hex = Some("11".repeat(32));
+        assert_eq!(config.celestia.get_private_key_hex().unwrap(), "11".repeat(32));
+    }
+
+    #[test]
+    fn test_expected_address_must_match_derived_key() {
+        let mut config = Config::for_tests();
+        let derived = crate::crypto::celestia_address_from_hex(config.celestia.private_key_hex.as_deref().unwrap()).unwrap();
+
+        config.celestia.expected_address = Some(derived.clone());
+        config.validate().unwrap();
+
+        let other = crate::crypto::celestia_address_from_hex(&"11".repeat(32)).unwrap();
+        config.celestia.expected_address = Some(other.clone());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains(&other) && err.contains(&derived), "{}", err);
+    }
+
+    #[test]
+    fn test_light_gateway_requires_token_and_posting_namespace() {
+        let mut config = Config::for_tests();
+        config.celestia.poster_mode = PosterMode::LightGateway;
+        config.celestia.namespace = "0x00000000000000000001".to_string();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("poster_mode = \"light-gateway\" requires celestia.auth_token"), "{}", err);
+
+        config.celestia.auth_token = Some("jwt".to_string());
+        config.validate().unwrap();
+
+        config.celestia.gateway_url = "https://light.example".to_string();
+        assert!(config.validate().is_err());
+
+        config.celestia.gateway_url = default_gateway_url();
+        config.celestia.namespace = "0x2N1CE".to_string();
+        assert!(config.validate().unwrap_err().to_string().contains("celestia.namespace"));
+    }
+
+    #[test]
+    fn test_unknown_poster_mode_rejected() {
+        let err = Config::parse(&TEST_CONFIG_TOML.replace("poster_mode = \"mock\"", "poster_mode = \"gateway\"")).unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown variant `gateway`"), "{:#}", err);
+    }
+
+    #[test]
+    fn test_metric_names_accept_string_or_list() {
+        let single = Config::for_tests();
+        assert!(single.metrics.head_metric.matches("das_sampled_chain_head"));
+        assert_eq!(serde_json::to_value(&single.metrics.head_metric).unwrap(), "das_sampled_chain_head");
+
+        let aliased = Config::parse(&TEST_CONFIG_TOML.replace(
+            r#"headers_metric = "das_total_sampled_headers""#,
+            r#"headers_metric = ["das_total_sampled_headers", "das_sampled_headers_total"]"#,
+        ))
+        .unwrap();
+        assert!(aliased.metrics.headers_metric.matches("das_sampled_headers_total"));
+        assert!(!aliased.metrics.headers_metric.matches("das_sampled_chain_head"));
+        assert_eq!(
+            serde_json::to_value(&aliased.metrics.headers_metric).unwrap(),
+            serde_json::json!(["das_total_sampled_headers", "das_sampled_headers_total"])
+        );
+
+        let empty = Config::parse(&TEST_CONFIG_TOML.replace(r#"head_metric = "das_sampled_chain_head""#, "head_metric = []"));
+        assert!(empty.unwrap().validate().is_err());
+    }
+
+    #[test]
+    fn test_warn_threshold_must_exceed_target() {
+        let mut config = Config::for_tests();
+        config.proofs.warn_threshold_percent = Some(0.98);
+        config.validate().unwrap();
+        config.proofs.warn_threshold_percent = Some(0.9);
+        assert!(config.validate().unwrap_err().to_string().contains("warn_threshold_percent"));
+    }
+
+    #[test]
+    fn test_sample_label_names_validated() {
+        let mut config = Config::for_tests();
+        config.metrics.sample_labels.insert("service.instance.id".to_string(), "instance".to_string());
+        config.validate().unwrap();
+        config.metrics.sample_labels.insert("k8s.pod.name".to_string(), "pod.name".to_string());
+        assert!(config.validate().unwrap_err().to_string().contains("pod.name"));
+    }
+
+    #[test]
+    fn test_replica_requires_source_public_key() {
+        let mut config = Config::for_tests();
+        config.mode = ReaderMode::Replica;
+        config.da_posting.enabled = false;
+        assert!(config.validate().unwrap_err().to_string().contains("source_public_key"));
+        config.replica.source_public_key = crate::da::DaSigner::from_config(&config.celestia).public_key_hex().unwrap();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_shipped_config_has_no_unknown_keys() {
+        let strict = format!("strict = true\n{}", include_str!("../config.toml"));
+        Config::parse(&strict).unwrap();
+    }
+
+    #[test]
+    fn test_posting_policies_validated_and_defaulted() {
+        let config = Config::for_tests();
+        let legacy = config.posting_policies();
+        assert_eq!(legacy.len(), 2);
+        assert_eq!(legacy[0].cadence, PostingCadence::EverySample);
+        assert_eq!(legacy[1].format, PayloadFormat::Batch);
+        assert_eq!(legacy[1].namespace.as_deref(), Some("0x2N1CE"));
+
+        let with_policies = |policies: &str| Config::parse(&format!("{}\n{}", TEST_CONFIG_TOML, policies));
+        let config = with_policies(
+            r#"
+            [[da_posting.policies]]
+            name = "sparse"
+            cadence = "every_n"
+            every_n = 10
+            format = "sample_compact"
+            namespace = "0x00000000000000000002"
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.posting_policies().len(), 1);
+
+        let missing_n = with_policies("[[da_posting.policies]]\nname = \"a\"\ncadence = \"every_n\"\nformat = \"sample\"");
+        assert!(missing_n.unwrap().validate().unwrap_err().to_string().contains("every_n"));
+        let batch_per_sample = with_policies("[[da_posting.policies]]\nname = \"a\"\ncadence = \"on_change\"\nformat = \"batch\"");
+        assert!(batch_per_sample.unwrap().validate().unwrap_err().to_string().contains("per_window"));
+
+        let batch_layer = |name: &str| format!("[[da_posting.policies]]\nname = \"{}\"\ncadence = \"per_window\"\nformat = \"batch\"\n", name);
+        let shared = with_policies(&format!("{}{}", batch_layer("a"), batch_layer("b")));
+        assert!(shared.unwrap().validate().unwrap_err().to_string().contains("already posts to namespace"));
+        // A sample layer and a batch layer may share the namespace
+        let mixed = with_policies(&format!("{}[[da_posting.policies]]\nname = \"s\"\ncadence = \"every_sample\"\nformat = \"sample\"\n", batch_layer("a")));
+        mixed.unwrap().validate().unwrap();
+    }
+}
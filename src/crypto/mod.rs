@@ -1,6 +1,8 @@
 use anyhow::{Context, Result};
+use bip32::{DerivationPath, XPrv};
 use bip39::Mnemonic;
-use slip10_ed25519::derive_ed25519_private_key;
+use ed25519_dalek::SigningKey;
+use celestia_client::types::state::AccAddress;
 
 /// Derives a private key from a mnemonic phrase
 /// 
@@ -30,23 +32,15 @@ pub fn mnemonic_to_private_key_hex(mnemonic_str: &str) -> Result<String> {
 
 /// Derives a Cosmos/Celestia private key from a seed
 /// 
-/// Uses SLIP-10 (ed25519 curve) with the standard Cosmos derivation path
+/// Uses BIP32 over secp256k1, the curve of Cosmos accounts, so a mnemonic yields the same
+/// account as in the user's wallet (Keplr, `celestia-appd keys`, ...)
 fn derive_cosmos_key(seed: &[u8], account: u32, change: u32, index: u32) -> Result<[u8; 32]> {
     // Cosmos derivation path: m/44'/118'/account'/change/index
-    // The ' indicates hardened derivation (0x80000000 | index)
-    const HARDENED: u32 = 0x80000000;
+    // The ' indicates hardened derivation
+    let path: DerivationPath = format!("m/44'/118'/{}'/{}/{}", account, change, index).parse()?;
+    let derived = XPrv::derive_from_path(seed, &path)?;
     
-    let path = vec![
-        HARDENED | 44,     // 44' - BIP44 purpose
-        HARDENED | 118,    // 118' - Cosmos coin type
-        HARDENED | account, // account' - hardened account
-        change,             // change - not hardened
-        index,              // index - not hardened
-    ];
-    
-    let derived = derive_ed25519_private_key(seed, &path);
-    
-    Ok(derived)
+    Ok(derived.private_key().to_bytes().into())
 }
 
 /// Validates that a hex string is a valid private key (32 bytes)
@@ -61,6 +55,31 @@ pub fn validate_private_key_hex(hex_str: &str) -> Result<()> {
     Ok(())
 }
 
+/// Build the ed25519 signing key for a validated hex private key
+pub fn signing_key_from_hex(hex_str: &str) -> Result<SigningKey> {
+    validate_private_key_hex(hex_str)?;
+    let bytes: [u8; 32] = hex::decode(hex_str)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Private key must be exactly 32 bytes"))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// Hex-encoded ed25519 public key of a signing key
+pub fn public_key_hex(key: &SigningKey) -> String {
+    hex::encode(key.verifying_key().to_bytes())
+}
+
+/// Celestia account address (`celestia1...`) that pays for blobs signed with this private key
+///
+/// Celestia accounts are secp256k1: the address is the bech32 form of
+/// RIPEMD160(SHA256(compressed public key)).
+pub fn celestia_address_from_hex(hex_str: &str) -> Result<String> {
+    validate_private_key_hex(hex_str)?;
+    let key = celestia_client::tx::SigningKey::from_slice(&hex::decode(hex_str)?)
+        .context("Private key is not a valid secp256k1 scalar")?;
+    Ok(AccAddress::from(key.verifying_key()).to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -94,5 +113,24 @@ mod tests {
         // Validate the derived key
         assert!(validate_private_key_hex(&hex_key).is_ok());
     }
+
+    #[test]
+    fn test_mnemonic_derives_wallet_account() {
+        // The well-known test mnemonic's first Cosmos account (cosmos19rl4cm2hmr8afy4kldpxz3fka4jguq0auqdal4),
+        // under the celestia prefix
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let key = mnemonic_to_private_key_hex(mnemonic).unwrap();
+        assert_eq!(celestia_address_from_hex(&key).unwrap(), "celestia19rl4cm2hmr8afy4kldpxz3fka4jguq0ad2ud9c");
+    }
+
+    #[test]
+    fn test_celestia_address_from_hex() {
+        let key = "393fdb5def075819de55756b45c9e2c8531a8c78dd6eede483d3440e9457d839";
+        let address = celestia_address_from_hex(key).unwrap();
+        assert!(address.starts_with("celestia1"), "{}", address);
+        assert_eq!(address.parse::<AccAddress>().unwrap().to_string(), address);
+        assert_ne!(address, celestia_address_from_hex(&"11".repeat(32)).unwrap());
+        assert!(celestia_address_from_hex(&"00".repeat(32)).is_err());
+    }
 }
 
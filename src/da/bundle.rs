@@ -0,0 +1,226 @@
+use anyhow::{anyhow, bail};
+use tracing::warn;
+use ed25519_dalek::{Signature, VerifyingKey};
+use crate::types::{AppState, Batch};
+use super::framing::{encode_blob_gzip, BlobType};
+use super::payload::{build_batch_payload, DaBatchPayload};
+use super::{ChainAnchor, DaSigner};
+
+/// Prefix of the signed message, so a batch signature can't be replayed as another message type
+const BATCH_DOMAIN: &[u8] = b"da-reader/batch/v1";
+
+/// The batch payload as it is hashed and signed
+///
+/// Stamped with the window end rather than the posting time, and without a content
+/// hash or signature, so identical batches yield byte-identical payloads.
+pub fn canonical_batch_payload(batch: &Batch, reader_id: &str, namespace: &str) -> DaBatchPayload {
+    let mut batch = batch.clone();
+    batch.content_hash = None;
+    batch.da_commitment = None;
+    batch.chain_anchor = None;
+    let end = batch.window.end;
+    build_batch_payload(&batch, reader_id, namespace, end)
+}
+
+/// blake3 of the canonical batch payload, as hex
+pub fn batch_content_hash(batch: &Batch, reader_id: &str, namespace: &str) -> anyhow::Result<String> {
+    let canonical = serde_json::to_vec(&canonical_batch_payload(batch, reader_id, namespace))?;
+    Ok(blake3::hash(&canonical).to_hex().to_string())
+}
+
+/// Encode a batch as a signed, gzipped blob: the canonical payload plus its content hash,
+/// an ed25519 signature over that hash and the optional ZK proof (verifiable against the
+/// batch on its own, so not signed), framed like any other blob
+///
+/// Deterministic (gzip stamps no mtime, ed25519 signatures are deterministic), so the
+/// same batch always yields the same blob and hence the same commitment.
+pub fn encode_batch_bundle(
+    batch: &Batch,
+    reader_id: &str,
+    namespace: &str,
+    proof: Option<&[u8]>,
+    signer: &DaSigner,
+    with_header: bool,
+    compression_level: u32,
+) -> anyhow::Result<Vec<u8>> {
+    let content_hash = batch_content_hash(batch, reader_id, namespace)?;
+    let mut payload = canonical_batch_payload(batch, reader_id, namespace);
+    payload.signature = signer
+        .sign(&batch_message(&content_hash, None))
+        .map(|sig| hex::encode(sig.to_bytes()));
+    payload.proof = proof.map(hex::encode);
+    payload.batch.content_hash = Some(content_hash);
+
+    encode_blob_gzip(BlobType::Batch, &payload, with_header, compression_level)
+}
+
+/// Message a batch signature covers: the content hash, then the chain anchor's height and
+/// time (big-endian) when the blob carries one (only blobs from readers that stamped the
+/// pre-submit chain head do)
+pub fn batch_message(content_hash: &str, anchor: Option<&ChainAnchor>) -> Vec<u8> {
+    let mut message = BATCH_DOMAIN.to_vec();
+    message.extend_from_slice(content_hash.as_bytes());
+    if let Some(anchor) = anchor {
+        message.extend_from_slice(&anchor.height.to_be_bytes());
+        message.extend_from_slice(&anchor.time.to_be_bytes());
+    }
+    message
+}
+
+/// Check a decoded batch blob against the posting reader's key
+///
+/// The content hash is recomputed from the payload, so a blob whose batch was altered
+/// after signing fails even if it carries the original hash and signature.
+pub fn verify_batch_payload(payload: &DaBatchPayload, key: &VerifyingKey) -> anyhow::Result<()> {
+    let content_hash = batch_content_hash(&payload.batch, &payload.reader_id, &payload.namespace)?;
+    if payload.batch.content_hash.as_deref() != Some(content_hash.as_str()) {
+        bail!("batch {}..{} does not match its content hash", payload.batch.window.start, payload.batch.window.end);
+    }
+    let signature = payload
+        .signature
+        .as_deref()
+        .ok_or_else(|| anyhow!("batch {}..{} is unsigned", payload.batch.window.start, payload.batch.window.end))?;
+    let signature = Signature::from_slice(&hex::decode(signature)?)?;
+    key.verify_strict(&batch_message(&content_hash, payload.chain_anchor.as_ref()), &signature)
+        .map_err(|_| anyhow!("batch {}..{} has an invalid signature", payload.batch.window.start, payload.batch.window.end))
+}
+
+/// Post a batch attestation, with its ZK proof if there is one, to DA as a signed,
+/// compressed bundle
+///
+/// Returns the blob commitment, which is stable for a given batch, proof and namespace,
+/// and with `da_posting.chain_time` the height and block time of the block that included
+/// the blob. The blob can't carry its own inclusion block, so the anchor goes into the
+/// batch record instead (`Batch::chain_anchor`); it is `None` if the block's header
+/// couldn't be read, as the blob is posted by then and must not be retried.
+pub async fn post_batch_to_da(
+    batch: &Batch,
+    proof: Option<&[u8]>,
+    namespace: &str,
+    state: &AppState,
+) -> anyhow::Result<(String, Option<ChainAnchor>)> {
+    let config = &state.config;
+    let blob = encode_batch_bundle(
+        batch,
+        &config.da_posting.reader_id,
+        namespace,
+        proof,
+        &state.da_signer,
+        config.da_posting.blob_header,
+        config.storage.compression_level,
+    )?;
+    let inclusion = state.da_poster.submit(&state.da_signer, namespace, blob).await?;
+    let anchor = if config.da_posting.chain_time {
+        match state.da_poster.header_at(inclusion.height).await {
+            Ok(anchor) => Some(anchor),
+            Err(e) => {
+                warn!("Couldn't read the header of inclusion height {} for da_posting.chain_time: {}", inclusion.height, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    Ok((inclusion.commitment, anchor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use ed25519_dalek::{Signature, VerifyingKey};
+    use crate::config::Config;
+    use crate::da::{decode_blob, DaPoster};
+    use crate::da::framing::DecodedBlob;
+
+    fn batch(good: usize) -> Batch {
+        Batch { good, ..Batch::sample() }
+    }
+
+    #[tokio::test]
+    async fn test_identical_batches_share_content_hash_and_commitment() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let hash = |b: &Batch| batch_content_hash(b, "reader-1", "0x2N1CE").unwrap();
+
+        assert_eq!(hash(&batch(19)), hash(&batch(19)));
+        assert_ne!(hash(&batch(19)), hash(&batch(18)));
+        // An already-enriched batch hashes the same as its bare form
+        let enriched = Batch { content_hash: Some(hash(&batch(19))), ..batch(19) };
+        assert_eq!(hash(&enriched), hash(&batch(19)));
+
+        let (first, _) = post_batch_to_da(&batch(19), None, "0x2N1CE", &state).await.unwrap();
+        let (second, _) = post_batch_to_da(&enriched, None, "0x2N1CE", &state).await.unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_bundle_decodes_with_verifiable_signature() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        post_batch_to_da(&batch(19), None, &state.config.celestia.namespace, &state).await.unwrap();
+
+        let DaPoster::Mock(mock) = state.da_poster.as_ref() else {
+            panic!("tests use the mock poster");
+        };
+        let DecodedBlob::Batch(payload) = decode_blob(&mock.submitted()[0].data).unwrap() else {
+            panic!("expected a batch blob");
+        };
+        let content_hash = payload.batch.content_hash.unwrap();
+        let (reader_id, namespace) = (&state.config.da_posting.reader_id, &state.config.celestia.namespace);
+        assert_eq!(content_hash, batch_content_hash(&batch(19), reader_id, namespace).unwrap());
+
+        let key_bytes: [u8; 32] = hex::decode(state.da_signer.public_key_hex().unwrap()).unwrap().try_into().unwrap();
+        let key = VerifyingKey::from_bytes(&key_bytes).unwrap();
+        let signature = Signature::from_slice(&hex::decode(payload.signature.unwrap()).unwrap()).unwrap();
+        key.verify_strict(&batch_message(&content_hash, None), &signature).unwrap();
+        assert!(payload.proof.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_proof_travels_with_batch() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let namespace = state.config.celestia.namespace.clone();
+        let (bare, _) = post_batch_to_da(&batch(19), None, &namespace, &state).await.unwrap();
+        let (proven, _) = post_batch_to_da(&batch(19), Some(&[0xca, 0xfe]), &namespace, &state).await.unwrap();
+        assert_ne!(bare, proven);
+
+        let DaPoster::Mock(mock) = state.da_poster.as_ref() else {
+            panic!("tests use the mock poster");
+        };
+        let DecodedBlob::Batch(payload) = decode_blob(&mock.submitted()[1].data).unwrap() else {
+            panic!("expected a batch blob");
+        };
+        assert_eq!(payload.proof.as_deref(), Some("cafe"));
+        // The proof doesn't change what the batch hashes (and signs) to
+        let (reader_id, namespace) = (&state.config.da_posting.reader_id, &state.config.celestia.namespace);
+        assert_eq!(payload.batch.content_hash.unwrap(), batch_content_hash(&batch(19), reader_id, namespace).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_posted_batch_anchored_at_inclusion_height() {
+        let mut config = Config::for_tests();
+        config.da_posting.chain_time = true;
+        let state = AppState::new(Arc::new(config));
+        let namespace = state.config.celestia.namespace.clone();
+        // The mock chain includes each blob in a block of its own
+        let (_, first) = post_batch_to_da(&batch(19), None, &namespace, &state).await.unwrap();
+        let (_, second) = post_batch_to_da(&batch(18), None, &namespace, &state).await.unwrap();
+        assert_eq!(first.map(|a| a.height), Some(1));
+        let anchor = second.unwrap();
+        assert_eq!(anchor.height, 2);
+        assert!(anchor.time > 0);
+
+        // The blob itself is unchanged by anchoring: same bytes, signature over the hash alone
+        let DaPoster::Mock(mock) = state.da_poster.as_ref() else {
+            panic!("tests use the mock poster");
+        };
+        let DecodedBlob::Batch(payload) = decode_blob(&mock.submitted()[1].data).unwrap() else {
+            panic!("expected a batch blob");
+        };
+        assert!(payload.chain_anchor.is_none());
+        let key = crate::da::parse_public_key(&state.da_signer.public_key_hex().unwrap()).unwrap();
+        verify_batch_payload(&payload, &key).unwrap();
+
+        let unanchored = AppState::new(Arc::new(Config::for_tests()));
+        assert_eq!(post_batch_to_da(&batch(19), None, &namespace, &unanchored).await.unwrap().1, None);
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+use celestia_client::tx::TxConfig;
+use celestia_client::types::{AppVersion, Blob};
+use celestia_client::Client;
+use tokio::sync::Mutex;
+use tracing::info;
+use crate::config::CelestiaConfig;
+use super::{namespace_from_hex, ChainAnchor, DaSigner, Inclusion};
+
+/// Submits blobs straight to a Celestia node (`poster_mode = "real"`)
+///
+/// Reads go over the node's JSON-RPC (`celestia.rpc_url`); PayForBlobs transactions are
+/// signed with the reader's own key (`celestia.private_key_hex` or `mnemonic`) and
+/// broadcast over gRPC (`celestia.grpc_url`). The client connects on first use, so a node
+/// that is down at startup only fails the posts made while it is down.
+///
+/// The key is read from the shared `DaSigner` on every use, and the client is rebuilt
+/// once POST /admin/reload-key swaps it.
+pub struct CelestiaPoster {
+    rpc_url: String,
+    grpc_url: String,
+    auth_token: Option<String>,
+    signer: Arc<DaSigner>,
+    /// Connected client and the private key (hex) it signs with
+    client: Mutex<Option<(String, Arc<Client>)>>,
+}
+
+impl std::fmt::Debug for CelestiaPoster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CelestiaPoster")
+            .field("rpc_url", &self.rpc_url)
+            .field("grpc_url", &self.grpc_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CelestiaPoster {
+    pub fn new(config: &CelestiaConfig, signer: Arc<DaSigner>) -> Self {
+        Self {
+            rpc_url: config.rpc_url.clone(),
+            grpc_url: config.grpc_url.clone(),
+            auth_token: config.auth_token.clone(),
+            signer,
+            client: Mutex::new(None),
+        }
+    }
+
+    /// Client signing with the signer's current key, connecting (again) if the key changed
+    async fn client(&self) -> anyhow::Result<Arc<Client>> {
+        let private_key = self
+            .signer
+            .private_key_hex()
+            .ok_or_else(|| anyhow::anyhow!("no usable Celestia signing key (see celestia.private_key_hex / mnemonic)"))?;
+        let mut cached = self.client.lock().await;
+        if let Some((key, client)) = cached.as_ref() {
+            if *key == private_key {
+                return Ok(client.clone());
+            }
+        }
+
+        let mut builder = Client::builder()
+            .rpc_url(&self.rpc_url)
+            .grpc_url(&self.grpc_url)
+            .private_key_hex(&private_key);
+        if let Some(token) = &self.auth_token {
+            builder = builder.rpc_auth_token(token);
+        }
+        let client = Arc::new(builder.build().await?);
+        info!("🔗 Connected to Celestia node at {} (chain {})", self.rpc_url, client.chain_id());
+        *cached = Some((private_key, client.clone()));
+        Ok(client)
+    }
+
+    /// Submit a single blob in a PayForBlobs transaction, returning its commitment and
+    /// inclusion height
+    pub async fn submit(&self, namespace: &str, data: Vec<u8>) -> anyhow::Result<Inclusion> {
+        let blob = Blob::new(namespace_from_hex(namespace)?, data, None, AppVersion::latest())?;
+        let commitment = hex::encode(blob.commitment.hash());
+        let len = blob.data.len();
+
+        let tx = self.client().await?.blob().submit(&[blob], TxConfig::default()).await?;
+        info!("📡 Celestia included {} byte blob at height {} (tx {}, commitment {})",
+              len, tx.height.value(), tx.hash, commitment);
+        Ok(Inclusion { commitment, height: tx.height.value() })
+    }
+
+    /// Height and block time of the network head
+    pub async fn network_head(&self) -> anyhow::Result<ChainAnchor> {
+        let header = self.client().await?.header().network_head().await?;
+        Ok(ChainAnchor {
+            height: header.height().value(),
+            time: header.time().unix_timestamp().max(0) as u64,
+        })
+    }
+
+    /// Height and block time of the block at `height`
+    pub async fn header_at(&self, height: u64) -> anyhow::Result<ChainAnchor> {
+        let header = self.client().await?.header().get_by_height(height).await?;
+        Ok(ChainAnchor {
+            height: header.height().value(),
+            time: header.time().unix_timestamp().max(0) as u64,
+        })
+    }
+
+    /// Data of every blob in `namespace` at `height`
+    pub async fn blobs_at(&self, namespace: &str, height: u64) -> anyhow::Result<Vec<Vec<u8>>> {
+        let blobs = self
+            .client()
+            .await?
+            .blob()
+            .get_all(height, &[namespace_from_hex(namespace)?])
+            .await?;
+        Ok(blobs.unwrap_or_default().into_iter().map(|blob| blob.data).collect())
+    }
+}
@@ -0,0 +1,203 @@
+use anyhow::{anyhow, bail};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use super::payload::DaSamplePayload;
+use super::DaSigner;
+
+/// Domain separator so a sample signature can't be replayed as any other signed message
+const CHAIN_DOMAIN: &[u8] = b"da-reader/sample-chain/v2";
+
+/// Numbers and links posted samples: each carries a sequence number and a signature over
+/// every other field of the blob, including `prev_sig`, so the per-sample DA layer is
+/// ordered, tamper-evident and gap-detectable
+///
+/// The head of the chain is persisted (`storage::save_sample_chains`) so a restart
+/// continues the sequence instead of starting a new chain a verifier can't link.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampleChain {
+    next_seq: u64,
+    prev_sig: Option<String>,
+}
+
+impl SampleChain {
+    /// Stamp the next sequence number on `payload` and sign it with `signer`
+    ///
+    /// Without a signing key the payload is numbered but unsigned. The chain itself only
+    /// moves on once `advance` confirms the payload was posted, so a failed post leaves
+    /// no gap.
+    pub fn link(&self, payload: &mut DaSamplePayload, signer: &DaSigner) {
+        payload.seq = Some(self.next_seq);
+        payload.prev_sig = self.prev_sig.clone();
+
+        let message = chain_message(payload);
+        payload.signature = signer.sign(&message).map(|sig| hex::encode(sig.to_bytes()));
+    }
+
+    /// Make `payload`, as stamped by `link`, the head of the chain
+    pub fn advance(&mut self, payload: &DaSamplePayload) {
+        self.next_seq = payload.seq.map_or(self.next_seq, |seq| seq + 1);
+        if payload.signature.is_some() {
+            self.prev_sig = payload.signature.clone();
+        }
+    }
+}
+
+/// Bytes signed for a chained sample: every payload field but the signature, strings
+/// length-prefixed and optional fields behind a presence byte so no two payloads share
+/// a message
+fn chain_message(payload: &DaSamplePayload) -> Vec<u8> {
+    fn push_str(message: &mut Vec<u8>, value: &str) {
+        message.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        message.extend_from_slice(value.as_bytes());
+    }
+    let mut message = CHAIN_DOMAIN.to_vec();
+    push_str(&mut message, &payload.payload_type);
+    push_str(&mut message, &payload.reader_id);
+    message.extend_from_slice(&payload.seq.unwrap_or_default().to_be_bytes());
+    message.extend_from_slice(&payload.timestamp.to_be_bytes());
+    message.push(payload.ok as u8);
+    message.extend_from_slice(&payload.prior_repeats.to_be_bytes());
+    for field in [&payload.reason, &payload.prev_sig] {
+        match field {
+            Some(value) => {
+                message.push(1);
+                push_str(&mut message, value);
+            }
+            None => message.push(0),
+        }
+    }
+    message
+}
+
+/// Missing sequence numbers between two posted samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeqGap {
+    /// Last sequence number seen before the gap
+    pub after: u64,
+    /// First sequence number seen after it
+    pub next: u64,
+}
+
+/// Result of checking a sample chain
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ChainVerification {
+    pub verified: usize,
+    pub gaps: Vec<SeqGap>,
+}
+
+/// Verify `samples` (in posting order) against the reader's public key
+///
+/// Every signature must check out, and without a gap each sample's `prev_sig` must be
+/// the previous sample's signature. Gaps (missing sequence numbers) are reported rather
+/// than rejected, since a link can't be checked across a sample that isn't there.
+pub fn verify_sample_chain(samples: &[DaSamplePayload], public_key_hex: &str) -> anyhow::Result<ChainVerification> {
+    let key = parse_public_key(public_key_hex)?;
+
+    let mut result = ChainVerification::default();
+    let mut previous: Option<&DaSamplePayload> = None;
+    for sample in samples {
+        let seq = verify_sample(sample, &key)?;
+
+        if let Some(prev) = previous {
+            let prev_seq = prev.seq.unwrap_or_default();
+            if seq <= prev_seq {
+                bail!("sample {} does not follow sample {}", seq, prev_seq);
+            }
+            if seq == prev_seq + 1 {
+                if sample.prev_sig != prev.signature {
+                    bail!("sample {} is not linked to sample {}", seq, prev_seq);
+                }
+            } else {
+                result.gaps.push(SeqGap { after: prev_seq, next: seq });
+            }
+        }
+        result.verified += 1;
+        previous = Some(sample);
+    }
+    Ok(result)
+}
+
+/// Check one chained sample's signature against the reader's key, returning its sequence number
+pub fn verify_sample(sample: &DaSamplePayload, key: &VerifyingKey) -> anyhow::Result<u64> {
+    let seq = sample.seq.ok_or_else(|| anyhow!("sample at {} has no sequence number", sample.timestamp))?;
+    let signature = sample
+        .signature
+        .as_deref()
+        .ok_or_else(|| anyhow!("sample {} is unsigned", seq))?;
+    let signature = Signature::from_slice(&hex::decode(signature)?)?;
+    key.verify_strict(&chain_message(sample), &signature)
+        .map_err(|_| anyhow!("sample {} has an invalid signature", seq))?;
+    Ok(seq)
+}
+
+/// Parse a reader's ed25519 public key, as published on `GET /identity`
+pub fn parse_public_key(public_key_hex: &str) -> anyhow::Result<VerifyingKey> {
+    let key_bytes: [u8; 32] = hex::decode(public_key_hex)?
+        .try_into()
+        .map_err(|_| anyhow!("public key must be 32 bytes"))?;
+    Ok(VerifyingKey::from_bytes(&key_bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::da::payload::build_sample_payload;
+    use crate::types::SampleBit;
+
+    fn signed_chain(len: u64) -> (Vec<DaSamplePayload>, String) {
+        let signer = DaSigner::from_config(&Config::for_tests().celestia);
+        let mut chain = SampleChain::default();
+        let samples = (0..len)
+            .map(|i| {
+                let bit = SampleBit { timestamp: 1000 + i * 30, ok: i != 2, reason: format!("r{}", i), ..Default::default() };
+                let mut payload = build_sample_payload(&bit, "reader-1", true);
+                chain.link(&mut payload, &signer);
+                chain.advance(&payload);
+                payload
+            })
+            .collect();
+        (samples, signer.public_key_hex().unwrap())
+    }
+
+    #[test]
+    fn test_chain_verifies_and_detects_gap() {
+        let (mut samples, public_key) = signed_chain(5);
+        assert_eq!(samples[0].seq, Some(0));
+        assert_eq!(samples[0].prev_sig, None);
+        assert_eq!(samples[1].prev_sig, samples[0].signature);
+
+        let full = verify_sample_chain(&samples, &public_key).unwrap();
+        assert_eq!(full, ChainVerification { verified: 5, gaps: Vec::new() });
+
+        // Sample 2 never made it on-chain
+        samples.remove(2);
+        let gapped = verify_sample_chain(&samples, &public_key).unwrap();
+        assert_eq!(gapped.verified, 4);
+        assert_eq!(gapped.gaps, [SeqGap { after: 1, next: 3 }]);
+    }
+
+    #[test]
+    fn test_tampered_or_reordered_chain_rejected() {
+        let (samples, public_key) = signed_chain(3);
+
+        let mut tampered = samples.clone();
+        tampered[1].ok = !tampered[1].ok;
+        assert!(verify_sample_chain(&tampered, &public_key).unwrap_err().to_string().contains("invalid signature"));
+
+        // Fields outside (seq, timestamp, ok, reason) are covered too
+        let mut tampered = samples.clone();
+        tampered[1].prior_repeats = 5;
+        assert!(verify_sample_chain(&tampered, &public_key).is_err());
+        let mut tampered = samples.clone();
+        tampered[1].reader_id = "reader-2".to_string();
+        assert!(verify_sample_chain(&tampered, &public_key).is_err());
+
+        let reordered = vec![samples[1].clone(), samples[0].clone()];
+        assert!(verify_sample_chain(&reordered, &public_key).is_err());
+
+        let (other, _) = signed_chain(1);
+        let spliced = vec![samples[0].clone(), samples[1].clone(), other[0].clone()];
+        assert!(verify_sample_chain(&spliced, &public_key).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use super::payload::DaSamplePayload;
+
+/// Collapses runs of identical consecutive sample blobs (`da_posting.dedup_consecutive`)
+///
+/// Samples are identical when their content hash (ok + reason) matches the last posted
+/// one. Repeats are not posted; the next differing sample carries the run length in
+/// `prior_repeats`, so the full history can still be replayed from the blobs.
+#[derive(Debug, Default)]
+pub struct SampleDedup {
+    last_posted: Option<blake3::Hash>,
+    skipped: u64,
+}
+
+impl SampleDedup {
+    /// The payload to post, or `None` if it repeats the last posted sample
+    ///
+    /// The run only resets once `posted` confirms the payload landed, so a failed post
+    /// leaves the next payload's `prior_repeats` pointing at the last blob that did.
+    pub fn filter(&mut self, mut payload: DaSamplePayload) -> Option<DaSamplePayload> {
+        if self.last_posted == Some(content_hash(&payload)) {
+            self.skipped += 1;
+            return None;
+        }
+        payload.prior_repeats = self.skipped;
+        Some(payload)
+    }
+
+    /// Record that `payload`, as returned by `filter`, was posted
+    pub fn posted(&mut self, payload: &DaSamplePayload) {
+        self.last_posted = Some(content_hash(payload));
+        self.skipped = 0;
+    }
+}
+
+/// Hash of what a sample blob says, ignoring when it was taken
+fn content_hash(payload: &DaSamplePayload) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[payload.ok as u8]);
+    hasher.update(payload.reason.as_deref().unwrap_or_default().as_bytes());
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::da::payload::build_sample_payload;
+    use crate::types::SampleBit;
+
+    fn payload(timestamp: u64, ok: bool, reason: &str) -> DaSamplePayload {
+        let bit = SampleBit { timestamp, ok, reason: reason.to_string(), ..Default::default() };
+        build_sample_payload(&bit, "reader-1", true)
+    }
+
+    #[test]
+    fn test_identical_consecutive_samples_posted_once() {
+        let mut dedup = SampleDedup::default();
+        let samples = [
+            payload(0, true, "ok (fresh data)"),
+            payload(30, true, "ok (fresh data)"),
+            payload(60, true, "ok (fresh data)"),
+            payload(90, false, "stale (age > 120s)"),
+            payload(120, true, "ok (fresh data)"),
+        ];
+        let mut posted = Vec::new();
+        for sample in samples {
+            if let Some(payload) = dedup.filter(sample) {
+                dedup.posted(&payload);
+                posted.push(payload);
+            }
+        }
+
+        assert_eq!(posted.iter().map(|p| p.timestamp).collect::<Vec<_>>(), [0, 90, 120]);
+        // The post after a run records how many repeats it replaces
+        assert_eq!(posted[0].prior_repeats, 0);
+        assert_eq!(posted[1].prior_repeats, 2);
+        assert_eq!(posted[2].prior_repeats, 0);
+    }
+
+    #[test]
+    fn test_same_ok_with_different_reason_is_posted() {
+        let mut dedup = SampleDedup::default();
+        let first = dedup.filter(payload(0, false, "head stuck at 100")).unwrap();
+        dedup.posted(&first);
+        assert!(dedup.filter(payload(30, false, "stale (age > 120s)")).is_some());
+    }
+
+    #[test]
+    fn test_failed_post_does_not_end_run() {
+        let mut dedup = SampleDedup::default();
+        let first = dedup.filter(payload(0, true, "ok")).unwrap();
+        dedup.posted(&first);
+        assert!(dedup.filter(payload(30, true, "ok")).is_none());
+        // Offered but never posted: the run of "ok" repeats goes on
+        assert!(dedup.filter(payload(60, false, "stale")).is_some());
+        assert!(dedup.filter(payload(90, true, "ok")).is_none());
+
+        let next = dedup.filter(payload(120, false, "stale")).unwrap();
+        assert_eq!(next.prior_repeats, 2);
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{anyhow, bail};
+use flate2::read::GzDecoder;
+use serde::{Serialize, de::DeserializeOwned};
+use std::io::Read;
+use crate::storage::gzip_compress;
+use super::payload::{DaBatchPayload, DaManifestPayload, DaSamplePayload};
+
+/// First byte of a framed blob; can't start a JSON document, so legacy blobs stay parseable
+pub const BLOB_MAGIC: u8 = 0xDA;
+/// Version of the header + JSON body layout
+pub const BLOB_FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = 3;
+/// First bytes of a gzip stream; like `BLOB_MAGIC`, they can't start a JSON document
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Type tag in the blob header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum BlobType {
+    Sample = 1,
+    Batch = 2,
+    Manifest = 3,
+}
+
+impl BlobType {
+    fn from_tag(tag: u8) -> anyhow::Result<Self> {
+        match tag {
+            1 => Ok(BlobType::Sample),
+            2 => Ok(BlobType::Batch),
+            3 => Ok(BlobType::Manifest),
+            other => bail!("unknown blob type tag {}", other),
+        }
+    }
+}
+
+/// A blob parsed by `decode_blob`
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum DecodedBlob {
+    Sample(DaSamplePayload),
+    Batch(Box<DaBatchPayload>),
+    Manifest(DaManifestPayload),
+}
+
+/// Serialize a payload as a blob: `[magic, version, type]` followed by the JSON payload
+///
+/// Without `with_header` (`da_posting.blob_header = false`) the blob is the bare JSON.
+pub fn encode_blob<T: Serialize>(blob_type: BlobType, payload: &T, with_header: bool) -> anyhow::Result<Vec<u8>> {
+    let mut blob = Vec::new();
+    if with_header {
+        blob.extend_from_slice(&[BLOB_MAGIC, BLOB_FORMAT_VERSION, blob_type as u8]);
+    }
+    serde_json::to_writer(&mut blob, payload)?;
+    Ok(blob)
+}
+
+/// Like `encode_blob`, but with the JSON payload gzipped at `level` (used for batch bundles)
+pub fn encode_blob_gzip<T: Serialize>(
+    blob_type: BlobType,
+    payload: &T,
+    with_header: bool,
+    level: u32,
+) -> anyhow::Result<Vec<u8>> {
+    let json = serde_json::to_vec(payload)?;
+    let mut blob = Vec::new();
+    if with_header {
+        blob.extend_from_slice(&[BLOB_MAGIC, BLOB_FORMAT_VERSION, blob_type as u8]);
+    }
+    blob.extend(gzip_compress(&json, level)?);
+    Ok(blob)
+}
+
+/// Parse a blob posted by any reader version, dispatching on its header
+///
+/// Unframed (bare JSON) blobs are dispatched on their `type` field; batch payloads
+/// predate that field, so JSON without one is read as a batch. Gzipped payloads
+/// (framed or not) are inflated first.
+pub fn decode_blob(blob: &[u8]) -> anyhow::Result<DecodedBlob> {
+    match blob {
+        [BLOB_MAGIC, BLOB_FORMAT_VERSION, tag, body @ ..] if body.starts_with(&GZIP_MAGIC) => {
+            decode_body(BlobType::from_tag(*tag)?, &gunzip(body)?)
+        }
+        [BLOB_MAGIC, BLOB_FORMAT_VERSION, tag, body @ ..] => decode_body(BlobType::from_tag(*tag)?, body),
+        [BLOB_MAGIC, version, ..] if blob.len() >= HEADER_LEN => {
+            bail!("unsupported blob format version {} (this reader understands {})", version, BLOB_FORMAT_VERSION)
+        }
+        [BLOB_MAGIC, ..] => bail!("truncated blob header"),
+        _ if blob.starts_with(&GZIP_MAGIC) => decode_blob(&gunzip(blob)?),
+        _ => {
+            let json: serde_json::Value = serde_json::from_slice(blob)
+                .map_err(|e| anyhow!("blob is neither framed nor JSON: {}", e))?;
+            let blob_type = match json.get("type").and_then(|t| t.as_str()) {
+                Some("sample") => BlobType::Sample,
+                Some("manifest") => BlobType::Manifest,
+                Some(other) => bail!("unknown blob type {:?}", other),
+                None => BlobType::Batch,
+            };
+            decode_body(blob_type, blob)
+        }
+    }
+}
+
+fn gunzip(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut inflated = Vec::new();
+    GzDecoder::new(data).read_to_end(&mut inflated)?;
+    Ok(inflated)
+}
+
+fn decode_body(blob_type: BlobType, body: &[u8]) -> anyhow::Result<DecodedBlob> {
+    fn parse<T: DeserializeOwned>(body: &[u8]) -> anyhow::Result<T> {
+        Ok(serde_json::from_slice(body)?)
+    }
+    Ok(match blob_type {
+        BlobType::Sample => DecodedBlob::Sample(parse(body)?),
+        BlobType::Batch => DecodedBlob::Batch(parse(body)?),
+        BlobType::Manifest => DecodedBlob::Manifest(parse(body)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::da::{build_batch_payload, build_manifest_payload};
+    use crate::da::payload::build_sample_payload;
+    use crate::types::{Batch, SampleBit};
+
+    fn batch() -> Batch {
+        Batch::sample()
+    }
+
+    #[test]
+    fn test_header_present_and_round_trips_for_each_type() {
+        let bit = SampleBit { timestamp: 1729785600, ok: true, reason: "+2 blocks".to_string(), ..Default::default() };
+        let sample = build_sample_payload(&bit, "reader-1", true);
+        let batch = build_batch_payload(&batch(), "reader-1", "0x2N1CE", 1729786200);
+        let manifest = build_manifest_payload(&Config::for_tests(), 1729785600);
+
+        let framed = encode_blob(BlobType::Sample, &sample, true).unwrap();
+        assert_eq!(framed[..3], [BLOB_MAGIC, BLOB_FORMAT_VERSION, BlobType::Sample as u8]);
+        let DecodedBlob::Sample(decoded) = decode_blob(&framed).unwrap() else { panic!("expected a sample") };
+        assert_eq!(decoded.timestamp, sample.timestamp);
+        assert_eq!(decoded.reason, sample.reason);
+
+        let framed = encode_blob(BlobType::Batch, &batch, true).unwrap();
+        assert_eq!(framed[..3], [BLOB_MAGIC, BLOB_FORMAT_VERSION, BlobType::Batch as u8]);
+        let DecodedBlob::Batch(decoded) = decode_blob(&framed).unwrap() else { panic!("expected a batch") };
+        assert_eq!(decoded.batch.good, 19);
+
+        let framed = encode_blob(BlobType::Manifest, &manifest, true).unwrap();
+        assert_eq!(framed[..3], [BLOB_MAGIC, BLOB_FORMAT_VERSION, BlobType::Manifest as u8]);
+        let DecodedBlob::Manifest(decoded) = decode_blob(&framed).unwrap() else { panic!("expected a manifest") };
+        assert_eq!(decoded, manifest);
+    }
+
+    #[test]
+    fn test_unframed_blobs_still_decode() {
+        let batch = build_batch_payload(&batch(), "reader-1", "0x2N1CE", 1729786200);
+        let bare = encode_blob(BlobType::Batch, &batch, false).unwrap();
+        assert_eq!(bare[0], b'{');
+        assert!(matches!(decode_blob(&bare).unwrap(), DecodedBlob::Batch(_)));
+
+        let manifest = build_manifest_payload(&Config::for_tests(), 1729785600);
+        let bare = encode_blob(BlobType::Manifest, &manifest, false).unwrap();
+        assert!(matches!(decode_blob(&bare).unwrap(), DecodedBlob::Manifest(_)));
+    }
+
+    #[test]
+    fn test_unknown_version_and_type_rejected() {
+        let err = decode_blob(&[BLOB_MAGIC, 9, 1, b'{', b'}']).unwrap_err();
+        assert!(err.to_string().contains("version 9"));
+        assert!(decode_blob(&[BLOB_MAGIC, BLOB_FORMAT_VERSION, 77]).is_err());
+        assert!(decode_blob(&[BLOB_MAGIC]).is_err());
+    }
+}
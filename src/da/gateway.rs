@@ -0,0 +1,185 @@
+use axum::body::Bytes;
+use axum::http::{header, Method, Request, Uri};
+use celestia_client::types::{AppVersion, Blob};
+use http_body_util::{BodyExt, Full};
+use hyper_util::client::legacy::{connect::HttpConnector, Client};
+use hyper_util::rt::TokioExecutor;
+use serde_json::{json, Value};
+use tracing::info;
+use chrono::DateTime;
+use super::{namespace_from_hex, ChainAnchor, Inclusion};
+
+/// Submits blobs through a light node's JSON-RPC gateway (`poster_mode = "light-gateway"`)
+///
+/// The light node signs and pays for the PFB with its own key; this reader only needs
+/// the node's auth token (`celestia.auth_token`).
+pub struct LightGatewayPoster {
+    client: Client<HttpConnector, Full<Bytes>>,
+    url: String,
+    auth_token: String,
+}
+
+impl std::fmt::Debug for LightGatewayPoster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LightGatewayPoster").field("url", &self.url).finish_non_exhaustive()
+    }
+}
+
+impl LightGatewayPoster {
+    pub fn new(url: &str, auth_token: &str) -> Self {
+        Self {
+            client: Client::builder(TokioExecutor::new()).build_http(),
+            url: url.to_string(),
+            auth_token: auth_token.to_string(),
+        }
+    }
+
+    /// `blob.Submit` a single blob, returning its commitment and inclusion height
+    pub async fn submit(&self, namespace: &str, data: Vec<u8>) -> anyhow::Result<Inclusion> {
+        let blob = Blob::new(namespace_from_hex(namespace)?, data, None, AppVersion::latest())?;
+        let commitment = hex::encode(blob.commitment.hash());
+        let len = blob.data.len();
+
+        let result = self.call("blob.Submit", json!([[blob], {}])).await?;
+        let height = result
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("no inclusion height in blob.Submit reply"))?;
+        info!("📡 Light node included {} byte blob at height {} (commitment {})", len, height, commitment);
+        Ok(Inclusion { commitment, height })
+    }
+
+    /// `header.NetworkHead`: height and block time of the chain head
+    pub async fn network_head(&self) -> anyhow::Result<ChainAnchor> {
+        let result = self.call("header.NetworkHead", json!([])).await?;
+        parse_header(&result, "header.NetworkHead")
+    }
+
+    /// `header.GetByHeight`: height and block time of the block at `height`
+    pub async fn header_at(&self, height: u64) -> anyhow::Result<ChainAnchor> {
+        let result = self.call("header.GetByHeight", json!([height])).await?;
+        parse_header(&result, "header.GetByHeight")
+    }
+
+    /// `blob.GetAll`: data of every blob in `namespace` at `height`
+    pub async fn blobs_at(&self, namespace: &str, height: u64) -> anyhow::Result<Vec<Vec<u8>>> {
+        let result = self.call("blob.GetAll", json!([height, [namespace_from_hex(namespace)?]])).await?;
+        // null when the height has no blobs in the namespace
+        let blobs: Option<Vec<Blob>> = serde_json::from_value(result)?;
+        Ok(blobs.unwrap_or_default().into_iter().map(|blob| blob.data).collect())
+    }
+
+    /// Make a JSON-RPC call, returning its `result`
+    async fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(self.url.parse::<Uri>()?)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::AUTHORIZATION, format!("Bearer {}", self.auth_token))
+            .body(Full::new(Bytes::from(body.to_string())))?;
+
+        let response = self.client.request(request).await?;
+        let status = response.status();
+        let bytes = response.into_body().collect().await?.to_bytes();
+        anyhow::ensure!(status.is_success(), "light node gateway returned {}", status);
+
+        let mut reply: Value = serde_json::from_slice(&bytes)?;
+        if let Some(error) = reply.get("error") {
+            anyhow::bail!("light node gateway error: {}", error);
+        }
+        Ok(reply["result"].take())
+    }
+}
+
+/// Height and block time of an ExtendedHeader returned by `method`
+fn parse_header(result: &Value, method: &str) -> anyhow::Result<ChainAnchor> {
+    let header = &result["header"];
+    // ExtendedHeader heights are JSON strings, times RFC 3339
+    let height = header["height"]
+        .as_str()
+        .and_then(|h| h.parse().ok())
+        .or_else(|| header["height"].as_u64())
+        .ok_or_else(|| anyhow::anyhow!("no header.height in {} reply", method))?;
+    let time = header["time"]
+        .as_str()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .ok_or_else(|| anyhow::anyhow!("no header.time in {} reply", method))?;
+    Ok(ChainAnchor { height, time: time.timestamp().max(0) as u64 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use axum::{http::HeaderMap, routing::post, Json, Router};
+
+    #[tokio::test]
+    async fn test_submit_request_shape() {
+        let seen: Arc<Mutex<Option<(HeaderMap, Value)>>> = Arc::default();
+        let recorder = seen.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |headers: HeaderMap, Json(req): Json<Value>| async move {
+                *recorder.lock().unwrap() = Some((headers, req));
+                Json(json!({ "jsonrpc": "2.0", "id": 1, "result": 8549697 }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let poster = LightGatewayPoster::new(&format!("http://{}/", addr), "secret-jwt");
+        let inclusion = poster.submit("0x00000000000000000001", b"sample".to_vec()).await.unwrap();
+        assert_eq!(inclusion.height, 8549697);
+
+        let (headers, req) = seen.lock().unwrap().take().unwrap();
+        assert_eq!(headers[header::AUTHORIZATION], "Bearer secret-jwt");
+        assert_eq!(req["method"], "blob.Submit");
+        let blob = &req["params"][0][0];
+        assert_eq!(blob["data"], "c2FtcGxl"); // base64("sample")
+        assert_eq!(blob["share_version"], 0);
+        let posted: Blob = serde_json::from_value(blob.clone()).unwrap();
+        assert_eq!(hex::encode(posted.namespace.id_v0().unwrap()), "00000000000000000001");
+        assert_eq!(hex::encode(posted.commitment.hash()), inclusion.commitment);
+    }
+
+    #[tokio::test]
+    async fn test_gateway_error_surfaces() {
+        let app = Router::new().route(
+            "/",
+            post(|| async { Json(json!({ "jsonrpc": "2.0", "id": 1, "error": { "message": "unauthorized" } })) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let poster = LightGatewayPoster::new(&format!("http://{}/", addr), "wrong");
+        let err = poster.submit("0x00000000000000000001", b"x".to_vec()).await.unwrap_err();
+        assert!(err.to_string().contains("unauthorized"));
+    }
+
+    #[tokio::test]
+    async fn test_headers_parsed() {
+        let app = Router::new().route(
+            "/",
+            post(|Json(req): Json<Value>| async move {
+                let height = match req["method"].as_str().unwrap() {
+                    "header.NetworkHead" => "8549697".to_string(),
+                    "header.GetByHeight" => req["params"][0].to_string(),
+                    method => panic!("unexpected {}", method),
+                };
+                Json(json!({ "jsonrpc": "2.0", "id": 1, "result": {
+                    "header": { "height": height, "time": "2024-10-24T16:00:00.5Z" }
+                } }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let poster = LightGatewayPoster::new(&format!("http://{}/", addr), "jwt");
+        let head = poster.network_head().await.unwrap();
+        assert_eq!(head, ChainAnchor { height: 8549697, time: 1729785600 });
+        assert_eq!(poster.header_at(8549690).await.unwrap(), ChainAnchor { height: 8549690, time: 1729785600 });
+    }
+}
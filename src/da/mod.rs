@@ -1,14 +1,155 @@
 // Data Availability layer posting functionality
 // 
-// This module will handle posting to Celestia DA:
+// This module handles posting to Celestia DA:
 // - Layer 1: Individual samples (every 30s) for detailed audit trail
 // - Layer 2: Batch attestations + ZK proofs (every 10min) for efficient verification
 //
-// TODO: Implement DA posting functions:
-// - post_sample_to_da(&sample_bit, &state) -> Result<String> // Returns blob commitment
-// - post_batch_to_da(&batch, &proof, &state) -> Result<String> // Returns blob commitment
+// Blob payloads for both layers are built in `payload` and carry the configured
+// `da_posting.reader_id` so consumers can attribute blobs when several readers share
+// a namespace. `framing` prefixes each blob with a magic byte, format version and type
+// tag (`da_posting.blob_header`); consumers parse blobs with `decode_blob`.
 //
-// These will be called from:
-// - metrics::sampler::run_sampler() for sample posting
-// - metrics::batch::run_batch_generator() for batch posting
+// Blobs go through `DaPoster` (`celestia.poster_mode`: "mock" keeps them in memory,
+// "light-gateway" submits via a light node's JSON-RPC `blob.Submit`, "real" pays for the
+// blobs itself with the `celestia.private_key_hex`/`mnemonic` key over `rpc_url`/`grpc_url`),
+// signed with the `DaSigner` key, which POST /admin/reload-key can swap at runtime.
+// At startup `post_manifest_to_da` announces the reader's configuration once.
+// `SampleDedup` can collapse runs of identical sample blobs into a run count, and
+// `SampleChain` numbers and signs posted samples so verifiers can detect tampering and gaps;
+// each layer's chain head is persisted so the sequence continues across restarts.
+// `post_batch_to_da` posts batches (and their ZK proof, if any) as gzipped bundles signed
+// over a content hash of the canonical payload, so the same batch always yields the same
+// commitment; with `da_posting.chain_time` the batch record is anchored to the block that
+// included it. Batches whose post fails wait in a `PendingDaQueue`, retried every
+// window and pruned by length and age; posted commitments land in `batch.da_commitment`.
+// Which layers exist is configured as posting policies (`Config::posting_policies`), each
+// with its own namespace, cadence and payload format; `SampleLayer` applies a per-sample one.
+// Submissions only return once the blob is included, so a returned commitment is final.
+// In `mode = "replica"`, `run_replica` reads another reader's blobs back with `decode_blob`
+// and rebuilds samples, the ring buffer and alert state from them instead of ingesting OTLP;
+// only blobs signed with `replica.source_public_key` are applied, and a `ReplicaCursor`
+// persists how far it got.
+// `post_sample_to_da` posts each sample a `SampleLayer` emits; `spawn_sample_poster` runs the
+// layers in a background task fed by `run_sampler`, so slow submissions never delay a tick.
 
+mod payload;
+mod poster;
+mod namespace;
+mod signer;
+mod dedup;
+mod gateway;
+mod framing;
+mod chain;
+mod bundle;
+mod pending;
+mod policy;
+mod replica;
+mod celestia;
+mod sample_poster;
+
+pub use payload::{build_batch_payload, build_manifest_payload, DaManifestPayload, DaSamplePayload};
+pub use poster::DaPoster;
+pub use signer::DaSigner;
+pub use dedup::SampleDedup;
+pub use framing::{decode_blob, encode_blob, BlobType};
+pub use chain::{parse_public_key, verify_sample_chain, SampleChain};
+pub use bundle::{batch_content_hash, post_batch_to_da};
+pub use pending::PendingDaQueue;
+pub use sample_poster::spawn_sample_poster;
+pub use replica::{run_replica, ReplicaCursor};
+pub use namespace::{namespace_from_hex, namespace_from_reader_id, namespace_to_hex, AUTO_NAMESPACE};
+
+use serde::{Deserialize, Serialize};
+use crate::types::AppState;
+
+/// A DA block height and its block time (Unix seconds)
+///
+/// Anchors an attestation to chain time instead of the reader's own clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainAnchor {
+    pub height: u64,
+    pub time: u64,
+}
+
+/// Where a submitted blob landed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inclusion {
+    /// Blob commitment, as hex
+    pub commitment: String,
+    /// Height of the block that included the blob
+    pub height: u64,
+}
+
+/// Post a sample blob into `namespace`, returning the blob commitment as hex
+///
+/// Fails (instead of pretending success) when the blob can't be encoded or the poster
+/// rejects it, so the caller can log the real outcome.
+pub async fn post_sample_to_da(payload: &DaSamplePayload, namespace: &str, state: &AppState) -> anyhow::Result<String> {
+    let data = encode_blob(BlobType::Sample, payload, state.config.da_posting.blob_header)?;
+    Ok(state.da_poster.submit(&state.da_signer, namespace, data).await?.commitment)
+}
+
+/// Post the reader's manifest (namespace, cadence, metric names, threshold, reader_id)
+///
+/// Called once at startup so consumers can interpret the attestation stream that follows.
+/// Returns the manifest and its blob commitment.
+pub async fn post_manifest_to_da(state: &AppState, now: u64) -> anyhow::Result<(DaManifestPayload, String)> {
+    let manifest = build_manifest_payload(&state.config, now);
+    let data = encode_blob(BlobType::Manifest, &manifest, state.config.da_posting.blob_header)?;
+    let inclusion = state
+        .da_poster
+        .submit(&state.da_signer, &state.config.celestia.namespace, data)
+        .await?;
+    Ok((manifest, inclusion.commitment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::config::Config;
+
+    #[tokio::test]
+    async fn test_manifest_posted_via_mock_poster() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let (manifest, commitment) = post_manifest_to_da(&state, 1729785600).await.unwrap();
+
+        assert_eq!(manifest.reader_id, "test-reader");
+        assert_eq!(manifest.window_secs, state.config.batching.window_secs);
+
+        let DaPoster::Mock(mock) = state.da_poster.as_ref() else {
+            panic!("tests use the mock poster");
+        };
+        let blobs = mock.submitted();
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0].namespace, state.config.celestia.namespace);
+        assert_eq!(blobs[0].commitment, commitment);
+
+        let framing::DecodedBlob::Manifest(posted) = decode_blob(&blobs[0].data).unwrap() else {
+            panic!("expected a manifest blob");
+        };
+        assert_eq!(posted, manifest);
+        let json: serde_json::Value = serde_json::from_slice(&blobs[0].data[3..]).unwrap();
+        assert_eq!(json["type"], "manifest");
+        assert_eq!(json["head_metric"], "das_sampled_chain_head");
+    }
+
+    #[tokio::test]
+    async fn test_post_sample_returns_commitment() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let bit = crate::types::SampleBit { timestamp: 1000, ok: true, reason: "+2 blocks".to_string(), ..Default::default() };
+        let payload = payload::build_sample_payload(&bit, "test-reader", true);
+        let commitment = post_sample_to_da(&payload, "0x00000000000000000001", &state).await.unwrap();
+
+        let DaPoster::Mock(mock) = state.da_poster.as_ref() else {
+            panic!("tests use the mock poster");
+        };
+        let blobs = mock.submitted();
+        assert_eq!(blobs[0].commitment, commitment);
+        assert_eq!(blobs[0].namespace, "0x00000000000000000001");
+        let framing::DecodedBlob::Sample(posted) = decode_blob(&blobs[0].data).unwrap() else {
+            panic!("expected a sample blob");
+        };
+        assert_eq!((posted.timestamp, posted.ok, posted.reason), (1000, true, Some("+2 blocks".to_string())));
+    }
+}
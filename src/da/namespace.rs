@@ -0,0 +1,93 @@
+use celestia_client::types::nmt::{Namespace, NS_SIZE};
+
+/// `celestia.namespace` value that derives the namespace from `da_posting.reader_id`
+pub const AUTO_NAMESPACE: &str = "auto";
+
+/// Deterministic user namespace (version 0) for a reader
+///
+/// The 10-byte v0 id is the BLAKE3 hash of the reader id, truncated, so every reader
+/// in a fleet gets its own namespace without coordinating byte values.
+pub fn namespace_from_reader_id(id: &str) -> Namespace {
+    let hash = blake3::hash(id.as_bytes());
+    let mut suffix = [0u8; 10];
+    suffix.copy_from_slice(&hash.as_bytes()[..10]);
+
+    // Ids whose first 9 bytes are zero fall in the reserved range; astronomically
+    // unlikely, but keep the result valid
+    if suffix[..9].iter().all(|&b| b == 0) {
+        suffix[0] = 1;
+    }
+    Namespace::const_v0(suffix)
+}
+
+/// Parse the config representation (`0x` + hex of a 10-byte v0 id) back into a namespace
+///
+/// Shorter ids are left-padded with zeros. The full 29-byte form (version byte plus the
+/// 28-byte id, as shown by explorers) is accepted too; any other length is rejected rather
+/// than truncated.
+pub fn namespace_from_hex(namespace: &str) -> anyhow::Result<Namespace> {
+    let id = namespace
+        .strip_prefix("0x")
+        .ok_or_else(|| anyhow::anyhow!("namespace {:?} must start with 0x", namespace))?;
+    let bytes = hex::decode(id).map_err(|e| anyhow::anyhow!("namespace {:?} is not hex: {}", namespace, e))?;
+    match bytes.len() {
+        NS_SIZE => Namespace::from_raw(&bytes)
+            .map_err(|e| anyhow::anyhow!("namespace {:?} is not a valid {}-byte namespace: {}", namespace, NS_SIZE, e)),
+        len if len <= 10 => Ok(Namespace::new_v0(&bytes)?),
+        len => anyhow::bail!(
+            "namespace {:?} is {} bytes; expected a v0 id of at most 10 bytes or a full {}-byte namespace",
+            namespace, len, NS_SIZE
+        ),
+    }
+}
+
+/// Config/log representation of a v0 namespace: `0x` + hex of the 10-byte id
+pub fn namespace_to_hex(namespace: &Namespace) -> String {
+    format!("0x{}", hex::encode(namespace.id_v0().unwrap_or_else(|| namespace.id())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derived_namespace_is_deterministic_and_valid() {
+        let a = namespace_from_reader_id("da-reader-1");
+        assert_eq!(a, namespace_from_reader_id("da-reader-1"));
+        assert_ne!(a, namespace_from_reader_id("da-reader-2"));
+
+        assert_eq!(a.version(), 0);
+        assert!(!a.is_reserved());
+        // Round-trips through the validating constructor
+        assert_eq!(Namespace::new_v0(a.id_v0().unwrap()).unwrap(), a);
+        assert_eq!(namespace_to_hex(&a).len(), 2 + 20);
+        assert_eq!(namespace_from_hex(&namespace_to_hex(&a)).unwrap(), a);
+    }
+
+    #[test]
+    fn test_namespace_from_hex_rejects_malformed() {
+        assert!(namespace_from_hex("0x2N1CE").is_err());
+        assert!(namespace_from_hex("deadbeef").is_err());
+        assert!(namespace_from_hex(&format!("0x{}", "ab".repeat(11))).is_err());
+    }
+
+    #[test]
+    fn test_namespace_lengths_around_29_bytes() {
+        let expected = Namespace::const_v0([0, 0, 0, 0, 0, 0, 0, 0, 0xbe, 0xef]);
+
+        // Short ids are zero-padded to the 10-byte v0 id
+        assert_eq!(namespace_from_hex("0xbeef").unwrap(), expected);
+
+        // The full 29-byte form: version 0, 18 zero bytes, then the 10-byte id
+        let full = format!("0x00{}{}", "00".repeat(18), "0000000000000000beef");
+        assert_eq!(namespace_from_hex(&full).unwrap(), expected);
+        assert_eq!(namespace_to_hex(&namespace_from_hex(&full).unwrap()), "0x0000000000000000beef");
+
+        // 29 bytes without the mandatory zero prefix, and 30 bytes, are rejected
+        let bad_prefix = format!("0x00{}{}", "01".repeat(18), "0000000000000000beef");
+        assert!(namespace_from_hex(&bad_prefix).is_err());
+        let too_long = format!("{}00", full);
+        let err = namespace_from_hex(&too_long).unwrap_err().to_string();
+        assert!(err.contains("30 bytes"), "{}", err);
+    }
+}
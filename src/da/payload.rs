@@ -0,0 +1,169 @@
+use serde::{Serialize, Deserialize};
+use crate::config::{Config, MetricNames};
+use crate::proofs::HashAlgo;
+use crate::types::{Batch, SampleBit};
+use super::ChainAnchor;
+
+/// Layer 1 blob: a single sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaSamplePayload {
+    #[serde(rename = "type")]
+    pub payload_type: String,
+    /// Identifies which reader posted this blob
+    pub reader_id: String,
+    pub timestamp: u64,
+    pub ok: bool,
+    /// Why the sample passed or failed; omitted when `da_posting.include_reason` is off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Identical samples skipped right before this one (`da_posting.dedup_consecutive`)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub prior_repeats: u64,
+    /// Position in this reader's sample chain (see `SampleChain`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    /// Signature of the previous sample in the chain, as hex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_sig: Option<String>,
+    /// ed25519 signature over every other field (see `SampleChain`), as hex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+/// Layer 2 blob: a batch attestation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaBatchPayload {
+    /// Identifies which reader posted this blob
+    pub reader_id: String,
+    pub batch: Batch,
+    pub namespace: String,
+    pub timestamp: u64,
+    /// DA chain head stamped before submission by earlier readers with `da_posting.chain_time`;
+    /// still covered by the signature when present. Batches are now anchored at their
+    /// inclusion block instead, recorded in `Batch::chain_anchor`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_anchor: Option<ChainAnchor>,
+    /// ed25519 signature over `batch.content_hash` and `chain_anchor` (see `da::batch_message`), as hex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// ZK proof over the batch's public inputs, as hex; verifiable on its own, so not signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<String>,
+}
+
+/// One-time blob announcing how this reader's attestations should be interpreted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaManifestPayload {
+    #[serde(rename = "type")]
+    pub payload_type: String,
+    pub reader_id: String,
+    pub reader_version: String,
+    pub namespace: String,
+    pub tick_secs: u64,
+    pub window_secs: u64,
+    pub head_metric: MetricNames,
+    pub headers_metric: MetricNames,
+    /// The configured threshold; each batch carries the one it was judged against
+    /// (`Batch::threshold_percent`), which POST /admin/threshold can change at runtime
+    pub threshold_percent: f64,
+    pub hash_algo: HashAlgo,
+    /// Further algorithms batches carry in `bitmap_hashes` (`proofs.hash_algos`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hash_algos: Vec<HashAlgo>,
+    pub timestamp: u64,
+}
+
+/// Build the per-sample blob payload; without `include_reason` it carries only the timestamp and ok bit
+pub fn build_sample_payload(sample_bit: &SampleBit, reader_id: &str, include_reason: bool) -> DaSamplePayload {
+    DaSamplePayload {
+        payload_type: "sample".to_string(),
+        reader_id: reader_id.to_string(),
+        timestamp: sample_bit.timestamp,
+        ok: sample_bit.ok,
+        reason: include_reason.then(|| sample_bit.reason.clone()),
+        prior_repeats: 0,
+        seq: None,
+        prev_sig: None,
+        signature: None,
+    }
+}
+
+/// Build the batch attestation blob payload
+pub fn build_batch_payload(batch: &Batch, reader_id: &str, namespace: &str, now: u64) -> DaBatchPayload {
+    DaBatchPayload {
+        reader_id: reader_id.to_string(),
+        batch: batch.clone(),
+        namespace: namespace.to_string(),
+        timestamp: now,
+        chain_anchor: None,
+        signature: None,
+        proof: None,
+    }
+}
+
+/// Build the manifest blob payload from the running configuration
+pub fn build_manifest_payload(config: &Config, now: u64) -> DaManifestPayload {
+    DaManifestPayload {
+        payload_type: "manifest".to_string(),
+        reader_id: config.da_posting.reader_id.clone(),
+        reader_version: env!("CARGO_PKG_VERSION").to_string(),
+        namespace: config.celestia.namespace.clone(),
+        tick_secs: config.sampling.tick_secs,
+        window_secs: config.batching.window_secs,
+        head_metric: config.metrics.head_metric.clone(),
+        headers_metric: config.metrics.headers_metric.clone(),
+        threshold_percent: config.proofs.threshold_percent,
+        hash_algo: config.proofs.hash_algo,
+        hash_algos: config.proofs.hash_algos.clone(),
+        timestamp: now,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_id_in_serialized_payloads() {
+        let bit = SampleBit {
+            timestamp: 1729785600,
+            ok: true,
+            reason: "+2 blocks".to_string(),
+            ..Default::default()
+        };
+        let sample_json = serde_json::to_value(build_sample_payload(&bit, "reader-eu-1", true)).unwrap();
+        assert_eq!(sample_json["reader_id"], "reader-eu-1");
+        assert_eq!(sample_json["type"], "sample");
+
+        let batch = Batch::sample();
+        let batch_json = serde_json::to_value(build_batch_payload(&batch, "reader-eu-1", "0x2N1CE", 1729786200)).unwrap();
+        assert_eq!(batch_json["reader_id"], "reader-eu-1");
+        assert_eq!(batch_json["batch"]["good"], 19);
+    }
+
+    #[test]
+    fn test_sample_blob_shape_with_and_without_reason() {
+        let bit = SampleBit {
+            timestamp: 1729785600,
+            ok: false,
+            reason: "head stalled".to_string(),
+            ..Default::default()
+        };
+        let with_reason = serde_json::to_value(build_sample_payload(&bit, "reader-1", true)).unwrap();
+        assert_eq!(with_reason["reason"], "head stalled");
+
+        let without = serde_json::to_value(build_sample_payload(&bit, "reader-1", false)).unwrap();
+        let mut keys: Vec<&str> = without.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["ok", "reader_id", "timestamp", "type"]);
+        assert_eq!(without["ok"], false);
+
+        // Reason-less blobs still decode
+        let decoded: DaSamplePayload = serde_json::from_value(without).unwrap();
+        assert_eq!(decoded.reason, None);
+    }
+}
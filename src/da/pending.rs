@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use tracing::{info, warn};
+use crate::types::{AppState, Batch};
+use super::post_batch_to_da;
+
+/// A batch whose DA post failed, waiting to be retried
+#[derive(Debug, Clone)]
+pub struct PendingBatch {
+    pub batch: Batch,
+    /// ZK proof posted along with the batch, if one was generated
+    pub proof: Option<Vec<u8>>,
+    /// Unix seconds it was first queued
+    pub queued_at: u64,
+    /// Failed post attempts so far
+    pub attempts: u32,
+}
+
+/// Batches waiting to be posted into one namespace, oldest first, bounded by length
+/// (`da_posting.pending_max_len`) and age (`da_posting.pending_max_age_secs`)
+#[derive(Debug)]
+pub struct PendingDaQueue {
+    namespace: String,
+    entries: VecDeque<PendingBatch>,
+}
+
+impl PendingDaQueue {
+    pub fn new(namespace: impl Into<String>) -> Self {
+        Self { namespace: namespace.into(), entries: VecDeque::new() }
+    }
+
+    /// Queue a batch (and its proof, if any) for posting, evicting the oldest entries beyond `max_len`
+    pub fn push(&mut self, state: &AppState, batch: Batch, proof: Option<Vec<u8>>, now: u64) {
+        self.entries.push_back(PendingBatch { batch, proof, queued_at: now, attempts: 0 });
+        while self.entries.len() > state.config.da_posting.pending_max_len {
+            if let Some(evicted) = self.entries.pop_front() {
+                evict(state, &evicted, "queue full");
+            }
+        }
+    }
+
+    /// Drop entries queued more than `pending_max_age_secs` ago; returns how many were dropped
+    pub fn prune(&mut self, state: &AppState, now: u64) -> usize {
+        let max_age = state.config.da_posting.pending_max_age_secs;
+        let before = self.entries.len();
+        self.entries.retain(|entry| {
+            let expired = now.saturating_sub(entry.queued_at) > max_age;
+            if expired {
+                evict(state, entry, "too old");
+            }
+            !expired
+        });
+        before - self.entries.len()
+    }
+
+    /// One flusher cycle: prune over-age entries, then post the rest oldest first
+    ///
+    /// Stops at the first failure (the node is likely down) and keeps it and everything
+    /// after it for the next cycle. Returns the posted batches (with their inclusion
+    /// `chain_anchor`, if any) and their commitments.
+    pub async fn flush(&mut self, state: &AppState, now: u64) -> Vec<(Batch, String)> {
+        self.prune(state, now);
+        let mut posted = Vec::new();
+        let pending = self.entries.len();
+        while let Some(entry) = self.entries.front_mut() {
+            let result = post_batch_to_da(&entry.batch, entry.proof.as_deref(), &self.namespace, state).await;
+            state.liveness.record_da(&result);
+            match result {
+                Ok((commitment, chain_anchor)) => {
+                    let entry = self.entries.pop_front().expect("front exists");
+                    posted.push((Batch { chain_anchor, ..entry.batch }, commitment));
+                }
+                Err(e) => {
+                    entry.attempts += 1;
+                    warn!("Failed to post batch {}-{} to DA (attempt {}, {} pending): {}",
+                          entry.batch.window.start, entry.batch.window.end, entry.attempts, pending - posted.len(), e);
+                    break;
+                }
+            }
+        }
+        posted
+    }
+}
+
+fn evict(state: &AppState, entry: &PendingBatch, why: &str) {
+    state.telemetry.da_pending_evicted.fetch_add(1, Ordering::Relaxed);
+    info!("🗑️  Dropped pending DA batch {}-{} ({}, queued {}s, {} failed attempts)",
+          entry.batch.window.start, entry.batch.window.end, why, entry.queued_at, entry.attempts);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::config::Config;
+    use crate::types::TimeWindow;
+
+    fn batch(start: u64) -> Batch {
+        Batch { window: TimeWindow { start, end: start + 600 }, ..Batch::sample() }
+    }
+
+    fn state(max_len: usize, max_age_secs: u64) -> AppState {
+        let mut config = Config::for_tests();
+        config.da_posting.pending_max_len = max_len;
+        config.da_posting.pending_max_age_secs = max_age_secs;
+        AppState::new(Arc::new(config))
+    }
+
+    #[tokio::test]
+    async fn test_over_age_entry_evicted() {
+        let state = state(100, 3600);
+        let mut queue = PendingDaQueue::new("0x2N1CE");
+        queue.push(&state, batch(0), None, 1_000);
+        queue.push(&state, batch(600), None, 4_000);
+
+        // At 5000 the first entry is 4000s old, past the 3600s limit
+        let posted = queue.flush(&state, 5_000).await;
+        assert_eq!(posted.len(), 1);
+        assert_eq!(posted[0].0.window.start, 600);
+        assert_eq!(queue.entries.len(), 0);
+        assert_eq!(state.telemetry.da_pending_evicted.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_queue_bounded_by_length() {
+        let state = state(2, 3600);
+        let mut queue = PendingDaQueue::new("0x2N1CE");
+        for start in [0, 600, 1200] {
+            queue.push(&state, batch(start), None, start);
+        }
+        assert_eq!(queue.entries.len(), 2);
+        assert_eq!(queue.entries[0].batch.window.start, 600);
+        assert_eq!(state.telemetry.da_pending_evicted.load(Ordering::Relaxed), 1);
+    }
+}
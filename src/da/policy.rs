@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use crate::config::{PayloadFormat, PostingCadence, PostingPolicy};
+use crate::types::SampleBit;
+use super::payload::{build_sample_payload, DaSamplePayload};
+use super::{DaSigner, SampleChain, SampleDedup};
+
+/// A per-sample DA layer: decides which samples its policy posts and builds their blobs
+///
+/// Each layer keeps its own dedup state and sample chain, so every namespace carries a
+/// self-contained, gap-detectable sequence.
+#[derive(Debug)]
+pub struct SampleLayer {
+    pub policy: PostingPolicy,
+    seen: u64,
+    /// `ok` of the last sample this layer posted
+    prev_ok: Option<bool>,
+    dedup: SampleDedup,
+    chain: SampleChain,
+}
+
+impl SampleLayer {
+    pub fn new(policy: PostingPolicy) -> Self {
+        Self {
+            policy,
+            seen: 0,
+            prev_ok: None,
+            dedup: SampleDedup::default(),
+            chain: SampleChain::default(),
+        }
+    }
+
+    /// Continue `chain` (e.g. as persisted before a restart) instead of starting a new one
+    pub fn with_chain(mut self, chain: SampleChain) -> Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Namespace this layer posts into
+    pub fn namespace(&self) -> &str {
+        self.policy.namespace.as_deref().unwrap_or_default()
+    }
+
+    /// Offer the next sample; returns the linked payload if this layer posts it
+    ///
+    /// With `dedup` (`da_posting.dedup_consecutive`) a repeat of the last posted blob is skipped.
+    /// The returned payload only becomes the layer's last post (for change detection, dedup
+    /// and the chain) once `posted` is called with it.
+    pub fn offer(&mut self, sample_bit: &SampleBit, reader_id: &str, dedup: bool, signer: &DaSigner) -> Option<DaSamplePayload> {
+        let due = is_due(self.policy.cadence, self.policy.every_n, self.seen, self.prev_ok, sample_bit.ok);
+        self.seen += 1;
+        if !due {
+            return None;
+        }
+
+        let include_reason = self.policy.format == PayloadFormat::Sample;
+        let payload = build_sample_payload(sample_bit, reader_id, include_reason);
+        let mut payload = if dedup { self.dedup.filter(payload)? } else { payload };
+        self.chain.link(&mut payload, signer);
+        Some(payload)
+    }
+
+    /// Record that `payload`, as returned by `offer`, landed on DA
+    pub fn posted(&mut self, payload: &DaSamplePayload) {
+        self.prev_ok = Some(payload.ok);
+        self.dedup.posted(payload);
+        self.chain.advance(payload);
+    }
+}
+
+/// Whether a per-sample cadence posts the sample after `seen` earlier ones
+fn is_due(cadence: PostingCadence, every_n: Option<u64>, seen: u64, prev_ok: Option<bool>, ok: bool) -> bool {
+    match cadence {
+        PostingCadence::EverySample => true,
+        PostingCadence::EveryN => seen.is_multiple_of(every_n.unwrap_or(1).max(1)),
+        PostingCadence::OnChange => prev_ok != Some(ok),
+        PostingCadence::PerWindow => false,
+    }
+}
+
+/// Per-sample layers among `policies`, continuing the chains in `chains` (by policy name)
+pub fn sample_layers(policies: &[PostingPolicy], chains: &BTreeMap<String, SampleChain>) -> Vec<SampleLayer> {
+    policies
+        .iter()
+        .filter(|p| p.cadence != PostingCadence::PerWindow)
+        .map(|p| {
+            let chain = chains.get(&p.name).cloned().unwrap_or_default();
+            SampleLayer::new(p.clone()).with_chain(chain)
+        })
+        .collect()
+}
+
+/// Chain head of each layer, by policy name, as persisted between restarts
+pub fn chain_heads(layers: &[SampleLayer]) -> BTreeMap<String, SampleChain> {
+    layers.iter().map(|layer| (layer.policy.name.clone(), layer.chain.clone())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(name: &str, cadence: PostingCadence, every_n: Option<u64>, format: PayloadFormat) -> PostingPolicy {
+        PostingPolicy { name: name.to_string(), namespace: Some(format!("ns-{}", name)), cadence, every_n, format }
+    }
+
+    fn offer_series(layer: &mut SampleLayer, series: &[bool]) -> Vec<DaSamplePayload> {
+        let signer = DaSigner::default();
+        series
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &ok)| {
+                let bit = SampleBit { timestamp: 1000 + i as u64 * 30, ok, reason: "r".to_string(), ..Default::default() };
+                let payload = layer.offer(&bit, "reader-1", false, &signer)?;
+                layer.posted(&payload);
+                Some(payload)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_two_policies_with_different_cadences() {
+        let policies = [
+            policy("changes", PostingCadence::OnChange, None, PayloadFormat::Sample),
+            policy("sparse", PostingCadence::EveryN, Some(3), PayloadFormat::SampleCompact),
+            policy("batches", PostingCadence::PerWindow, None, PayloadFormat::Batch),
+        ];
+        let mut layers = sample_layers(&policies, &BTreeMap::new());
+        assert_eq!(layers.len(), 2);
+
+        let series = [true, true, true, false, false, true, true];
+        let changes = offer_series(&mut layers[0], &series);
+        let sparse = offer_series(&mut layers[1], &series);
+
+        // First sample, then ok→fail at 3 and fail→ok at 5
+        let at = |posted: &[DaSamplePayload]| posted.iter().map(|p| (p.timestamp - 1000) / 30).collect::<Vec<_>>();
+        assert_eq!(at(&changes), vec![0, 3, 5]);
+        assert_eq!(at(&sparse), vec![0, 3, 6]);
+
+        // Each layer numbers its own chain and uses its own payload format
+        assert_eq!(sparse.iter().map(|p| p.seq).collect::<Vec<_>>(), vec![Some(0), Some(1), Some(2)]);
+        assert!(changes.iter().all(|p| p.reason.is_some()));
+        assert!(sparse.iter().all(|p| p.reason.is_none()));
+        assert_eq!(layers[1].namespace(), "ns-sparse");
+    }
+
+    #[test]
+    fn test_restored_chain_continues_sequence() {
+        let policies = [policy("all", PostingCadence::EverySample, None, PayloadFormat::Sample)];
+        let signer = DaSigner::from_config(&crate::config::Config::for_tests().celestia);
+        let bit = |i: u64| SampleBit { timestamp: 1000 + i * 30, ok: true, reason: format!("r{}", i), ..Default::default() };
+
+        let mut before = sample_layers(&policies, &BTreeMap::new());
+        let first = before[0].offer(&bit(0), "reader-1", false, &signer).unwrap();
+        before[0].posted(&first);
+        // Restart: the persisted head carries the sequence and link over
+        let mut after = sample_layers(&policies, &chain_heads(&before));
+        let second = after[0].offer(&bit(1), "reader-1", false, &signer).unwrap();
+
+        assert_eq!(second.seq, Some(1));
+        assert_eq!(second.prev_sig, first.signature);
+        let public_key = signer.public_key_hex().unwrap();
+        let verified = crate::da::verify_sample_chain(&[first, second], &public_key).unwrap();
+        assert!(verified.gaps.is_empty());
+    }
+
+    #[test]
+    fn test_failed_post_leaves_no_gap() {
+        let mut layer = SampleLayer::new(policy("changes", PostingCadence::OnChange, None, PayloadFormat::Sample));
+        let signer = DaSigner::from_config(&crate::config::Config::for_tests().celestia);
+        let bit = |i: u64, ok| SampleBit { timestamp: 1000 + i * 30, ok, reason: "r".to_string(), ..Default::default() };
+
+        let first = layer.offer(&bit(0, true), "reader-1", true, &signer).unwrap();
+        layer.posted(&first);
+        // The ok→fail post fails, so the change is still pending on the next tick
+        let lost = layer.offer(&bit(1, false), "reader-1", true, &signer).unwrap();
+        let retried = layer.offer(&bit(2, false), "reader-1", true, &signer).unwrap();
+        layer.posted(&retried);
+
+        assert_eq!(retried.seq, lost.seq);
+        assert_eq!(retried.prev_sig, first.signature);
+        let public_key = signer.public_key_hex().unwrap();
+        let verified = crate::da::verify_sample_chain(&[first, retried], &public_key).unwrap();
+        assert!(verified.gaps.is_empty());
+    }
+
+    #[test]
+    fn test_every_sample_cadence_posts_all() {
+        let mut layer = SampleLayer::new(policy("all", PostingCadence::EverySample, None, PayloadFormat::Sample));
+        assert_eq!(offer_series(&mut layer, &[true, true, false]).len(), 3);
+    }
+}
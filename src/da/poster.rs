@@ -0,0 +1,135 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+use crate::config::{CelestiaConfig, PosterMode};
+use super::celestia::CelestiaPoster;
+use super::gateway::LightGatewayPoster;
+use super::{ChainAnchor, DaSigner, Inclusion};
+
+/// A blob handed to the mock poster (recorded in tests only)
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct SubmittedBlob {
+    pub namespace: String,
+    pub data: Vec<u8>,
+    pub commitment: String,
+    /// Public key of the signer at submission time
+    pub signer: Option<String>,
+}
+
+/// Destination for DA blobs, selected by `celestia.poster_mode`
+#[derive(Debug)]
+pub enum DaPoster {
+    /// "mock": log blobs and return a deterministic fake commitment
+    Mock(MockPoster),
+    /// "real": submit to the Celestia node, signed with the reader's key
+    Celestia(Box<CelestiaPoster>),
+    /// "light-gateway": submit through a light node's local gateway
+    LightGateway(Box<LightGatewayPoster>),
+}
+
+impl DaPoster {
+    /// Poster for `config.poster_mode`; "real" pays for blobs with whatever key `signer`
+    /// holds at the time, so POST /admin/reload-key switches the paying account too
+    pub fn from_config(config: &CelestiaConfig, signer: Arc<DaSigner>) -> Self {
+        match config.poster_mode {
+            PosterMode::Real => DaPoster::Celestia(Box::new(CelestiaPoster::new(config, signer))),
+            PosterMode::LightGateway => DaPoster::LightGateway(Box::new(LightGatewayPoster::new(
+                &config.gateway_url,
+                config.auth_token.as_deref().unwrap_or_default(),
+            ))),
+            PosterMode::Mock => DaPoster::Mock(MockPoster::default()),
+        }
+    }
+
+    /// Submit `data` under `namespace` signed by `signer`, returning where it was included
+    pub async fn submit(&self, signer: &DaSigner, namespace: &str, data: Vec<u8>) -> anyhow::Result<Inclusion> {
+        match self {
+            DaPoster::Mock(mock) => Ok(mock.submit(signer, namespace, data)),
+            DaPoster::LightGateway(gateway) => gateway.submit(namespace, data).await,
+            DaPoster::Celestia(celestia) => celestia.submit(namespace, data).await,
+        }
+    }
+
+    /// Height and time of the DA chain head
+    pub async fn chain_head(&self) -> anyhow::Result<ChainAnchor> {
+        match self {
+            DaPoster::Mock(mock) => Ok(mock.chain_head()),
+            DaPoster::LightGateway(gateway) => gateway.network_head().await,
+            DaPoster::Celestia(celestia) => celestia.network_head().await,
+        }
+    }
+
+    /// Height and block time of the block at `height`
+    pub async fn header_at(&self, height: u64) -> anyhow::Result<ChainAnchor> {
+        match self {
+            DaPoster::Mock(_) => Ok(ChainAnchor { height, time: unix_now() }),
+            DaPoster::LightGateway(gateway) => gateway.header_at(height).await,
+            DaPoster::Celestia(celestia) => celestia.header_at(height).await,
+        }
+    }
+
+    /// Data of every blob in `namespace` at `height` (replica mode)
+    pub async fn blobs_at(&self, namespace: &str, height: u64) -> anyhow::Result<Vec<Vec<u8>>> {
+        match self {
+            DaPoster::LightGateway(gateway) => gateway.blobs_at(namespace, height).await,
+            DaPoster::Celestia(celestia) => celestia.blobs_at(namespace, height).await,
+            DaPoster::Mock(_) => anyhow::bail!("the mock poster keeps no chain to read blobs from (use poster_mode = \"light-gateway\" or \"real\")"),
+        }
+    }
+}
+
+/// Poster for local runs and tests: logs blobs instead of submitting them
+#[derive(Debug, Default)]
+pub struct MockPoster {
+    /// Blobs accepted so far; the mock chain advances one block per blob
+    accepted: AtomicU64,
+    #[cfg(test)]
+    blobs: std::sync::Mutex<Vec<SubmittedBlob>>,
+}
+
+impl MockPoster {
+    fn submit(&self, signer: &DaSigner, namespace: &str, data: Vec<u8>) -> Inclusion {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(namespace.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(&data);
+        let commitment = hasher.finalize().to_hex().to_string();
+        let height = self.accepted.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let signer = signer.public_key_hex();
+        info!(
+            "🧪 Mock DA poster accepted {} byte blob (commitment {}, signer {})",
+            data.len(),
+            commitment,
+            signer.as_deref().unwrap_or("none")
+        );
+        #[cfg(test)]
+        self.blobs.lock().unwrap().push(SubmittedBlob {
+            namespace: namespace.to_string(),
+            data,
+            commitment: commitment.clone(),
+            signer,
+        });
+        Inclusion { commitment, height }
+    }
+
+    /// Mock head: the block the next blob would land in, at the current time
+    fn chain_head(&self) -> ChainAnchor {
+        ChainAnchor {
+            height: self.accepted.load(Ordering::Relaxed) + 1,
+            time: unix_now(),
+        }
+    }
+
+    /// Blobs submitted so far, oldest first
+    #[cfg(test)]
+    pub fn submitted(&self) -> Vec<SubmittedBlob> {
+        self.blobs.lock().unwrap().clone()
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
@@ -0,0 +1,363 @@
+use std::path::Path;
+use std::time::Duration;
+use anyhow::bail;
+use ed25519_dalek::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use crate::storage::{load_replica_cursor, save_replica_cursor, save_samples, spawn_write, REPLICA_CURSOR_PATH};
+use crate::telemetry::Liveness;
+use crate::types::{AppState, BatchHealth, Sample, SampleBit};
+use crate::utils::window_size;
+use super::bundle::verify_batch_payload;
+use super::chain::{parse_public_key, verify_sample};
+use super::framing::{decode_blob, DecodedBlob};
+
+/// What a blob contributed to a replica's state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applied {
+    /// A sample, plus this many dedup-skipped repeats reconstructed before it
+    Sample { repeats: u64 },
+    Batch,
+    Manifest,
+    /// From a reader this replica doesn't follow
+    Skipped,
+}
+
+/// How far a replica has read, persisted at `storage::REPLICA_CURSOR_PATH` so a restart
+/// resumes where it stopped instead of replaying the namespace from `replica.start_height`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicaCursor {
+    /// Next DA height to read
+    pub next_height: u64,
+    /// Sequence number of the last applied sample
+    pub last_seq: Option<u64>,
+    /// Signature of the last applied sample, which the next one must link to
+    pub last_sig: Option<String>,
+    /// End of the last applied batch window
+    pub last_batch_end: Option<u64>,
+}
+
+/// Background task for `mode = "replica"`: follow the primary's blobs in `celestia.namespace`
+/// height by height and rebuild samples, the ring buffer and alert state from them
+pub async fn run_replica(state: AppState) {
+    let replica = &state.config.replica;
+    let namespace = state.config.celestia.namespace.clone();
+    let key = match parse_public_key(&replica.source_public_key) {
+        Ok(key) => key,
+        Err(e) => {
+            error!("Replica: replica.source_public_key is unusable, not following: {}", e);
+            return;
+        }
+    };
+    let cursor_path = Path::new(REPLICA_CURSOR_PATH);
+    let mut cursor = match load_replica_cursor(cursor_path) {
+        Ok(Some(cursor)) => cursor,
+        Ok(None) => ReplicaCursor { next_height: replica.start_height, ..Default::default() },
+        Err(e) => {
+            error!("Replica: can't read {}, not following rather than re-applying old blobs: {}", REPLICA_CURSOR_PATH, e);
+            return;
+        }
+    };
+    let mut ticker = tokio::time::interval(Duration::from_secs(replica.poll_secs.max(1)));
+
+    info!("🪞 Replica following {} in namespace {} from height {}",
+          if replica.source_reader_id.is_empty() { "all readers" } else { &replica.source_reader_id },
+          namespace, cursor.next_height);
+
+    loop {
+        ticker.tick().await;
+        Liveness::mark(&state.liveness.last_tick);
+
+        let head = match state.da_poster.chain_head().await {
+            Ok(head) => head.height,
+            Err(e) => {
+                warn!("Replica: chain head query failed: {}", e);
+                continue;
+            }
+        };
+
+        let start_height = cursor.next_height;
+        let mut new_samples = false;
+        while cursor.next_height <= head {
+            let height = cursor.next_height;
+            let blobs = match state.da_poster.blobs_at(&namespace, height).await {
+                Ok(blobs) => blobs,
+                Err(e) => {
+                    warn!("Replica: reading blobs at height {} failed, retrying next poll: {}", height, e);
+                    break;
+                }
+            };
+            for data in blobs {
+                match apply_blob(&state, &key, &mut cursor, &data) {
+                    Ok(Applied::Sample { .. }) => new_samples = true,
+                    Ok(_) => {}
+                    Err(e) => warn!("Replica: rejected blob at height {}: {}", height, e),
+                }
+            }
+            cursor.next_height += 1;
+        }
+
+        if new_samples {
+            let samples = state.samples.lock().unwrap().clone();
+            let storage = state.config.storage.clone();
+            let saved = spawn_write(move || save_samples(&samples, &storage)).await;
+            state.record_storage(&saved);
+            if let Err(e) = saved {
+                error!("Replica: failed to save samples: {}", e);
+            }
+        }
+        if cursor.next_height != start_height {
+            let saved = spawn_write({
+                let (cursor, storage) = (cursor.clone(), state.config.storage.clone());
+                move || save_replica_cursor(cursor_path, &cursor, &storage)
+            })
+            .await;
+            state.record_storage(&saved);
+            if let Err(e) = saved {
+                error!("Replica: failed to save read position: {}", e);
+            }
+        }
+    }
+}
+
+/// Decode one of the primary's blobs, check it against the primary's `key` and fold it
+/// into `state`, moving `cursor` past it
+///
+/// Samples and batches must be signed with `key`; samples must also continue the chain
+/// after `cursor` (a gap is tolerated, a replayed or unlinked sample is not) and batches
+/// must cover a newer window. Manifests are unsigned and change nothing, so they are
+/// only logged. Samples that `da_posting.dedup_consecutive` skipped on the primary are
+/// rebuilt from `prior_repeats` as copies of the previous sample, one tick apart.
+pub fn apply_blob(state: &AppState, key: &VerifyingKey, cursor: &mut ReplicaCursor, data: &[u8]) -> anyhow::Result<Applied> {
+    let config = &state.config;
+    let follows = |reader_id: &str| {
+        config.replica.source_reader_id.is_empty() || config.replica.source_reader_id == reader_id
+    };
+
+    match decode_blob(data)? {
+        DecodedBlob::Sample(payload) if follows(&payload.reader_id) => {
+            let seq = verify_sample(&payload, key)?;
+            if let Some(last_seq) = cursor.last_seq {
+                if seq <= last_seq {
+                    bail!("sample {} replays an already applied sequence number (last {})", seq, last_seq);
+                }
+                if seq == last_seq + 1 && payload.prev_sig != cursor.last_sig {
+                    bail!("sample {} is not linked to sample {}", seq, last_seq);
+                }
+                if seq > last_seq + 1 {
+                    warn!("Replica: samples {}..{} are missing from DA", last_seq + 1, seq - 1);
+                }
+            }
+
+            let window_size = window_size(config.batching.window_secs, config.sampling.tick_secs)?;
+            let mut samples = state.samples.lock().unwrap();
+            let mut ring = state.ring_buffer.lock().unwrap();
+
+            let mut replayed = Vec::new();
+            if let Some(last) = samples.last() {
+                for k in 1..=payload.prior_repeats {
+                    replayed.push(Sample { timestamp: last.timestamp + k * config.sampling.tick_secs, ..last.clone() });
+                }
+            }
+            replayed.push(Sample {
+                timestamp: payload.timestamp,
+                ok: payload.ok,
+                reason: payload.reason.unwrap_or_default(),
+                ..Default::default()
+            });
+
+            for sample in replayed {
+                let bit = SampleBit {
+                    timestamp: sample.timestamp,
+                    ok: sample.ok,
+                    reason: sample.reason.clone(),
+                    ..Default::default()
+                };
+                ring.push(bit, window_size);
+                state.telemetry.record_sample(&sample);
+                samples.push(sample);
+            }
+            Liveness::mark(&state.liveness.last_ingest);
+            cursor.last_seq = Some(seq);
+            cursor.last_sig = payload.signature;
+            Ok(Applied::Sample { repeats: payload.prior_repeats })
+        }
+        DecodedBlob::Batch(payload) if follows(&payload.reader_id) => {
+            verify_batch_payload(&payload, key)?;
+            let batch = &payload.batch;
+            if cursor.last_batch_end.is_some_and(|end| batch.window.end <= end) {
+                bail!("batch {}..{} is not newer than the last applied window", batch.window.start, batch.window.end);
+            }
+            let health = batch.health.unwrap_or(if batch.good >= batch.threshold {
+                BatchHealth::Healthy
+            } else {
+                BatchHealth::Failed
+            });
+            state
+                .alert_state
+                .lock()
+                .unwrap()
+                .record_window(health, config.alerts.hysteresis_windows);
+            Liveness::mark(&state.liveness.last_batch);
+            state.telemetry.record_batch(batch);
+            cursor.last_batch_end = Some(batch.window.end);
+            Ok(Applied::Batch)
+        }
+        DecodedBlob::Manifest(manifest) if follows(&manifest.reader_id) => {
+            info!("🪞 Replica: {} runs v{} (tick {}s, window {}s, threshold {:.0}%)",
+                  manifest.reader_id, manifest.reader_version, manifest.tick_secs, manifest.window_secs,
+                  manifest.threshold_percent * 100.0);
+            Ok(Applied::Manifest)
+        }
+        _ => Ok(Applied::Skipped),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::config::Config;
+    use crate::da::{build_manifest_payload, encode_blob, BlobType, DaSigner, SampleChain};
+    use crate::da::bundle::encode_batch_bundle;
+    use crate::da::payload::build_sample_payload;
+    use crate::types::{Batch, TimeWindow};
+
+    fn signer() -> DaSigner {
+        DaSigner::from_config(&Config::for_tests().celestia)
+    }
+
+    fn key() -> VerifyingKey {
+        parse_public_key(&signer().public_key_hex().unwrap()).unwrap()
+    }
+
+    /// Build a sample blob linked onto `chain`, as the primary's `SampleLayer` would
+    fn sample_blob(chain: &mut SampleChain, signer: &DaSigner, reader_id: &str, timestamp: u64, ok: bool, prior_repeats: u64) -> Vec<u8> {
+        let bit = SampleBit { timestamp, ok, reason: format!("r{}", timestamp), ..Default::default() };
+        let mut payload = build_sample_payload(&bit, reader_id, true);
+        payload.prior_repeats = prior_repeats;
+        chain.link(&mut payload, signer);
+        chain.advance(&payload);
+        encode_blob(BlobType::Sample, &payload, true).unwrap()
+    }
+
+    fn batch_blob(batch: &Batch, signer: &DaSigner) -> Vec<u8> {
+        encode_batch_bundle(batch, "primary", "0x2N1CE", None, signer, true, 6).unwrap()
+    }
+
+    fn replica_state() -> AppState {
+        let mut config = Config::for_tests();
+        config.replica.source_reader_id = "primary".to_string();
+        // The manifest is built from this config, so it names the followed reader
+        config.da_posting.reader_id = "primary".to_string();
+        config.alerts.hysteresis_windows = 1;
+        AppState::new(Arc::new(config))
+    }
+
+    #[test]
+    fn test_replica_rebuilds_state_from_blobs() {
+        let state = replica_state();
+        let signer = signer();
+        let mut chain = SampleChain::default();
+        let mut other_chain = SampleChain::default();
+
+        let failed_batch = Batch { good: 10, window: TimeWindow { start: 1000, end: 1600 }, ..Batch::sample() };
+        let blobs = [
+            encode_blob(BlobType::Manifest, &build_manifest_payload(&state.config, 900), true).unwrap(),
+            sample_blob(&mut chain, &signer, "primary", 1000, true, 0),
+            sample_blob(&mut other_chain, &signer, "someone-else", 1010, false, 0),
+            // Two identical samples at 1030 and 1060 were deduplicated on the primary
+            sample_blob(&mut chain, &signer, "primary", 1090, false, 2),
+            batch_blob(&failed_batch, &signer),
+        ];
+
+        let mut cursor = ReplicaCursor::default();
+        let applied: Vec<Applied> = blobs.iter().map(|b| apply_blob(&state, &key(), &mut cursor, b).unwrap()).collect();
+        assert_eq!(applied, [
+            Applied::Manifest,
+            Applied::Sample { repeats: 0 },
+            Applied::Skipped,
+            Applied::Sample { repeats: 2 },
+            Applied::Batch,
+        ]);
+        assert_eq!(cursor.last_seq, Some(1));
+        assert_eq!(cursor.last_batch_end, Some(1600));
+
+        let samples = state.samples.lock().unwrap();
+        let series: Vec<(u64, bool)> = samples.iter().map(|s| (s.timestamp, s.ok)).collect();
+        assert_eq!(series, [(1000, true), (1030, true), (1060, true), (1090, false)]);
+        assert_eq!(state.ring_buffer.lock().unwrap().counts(), (3, 4));
+        assert!(state.alert_state.lock().unwrap().alerting);
+
+        assert!(apply_blob(&state, &key(), &mut cursor, b"not a blob").is_err());
+    }
+
+    #[test]
+    fn test_replica_rejects_unsigned_and_forged_blobs() {
+        let state = replica_state();
+        let mut cursor = ReplicaCursor::default();
+        let mut forger_config = Config::for_tests().celestia;
+        forger_config.private_key_hex = Some("11".repeat(32));
+        let forger = DaSigner::from_config(&forger_config);
+
+        // Anyone can post in the namespace under the primary's reader_id
+        let unsigned = sample_blob(&mut SampleChain::default(), &DaSigner::default(), "primary", 1000, false, 0);
+        let forged = sample_blob(&mut SampleChain::default(), &forger, "primary", 1000, false, 0);
+        let forged_batch = batch_blob(&Batch { window: TimeWindow { start: 1000, end: 1600 }, ..Batch::sample() }, &forger);
+        for blob in [unsigned, forged, forged_batch] {
+            assert!(apply_blob(&state, &key(), &mut cursor, &blob).is_err());
+        }
+
+        // A signed batch whose counts were edited no longer matches its content hash
+        let blob = batch_blob(&Batch::sample(), &signer());
+        let DecodedBlob::Batch(mut payload) = decode_blob(&blob).unwrap() else { panic!("not a batch") };
+        payload.batch.good = 0;
+        let tampered = crate::da::framing::encode_blob_gzip(BlobType::Batch, &payload, true, 6).unwrap();
+        assert!(apply_blob(&state, &key(), &mut cursor, &tampered).is_err());
+
+        assert!(state.samples.lock().unwrap().is_empty());
+        assert!(!state.alert_state.lock().unwrap().alerting);
+        assert_eq!(cursor, ReplicaCursor::default());
+    }
+
+    #[test]
+    fn test_replica_rejects_replayed_blobs() {
+        let state = replica_state();
+        let signer = signer();
+        let mut chain = SampleChain::default();
+        let mut cursor = ReplicaCursor::default();
+
+        let first = sample_blob(&mut chain, &signer, "primary", 1000, true, 0);
+        let batch = batch_blob(&Batch::sample(), &signer);
+        apply_blob(&state, &key(), &mut cursor, &first).unwrap();
+        apply_blob(&state, &key(), &mut cursor, &batch).unwrap();
+
+        // Re-posting a genuine blob doesn't apply it twice
+        assert!(apply_blob(&state, &key(), &mut cursor, &first).is_err());
+        assert!(apply_blob(&state, &key(), &mut cursor, &batch).is_err());
+        assert_eq!(state.samples.lock().unwrap().len(), 1);
+
+        // Nor does a validly signed sample from a different chain slot in as the next one
+        let mut fork = SampleChain::default();
+        sample_blob(&mut fork, &signer, "primary", 999, false, 0);
+        let unlinked = sample_blob(&mut fork, &signer, "primary", 1030, true, 0);
+        assert!(apply_blob(&state, &key(), &mut cursor, &unlinked).is_err());
+
+        // A gap is tolerated
+        sample_blob(&mut chain, &signer, "primary", 1030, true, 0);
+        let after_gap = sample_blob(&mut chain, &signer, "primary", 1060, true, 0);
+        apply_blob(&state, &key(), &mut cursor, &after_gap).unwrap();
+        assert_eq!(cursor.last_seq, Some(2));
+    }
+
+    #[test]
+    fn test_replica_cursor_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("replica_cursor.json");
+        assert_eq!(load_replica_cursor(&path).unwrap(), None);
+
+        let cursor = ReplicaCursor { next_height: 42, last_seq: Some(7), last_sig: Some("ab".to_string()), last_batch_end: Some(1600) };
+        save_replica_cursor(&path, &cursor, &Config::for_tests().storage).unwrap();
+        assert_eq!(load_replica_cursor(&path).unwrap(), Some(cursor));
+    }
+}
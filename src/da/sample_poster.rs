@@ -0,0 +1,165 @@
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+use crate::storage::{load_sample_chains, save_sample_chains, spawn_write, SAMPLE_CHAINS_PATH};
+use crate::types::{AppState, SampleBit};
+use crate::utils::backoff_with_jitter;
+use super::policy::{chain_heads, sample_layers, SampleLayer};
+use super::post_sample_to_da;
+
+/// Samples waiting for the per-sample DA layers; beyond this the sampler drops them
+/// rather than wait for a slow node
+const QUEUE_LEN: usize = 64;
+
+/// Failed sample submissions are retried this many times, after a jittered backoff
+/// between `RETRY_BASE` and `RETRY_MAX`, before the layer moves on without the blob
+const SUBMIT_RETRIES: u32 = 2;
+const RETRY_BASE: Duration = Duration::from_millis(500);
+const RETRY_MAX: Duration = Duration::from_secs(5);
+
+/// Start posting samples to the per-sample DA layers in the background, returning the
+/// queue the sampler hands each sample to
+///
+/// Submissions (which in "real" mode wait for inclusion) then never hold up a tick.
+pub fn spawn_sample_poster(state: AppState) -> mpsc::Sender<SampleBit> {
+    let (tx, rx) = mpsc::channel(QUEUE_LEN);
+    tokio::spawn(run_sample_poster(state, rx));
+    tx
+}
+
+/// Post each queued sample to every layer whose cadence is due, one sample at a time so
+/// each layer's chain stays in order
+async fn run_sample_poster(state: AppState, mut samples: mpsc::Receiver<SampleBit>) {
+    // Layers continue their signed chains from before a restart
+    let chains = load_sample_chains(Path::new(SAMPLE_CHAINS_PATH)).unwrap_or_else(|e| {
+        error!("Failed to load {} ({}), sample chains restart at seq 0", SAMPLE_CHAINS_PATH, e);
+        Default::default()
+    });
+    let mut layers = sample_layers(&state.config.posting_policies(), &chains);
+
+    while let Some(sample_bit) = samples.recv().await {
+        if post_to_layers(&state, &mut layers, &sample_bit).await {
+            let (chains, storage) = (chain_heads(&layers), state.config.storage.clone());
+            let saved = spawn_write(move || save_sample_chains(Path::new(SAMPLE_CHAINS_PATH), &chains, &storage)).await;
+            if let Err(e) = saved {
+                error!("Failed to save sample chain heads: {}", e);
+            }
+        }
+    }
+}
+
+/// Offer `sample_bit` to every layer and post the blobs that are due, each submission
+/// bounded by `da_posting.submit_timeout_secs` and retried up to `SUBMIT_RETRIES` times
+/// if it fails; returns whether any layer's chain moved on
+async fn post_to_layers(state: &AppState, layers: &mut [SampleLayer], sample_bit: &SampleBit) -> bool {
+    let da_posting = &state.config.da_posting;
+    let timeout = Duration::from_secs(da_posting.submit_timeout_secs);
+    let mut moved = false;
+    for layer in layers.iter_mut() {
+        let Some(payload) = layer.offer(sample_bit, &da_posting.reader_id, da_posting.dedup_consecutive, &state.da_signer) else {
+            debug!("📡 Sample not posted to DA layer {} (cadence not due or repeat)", layer.policy.name);
+            continue;
+        };
+        let mut attempt = 0;
+        let submitted = loop {
+            match tokio::time::timeout(timeout, post_sample_to_da(&payload, layer.namespace(), state)).await {
+                Ok(Err(e)) if attempt < SUBMIT_RETRIES => {
+                    let delay = backoff_with_jitter(&mut *state.rng.lock().unwrap(), attempt, RETRY_BASE, RETRY_MAX);
+                    attempt += 1;
+                    warn!("📡 Posting sample at {} to DA [{} → {}] failed ({}), retry {}/{} in {:?}",
+                          payload.timestamp, layer.policy.name, layer.namespace(), e, attempt, SUBMIT_RETRIES, delay);
+                    tokio::time::sleep(delay).await;
+                }
+                submitted => break submitted,
+            }
+        };
+        match submitted {
+            Ok(Ok(commitment)) => {
+                layer.posted(&payload);
+                moved = true;
+                info!(
+                    "📡 Posted sample to Celestia DA [{} → {}]: ok={}, timestamp={}, seq={:?}, prior_repeats={} (commitment {})",
+                    layer.policy.name, layer.namespace(), payload.ok, payload.timestamp,
+                    payload.seq, payload.prior_repeats, commitment
+                );
+            }
+            // The layer's chain and dedup state stay at the last blob that landed
+            Ok(Err(e)) => error!("Failed to post sample at {} to DA [{} → {}]: {}",
+                                 payload.timestamp, layer.policy.name, layer.namespace(), e),
+            Err(_) => {
+                // A timed-out submission may still land. Moving on leaves a gap if it doesn't,
+                // which verifiers report; staying put would reuse its seq, which they reject.
+                layer.posted(&payload);
+                moved = true;
+                warn!("⏱️  Posting sample at {} to DA [{} → {}] timed out after {}s; the chain continues after it",
+                      payload.timestamp, layer.policy.name, layer.namespace(), timeout.as_secs());
+            }
+        }
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use axum::{routing::post, Router};
+    use crate::config::Config;
+
+    #[tokio::test]
+    async fn test_hung_submission_times_out() {
+        // A gateway that accepts the request and never answers
+        let app = Router::new().route("/", post(std::future::pending::<String>));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut config = Config::for_tests();
+        config.da_posting.enabled = true;
+        config.da_posting.submit_timeout_secs = 1;
+        config.celestia.poster_mode = crate::config::PosterMode::LightGateway;
+        config.celestia.gateway_url = format!("http://{}/", addr);
+        config.celestia.namespace = "0x00000000000000000001".to_string();
+        let state = AppState::new(Arc::new(config));
+        let mut layers = sample_layers(&state.config.posting_policies(), &Default::default());
+        assert!(!layers.is_empty());
+
+        let bit = SampleBit { timestamp: 1000, ok: true, reason: "+1 blocks".to_string(), ..Default::default() };
+        let posting = post_to_layers(&state, &mut layers, &bit);
+        let moved = tokio::time::timeout(Duration::from_secs(10), posting).await.expect("bounded by submit_timeout_secs");
+        assert!(moved);
+        // The chain continues after the timed-out blob instead of reusing its seq
+        let next = SampleBit { timestamp: 1030, ..bit };
+        assert_eq!(layers[0].offer(&next, "test-reader", false, &state.da_signer).unwrap().seq, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_failed_submission_retried() {
+        // A gateway whose first blob.Submit fails
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let app = Router::new().route("/", post(move || async move {
+            if counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                r#"{"jsonrpc":"2.0","id":1,"error":{"code":1,"message":"mempool full"}}"#
+            } else {
+                r#"{"jsonrpc":"2.0","id":1,"result":7}"#
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut config = Config::for_tests();
+        config.da_posting.enabled = true;
+        config.celestia.poster_mode = crate::config::PosterMode::LightGateway;
+        config.celestia.gateway_url = format!("http://{}/", addr);
+        config.celestia.namespace = "0x00000000000000000001".to_string();
+        let state = AppState::new(Arc::new(config)).with_rng_seed(7);
+        let mut layers = sample_layers(&state.config.posting_policies(), &Default::default());
+
+        let bit = SampleBit { timestamp: 1000, ok: true, reason: "+1 blocks".to_string(), ..Default::default() };
+        assert!(post_to_layers(&state, &mut layers, &bit).await);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,72 @@
+use ed25519_dalek::{Signature, Signer, SigningKey};
+use std::sync::RwLock;
+use tracing::warn;
+use crate::config::CelestiaConfig;
+use crate::crypto::{public_key_hex, signing_key_from_hex};
+
+/// Environment variables the signing key is (re-)read from
+const MNEMONIC_VAR: &str = "CELESTIA_MNEMONIC";
+const PRIVATE_KEY_VAR: &str = "CELESTIA_PRIVATE_KEY";
+
+/// Key that signs this reader's DA submissions, swappable without a restart
+#[derive(Debug, Default)]
+pub struct DaSigner {
+    key: RwLock<Option<SigningKey>>,
+}
+
+impl DaSigner {
+    /// Derive the key from the loaded credentials; posting works unsigned (mock) if that fails
+    pub fn from_config(config: &CelestiaConfig) -> Self {
+        let key = match config.get_private_key_hex().and_then(|hex| signing_key_from_hex(&hex)) {
+            Ok(key) => Some(key),
+            Err(e) => {
+                warn!("DA signing key unavailable: {}", e);
+                None
+            }
+        };
+        Self { key: RwLock::new(key) }
+    }
+
+    /// Re-read the credentials via `lookup` (e.g. `std::env::var`) and swap in the new key
+    ///
+    /// The key is picked as at startup (`CelestiaConfig::get_private_key_hex`):
+    /// `CELESTIA_PRIVATE_KEY` wins over `CELESTIA_MNEMONIC`, unless it is invalid and
+    /// `config.key_fallback_to_mnemonic` is set. The current key stays in place unless the
+    /// new one derives successfully. Returns the new public key as hex.
+    pub fn reload(&self, config: &CelestiaConfig, lookup: impl Fn(&str) -> Option<String>) -> anyhow::Result<String> {
+        let read = |name| lookup(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let credentials = CelestiaConfig {
+            mnemonic: read(MNEMONIC_VAR),
+            private_key_hex: read(PRIVATE_KEY_VAR),
+            ..config.clone()
+        };
+        if credentials.mnemonic.is_none() && credentials.private_key_hex.is_none() {
+            anyhow::bail!("neither {} nor {} is set", PRIVATE_KEY_VAR, MNEMONIC_VAR);
+        }
+        let key = signing_key_from_hex(&credentials.get_private_key_hex()?)?;
+        let public_key = public_key_hex(&key);
+        *self.key.write().unwrap() = Some(key);
+        Ok(public_key)
+    }
+
+    /// Sign `message` with the current key, if there is one
+    pub fn sign(&self, message: &[u8]) -> Option<Signature> {
+        self.key.read().unwrap().as_ref().map(|key| key.sign(message))
+    }
+
+    /// Raw bytes of the current key, as hex; "real" posting pays for blobs with the
+    /// secp256k1 account of the same private key
+    pub fn private_key_hex(&self) -> Option<String> {
+        self.key.read().unwrap().as_ref().map(|key| hex::encode(key.to_bytes()))
+    }
+
+    /// Public key of the current signing key, as hex
+    pub fn public_key_hex(&self) -> Option<String> {
+        self.key
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(public_key_hex)
+    }
+}
+
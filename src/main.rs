@@ -6,67 +6,177 @@ mod metrics;
 mod da;
 mod storage;
 mod crypto;
+mod proofs;
+mod cli;
+mod api;
+mod server;
+mod telemetry;
 
-use axum::{routing::post, Router};
-use std::{
-    collections::VecDeque,
-    fs,
-    net::SocketAddr,
-    sync::{Arc, Mutex},
-};
+use std::{fs, net::SocketAddr, sync::Arc};
 use tokio::net::TcpListener;
-use tracing::info;
+use tracing::{info, warn, error};
 
-use config::Config;
-use types::{AppState, DasMetrics};
-use otlp::handle_metrics;
-use metrics::{run_sampler, run_batch_generator};
+use config::{Config, ReaderMode, StorageBackend};
+use types::AppState;
+use metrics::{run_sampler, run_batch_generator, run_compactor, run_watchdog, flush_samples, restore_samples, threshold_granularity_warning};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
 
+    // Run a subcommand instead of the service if one was given
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        return cli::run(command, &args[1..]);
+    }
+
     // Load configuration
     let config = Arc::new(Config::load()?);
     info!("Loaded config: {:?}", config);
     
     // Create data directory if it doesn't exist
     fs::create_dir_all("data")?;
+    if storage::migrate_data_dir(std::path::Path::new("data"))? {
+        info!("📂 Migrated legacy data/ files to layout v{} (originals in data/legacy-v0/)",
+              storage::LAYOUT_VERSION);
+    }
+    
+    // Check the configured metric names against a synthetic export before real traffic
+    if config.debug.self_test {
+        match otlp::run_self_test(&config).await {
+            Ok(()) => info!("✅ OTLP self-test passed: head/headers metric names extract as configured"),
+            Err(e) => error!("❌ OTLP self-test failed: {}", e),
+        }
+    }
     
     // Initialize shared state
-    let state = AppState {
-        config: config.clone(),
-        das_metrics: Arc::new(Mutex::new(DasMetrics::default())),
-        ring_buffer: Arc::new(Mutex::new(VecDeque::new())),
-        samples: Arc::new(Mutex::new(Vec::new())),
-    };
+    let state = AppState::new(config.clone());
     
-    // Spawn background sampler task
-    let sampler_state = state.clone();
-    tokio::spawn(async move {
-        run_sampler(sampler_state).await;
-    });
+    // Downsampled history is merged into by the compactor, so it must be loaded before the
+    // compactor's first pass rewrites the file, and before the raw samples: those it already
+    // counts (before its watermark) are not restored
+    match storage::load_summaries(std::path::Path::new(storage::SUMMARIES_PATH)) {
+        Ok((summaries, compacted_before)) => {
+            if !summaries.is_empty() {
+                info!("♻️  Restored {} downsampled buckets from {}", summaries.len(), storage::SUMMARIES_PATH);
+            }
+            *state.sample_summaries.lock().unwrap() = summaries;
+            *state.compacted_before.lock().unwrap() = compacted_before;
+        }
+        Err(e) => {
+            let backup = format!("{}.corrupt", storage::SUMMARIES_PATH);
+            warn!("⚠️  Couldn't read {} ({}), starting with no downsampled history (moved to {})",
+                  storage::SUMMARIES_PATH, e, backup);
+            if let Err(e) = fs::rename(storage::SUMMARIES_PATH, &backup) {
+                error!("Failed to move {} aside: {}", storage::SUMMARIES_PATH, e);
+            }
+        }
+    }
+    
+    // Pick up the sample history and the batch window in progress from before a restart
+    // (a replica rebuilds both from DA instead)
+    if config.mode == ReaderMode::Primary && config.storage.backend == StorageBackend::Sqlite {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let since = config.storage.memory_retention_secs().map_or(0, |secs| now.saturating_sub(secs))
+            .max(*state.compacted_before.lock().unwrap());
+        match storage::init_db().and_then(|conn| storage::load_samples_db(&conn, since)) {
+            Ok(samples) if samples.is_empty() => {}
+            Ok(samples) => {
+                let total = samples.len();
+                let in_window = restore_samples(&state, samples, now);
+                info!("♻️  Restored {} samples from {} ({} in the current batch window)",
+                      total, storage::SAMPLES_DB_PATH, in_window);
+            }
+            Err(e) => warn!("⚠️  Couldn't read {} ({}), starting with no sample history", storage::SAMPLES_DB_PATH, e),
+        }
+    }
+    if config.mode == ReaderMode::Primary && config.storage.backend == StorageBackend::Json {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        match storage::load_samples() {
+            Ok(samples) if samples.is_empty() => {}
+            Ok(samples) => {
+                let total = samples.len();
+                let in_window = restore_samples(&state, samples, now);
+                info!("♻️  Restored {} samples from {} ({} in the current batch window)",
+                      total, storage::SAMPLES_PATH, in_window);
+            }
+            Err(e) => {
+                // Keep the unreadable file aside; the sampler would overwrite it on its first tick
+                let backup = format!("{}.corrupt", storage::SAMPLES_PATH);
+                warn!("⚠️  Couldn't read {} ({}), starting with no sample history (moved to {})",
+                      storage::SAMPLES_PATH, e, backup);
+                if let Err(e) = fs::rename(storage::SAMPLES_PATH, &backup) {
+                    error!("Failed to move {} aside: {}", storage::SAMPLES_PATH, e);
+                }
+            }
+        }
+    }
     
-    // Spawn background batch generator task
-    let batch_state = state.clone();
+    // Announce this reader's configuration before any attestations
+    if config.da_posting.enabled && config.mode == ReaderMode::Primary {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let posted = da::post_manifest_to_da(&state, now).await;
+        state.liveness.record_da(&posted);
+        match posted {
+            Ok((manifest, commitment)) => {
+                info!("📜 Posted reader manifest to DA (commitment {})", commitment);
+                if let Err(e) = storage::save_manifest(&manifest, &commitment, &config.storage) {
+                    error!("Failed to save manifest record: {}", e);
+                }
+            }
+            Err(e) => error!("Failed to post reader manifest to DA: {}", e),
+        }
+    }
+    
+    if config.mode == ReaderMode::Replica {
+        // Rebuild state from the primary's DA blobs instead of sampling OTLP
+        let replica_state = state.clone();
+        tokio::spawn(async move {
+            da::run_replica(replica_state).await;
+        });
+    } else {
+        // Spawn background sampler task
+        let sampler_state = state.clone();
+        tokio::spawn(async move {
+            run_sampler(sampler_state).await;
+        });
+        
+        // Spawn background batch generator task
+        let batch_state = state.clone();
+        tokio::spawn(async move {
+            run_batch_generator(batch_state).await;
+        });
+        
+        // Spawn background watchdog for tasks that stop making progress without panicking
+        let watchdog_state = state.clone();
+        tokio::spawn(async move {
+            run_watchdog(watchdog_state).await;
+        });
+    }
+    
+    // Spawn background compaction of old samples (no-op unless storage.downsample_after_secs is set)
+    let compactor_state = state.clone();
     tokio::spawn(async move {
-        run_batch_generator(batch_state).await;
+        run_compactor(compactor_state).await;
     });
     
     // Start HTTP server
-    let app = Router::new()
-        .route("/v1/metrics", post(handle_metrics))
-        .with_state(state);
+    let app = api::router(state.clone());
 
     let addr: SocketAddr = "0.0.0.0:4318".parse()?;
     info!("🚀 Listening for OTLP/HTTP on http://{addr}");
     info!("📊 Sampler will tick every {} seconds", config.sampling.tick_secs);
     
     if config.da_posting.enabled {
-        if config.da_posting.post_every_sample {
-            info!("📡 DA posting: ENABLED - Will post each sample to Celestia DA");
-        } else {
-            info!("📡 DA posting: ENABLED - Will post batched samples to Celestia DA");
+        for policy in config.posting_policies() {
+            info!("📡 DA posting: ENABLED - layer {} posts {:?} blobs {:?} to namespace {}",
+                  policy.name, policy.format, policy.cadence, policy.namespace.as_deref().unwrap_or_default());
         }
     } else {
         info!("📡 DA posting: DISABLED - Samples will be stored locally only");
@@ -75,9 +185,42 @@ async fn main() -> anyhow::Result<()> {
     info!("📦 Batches (for ZK proofs) will be generated every {} seconds ({} minutes)", 
           config.batching.window_secs, 
           config.batching.window_secs / 60);
+    let window_size = utils::window_size(config.batching.window_secs, config.sampling.tick_secs)?;
+    if let Some(warning) = threshold_granularity_warning(window_size, config.proofs.threshold_percent) {
+        warn!("⚠️  {}", warning);
+    }
     
     let listener = TcpListener::bind(&addr).await?;
-    axum::serve(listener, app.into_make_service()).await?;
-
+    tokio::select! {
+        result = server::serve(listener, app, &config.server) => result?,
+        signal = shutdown_signal() => info!("🛑 Shutting down ({})", signal?),
+    }
+    
+    // Samples may be buffered when storage.flush_every_n_samples > 1 (the sqlite backend
+    // inserts every sample as it's taken)
+    if config.storage.backend == StorageBackend::Json {
+        match flush_samples(&state, std::path::Path::new(storage::SAMPLES_PATH)) {
+            Ok(n) => info!("💾 Flushed {} samples to {}", n, storage::SAMPLES_PATH),
+            Err(e) => error!("Failed to flush samples on shutdown: {}", e),
+        }
+    }
     Ok(())
 }
+
+/// Wait for Ctrl-C or, on Unix, SIGTERM (what `docker stop` and systemd send), returning
+/// which one arrived
+async fn shutdown_signal() -> anyhow::Result<&'static str> {
+    #[cfg(unix)]
+    {
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.map(|_| "Ctrl-C").map_err(Into::into),
+            _ = terminate.recv() => Ok("SIGTERM"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok("Ctrl-C")
+    }
+}
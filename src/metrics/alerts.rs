@@ -0,0 +1,135 @@
+use serde::Serialize;
+use crate::types::BatchHealth;
+
+/// Threshold alert emitted after a batch window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AlertEvent {
+    /// Uptime stayed below threshold for `hysteresis_windows` consecutive windows
+    BelowThreshold,
+    /// Uptime met threshold again for `hysteresis_windows` consecutive windows
+    Recovered,
+    /// Uptime met threshold but stayed below the warn threshold for `hysteresis_windows` windows
+    Degraded,
+}
+
+/// Consecutive-window streaks used to debounce threshold alerts
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AlertState {
+    pub failing_streak: u32,
+    pub passing_streak: u32,
+    /// Consecutive degraded windows (a subset of the passing streak)
+    pub degraded_streak: u32,
+    /// Whether a below-threshold alert is currently active
+    pub alerting: bool,
+}
+
+impl AlertState {
+    /// Record a window outcome and return the alerts whose streak crossed `hysteresis_windows`
+    ///
+    /// Degraded windows meet the threshold, so they count toward recovery; a degraded
+    /// warning fires once when the degraded streak reaches `hysteresis_windows`. Recovering
+    /// through degraded windows can trigger both in the same window, `Recovered` first.
+    pub fn record_window(&mut self, health: BatchHealth, hysteresis_windows: u32) -> Vec<AlertEvent> {
+        let k = hysteresis_windows.max(1);
+        let mut events = Vec::new();
+
+        if health != BatchHealth::Failed {
+            self.passing_streak += 1;
+            self.failing_streak = 0;
+            if health == BatchHealth::Degraded {
+                self.degraded_streak += 1;
+            } else {
+                self.degraded_streak = 0;
+            }
+            if self.alerting && self.passing_streak >= k {
+                self.alerting = false;
+                events.push(AlertEvent::Recovered);
+            }
+            if !self.alerting && self.degraded_streak == k {
+                events.push(AlertEvent::Degraded);
+            }
+        } else {
+            self.failing_streak += 1;
+            self.passing_streak = 0;
+            self.degraded_streak = 0;
+            if !self.alerting && self.failing_streak >= k {
+                self.alerting = true;
+                events.push(AlertEvent::BelowThreshold);
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(windows: &[bool], k: u32) -> Vec<(usize, AlertEvent)> {
+        let mut state = AlertState::default();
+        windows
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &meets)| {
+                let health = if meets { BatchHealth::Healthy } else { BatchHealth::Failed };
+                state.record_window(health, k).into_iter().map(move |e| (i, e))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_oscillating_windows_suppressed() {
+        // Flapping around the threshold never builds a streak of 3
+        let windows = [true, false, true, false, false, true, false, true];
+        assert!(run(&windows, 3).is_empty());
+    }
+
+    #[test]
+    fn test_sustained_failure_then_recovery() {
+        let windows = [false, false, false, true, false, true, true, true];
+        assert_eq!(
+            run(&windows, 3),
+            vec![(2, AlertEvent::BelowThreshold), (7, AlertEvent::Recovered)]
+        );
+    }
+
+    #[test]
+    fn test_hysteresis_of_one_alerts_every_transition() {
+        let windows = [true, false, true, false];
+        assert_eq!(
+            run(&windows, 1),
+            vec![
+                (1, AlertEvent::BelowThreshold),
+                (2, AlertEvent::Recovered),
+                (3, AlertEvent::BelowThreshold),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_degraded_windows_warn_without_failing() {
+        use BatchHealth::*;
+        let mut state = AlertState::default();
+        let events: Vec<_> = [Healthy, Degraded, Degraded, Degraded, Healthy, Degraded, Degraded]
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, health)| state.record_window(health, 2).into_iter().map(move |e| (i, e)))
+            .collect();
+        // Fires once per degraded streak, never as a below-threshold alert
+        assert_eq!(events, vec![(2, AlertEvent::Degraded), (6, AlertEvent::Degraded)]);
+        assert!(!state.alerting);
+    }
+
+    #[test]
+    fn test_recovery_through_degraded_windows_reports_both() {
+        use BatchHealth::*;
+        let mut state = AlertState::default();
+        assert_eq!(state.record_window(Failed, 2), vec![]);
+        assert_eq!(state.record_window(Failed, 2), vec![AlertEvent::BelowThreshold]);
+        assert_eq!(state.record_window(Degraded, 2), vec![]);
+        // Back above threshold, but only just: the degraded warning isn't swallowed
+        assert_eq!(state.record_window(Degraded, 2), vec![AlertEvent::Recovered, AlertEvent::Degraded]);
+        assert_eq!(state.record_window(Degraded, 2), vec![]);
+    }
+}
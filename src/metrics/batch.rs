@@ -1,19 +1,70 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::time::interval;
-use tracing::{info, warn, error};
-use crate::types::{AppState, Batch, TimeWindow, SampleBit};
-use crate::storage::{save_batch, save_bitmap};
+use tracing::{debug, info, warn, error};
+use crate::types::{AppState, Batch, BatchHealth, CategoryCounts, ReasonCategory, Sample, TimeWindow, SampleBit, SampleReason};
+use crate::config::{Config, NoDataPolicy, PostingCadence, PostingPolicy, ProofsConfig};
+use super::alerts::AlertEvent;
+use crate::storage::{
+    append_accumulator_leaves, load_accumulator_leaves, record_batch_commitment, save_batch, save_bitmap, save_proof, spawn_write, PostedWindows,
+    ACCUMULATOR_LEAVES_PATH, POSTED_WINDOWS_PATH,
+};
 use crate::utils::format_timestamp;
+use crate::da::{batch_content_hash, build_batch_payload, PendingDaQueue};
+use crate::proofs::{hash_bitmap, AccumulatorLeaf, HashAlgo, MerkleAccumulator};
+use crate::telemetry::Liveness;
 
 /// Background task: generates batches at fixed intervals (for ZK proofs)
 pub async fn run_batch_generator(state: AppState) {
     let batch_duration = Duration::from_secs(state.config.batching.window_secs);
     let mut ticker = interval(batch_duration);
+    ticker.set_missed_tick_behavior(state.config.sampling.missed_ticks.behavior());
     
     info!("📦 Batch generator started (every {}s = {} min) for ZK proof generation", 
           state.config.batching.window_secs,
           state.config.batching.window_secs / 60);
     
+    // Windows already posted to DA (survives restarts)
+    let posted_windows = Arc::new(Mutex::new(match PostedWindows::load(POSTED_WINDOWS_PATH) {
+        Ok(posted) => posted,
+        Err(e) => {
+            error!("Failed to load {}: {} - starting with an empty set", POSTED_WINDOWS_PATH, e);
+            PostedWindows::empty(POSTED_WINDOWS_PATH)
+        }
+    }));
+    
+    // Merkle accumulator over every batch's window and bitmap hash, rebuilt from its leaf
+    // log (survives restarts); only its frontier stays in memory
+    let accumulator_path = Path::new(ACCUMULATOR_LEAVES_PATH);
+    let mut accumulator = match load_accumulator_leaves(accumulator_path) {
+        Ok(leaves) => MerkleAccumulator::from_leaves(&leaves),
+        Err(e) => {
+            // Keep the unreadable log aside; appending to it would misnumber every later leaf
+            let backup = format!("{}.corrupt", ACCUMULATOR_LEAVES_PATH);
+            error!("Failed to load {}: {} - starting a new accumulator (moved to {})", ACCUMULATOR_LEAVES_PATH, e, backup);
+            if let Err(e) = std::fs::rename(accumulator_path, &backup) {
+                error!("Failed to move {} aside: {}", ACCUMULATOR_LEAVES_PATH, e);
+            }
+            MerkleAccumulator::default()
+        }
+    };
+    // Leaves not yet in the log because appending failed; retried, in order, next window
+    let mut unlogged_leaves: Vec<AccumulatorLeaf> = Vec::new();
+    
+    // Batches waiting for a successful DA post, one queue per per-window DA layer
+    let mut layers: Vec<(PostingPolicy, PendingDaQueue)> = state
+        .config
+        .posting_policies()
+        .into_iter()
+        .filter(|p| p.cadence == PostingCadence::PerWindow)
+        .map(|p| {
+            let queue = PendingDaQueue::new(p.namespace.clone().unwrap_or_default());
+            (p, queue)
+        })
+        .collect();
+    
     // Skip the first immediate tick
     ticker.tick().await;
     
@@ -25,51 +76,44 @@ pub async fn run_batch_generator(state: AppState) {
             .unwrap()
             .as_secs();
         
-        // Get the ring buffer
-        let bits: Vec<SampleBit> = {
-            let ring_buffer = state.ring_buffer.lock().unwrap();
-            ring_buffer.iter().cloned().collect()
-        };
-        
-        if bits.is_empty() {
-            warn!("No samples in ring buffer yet, skipping batch");
+        let Some((mut batch, bitmap_bytes)) = generate_batch(&state, now) else {
             continue;
-        }
-        
-        // Generate batch
-        let n = bits.len();
-        let good = bits.iter().filter(|b| b.ok).count();
-        let threshold = ((n as f64) * state.config.proofs.threshold_percent).ceil() as usize;
-        
-        let window_start = bits.first().map(|b| b.timestamp).unwrap_or(now);
-        let window_end = bits.last().map(|b| b.timestamp).unwrap_or(now);
-        
-        // Create bitmap (1 = ok, 0 = not ok)
-        let bitmap_bytes: Vec<u8> = bits.iter().map(|b| if b.ok { 1 } else { 0 }).collect();
-        
-        // Hash the bitmap
-        let bitmap_hash = blake3::hash(&bitmap_bytes);
-        let bitmap_hash_hex = bitmap_hash.to_hex();
-        
-        // Create batch
-        let batch = Batch {
-            n,
-            good,
-            threshold,
-            bitmap_hash: bitmap_hash_hex.to_string(),
-            window: TimeWindow {
-                start: window_start,
-                end: window_end,
-            },
         };
+        accumulate(&mut batch, &mut accumulator, &state);
+        unlogged_leaves.push(AccumulatorLeaf::of(&batch));
+        let saved = spawn_write({
+            let (leaves, storage) = (unlogged_leaves.clone(), state.config.storage.clone());
+            move || append_accumulator_leaves(accumulator_path, &leaves, &storage)
+        })
+        .await;
+        state.record_storage(&saved);
+        match saved {
+            Ok(()) => unlogged_leaves.clear(),
+            Err(e) => error!("Failed to log accumulator leaf ({} pending): {}", unlogged_leaves.len(), e),
+        }
+        let (n, good, threshold) = (batch.n, batch.good, batch.threshold);
+        Liveness::mark(&state.liveness.last_batch);
+        state.telemetry.record_batch(&batch);
         
         // Save batch
-        if let Err(e) = save_batch(&batch) {
+        let saved = spawn_write({
+            let (batch, storage) = (batch.clone(), state.config.storage.clone());
+            move || save_batch(&batch, &storage)
+        })
+        .await;
+        state.record_storage(&saved);
+        if let Err(e) = saved {
             error!("Failed to save batch: {}", e);
         }
         
         // Save bitmap
-        if let Err(e) = save_bitmap(&bitmap_bytes) {
+        let saved = spawn_write({
+            let (bitmap, storage) = (bitmap_bytes.clone(), state.config.storage.clone());
+            move || save_bitmap(&bitmap, &storage)
+        })
+        .await;
+        state.record_storage(&saved);
+        if let Err(e) = saved {
             error!("Failed to save bitmap: {}", e);
         }
         
@@ -77,44 +121,368 @@ pub async fn run_batch_generator(state: AppState) {
         print_batch_summary(&batch, &bitmap_bytes, &state, now);
         
         let uptime_percent = (good as f64 / n as f64) * 100.0;
-        let meets_threshold = good >= threshold;
         
         info!(
             "✅ Batch generated: n={}, good={}, threshold={}, uptime={:.2}%",
             n, good, threshold, uptime_percent
         );
+        if let Some(weighted_good) = batch.weighted_good {
+            info!("⚖️  Weighted uptime: {:.2}% ({:.2}/{} weighted score)",
+                  weighted_good / n as f64 * 100.0, weighted_good, n);
+        }
         
-        if meets_threshold {
+        let health = batch.health.unwrap_or(classify(good, threshold, batch.warn_threshold));
+        let meets_threshold = health != BatchHealth::Failed;
+        if health == BatchHealth::Degraded {
+            warn!("🟡 Uptime DEGRADED - threshold met ({:.0}%) but below the warn threshold ({:.0}%), got {:.2}%",
+                  state.threshold_percent() * 100.0,
+                  state.config.proofs.warn_threshold_percent.unwrap_or(1.0) * 100.0,
+                  uptime_percent);
+        } else if meets_threshold {
             info!("🎉 Uptime threshold MET ({:.0}%) - Batch ready for ZK proof generation", 
-                  state.config.proofs.threshold_percent * 100.0);
+                  state.threshold_percent() * 100.0);
         } else {
             warn!("⚠️  Uptime threshold NOT MET - ZK proof would fail (need {:.0}%, got {:.2}%)", 
-                  state.config.proofs.threshold_percent * 100.0,
+                  state.threshold_percent() * 100.0,
                   uptime_percent);
         }
         
+        let alerts = state
+            .alert_state
+            .lock()
+            .unwrap()
+            .record_window(health, state.config.alerts.hysteresis_windows);
+        for alert in alerts {
+            match alert {
+                AlertEvent::BelowThreshold => warn!(
+                    "🚨 ALERT: uptime below threshold for {} consecutive window(s)",
+                    state.config.alerts.hysteresis_windows
+                ),
+                AlertEvent::Recovered => info!(
+                    "💚 RECOVERED: uptime met threshold for {} consecutive window(s)",
+                    state.config.alerts.hysteresis_windows
+                ),
+                AlertEvent::Degraded => warn!(
+                    "🟡 WARNING: uptime degraded (below proofs.warn_threshold_percent) for {} consecutive window(s)",
+                    state.config.alerts.hysteresis_windows
+                ),
+            }
+        }
+        
         info!("💾 Batch files saved to data/ directory (batch.json, bitmap.hex)");
         
         // TODO: Generate ZK proof
-        info!("🔐 TODO: Generate ZK proof from this batch");
         // let proof = generate_zk_proof(&batch, &bitmap_bytes).await;
+        let proof: Option<Vec<u8>> = None;
+        if state.config.proofs.enabled {
+            info!("🔐 TODO: Generate ZK proof from this batch");
+        }
+        if let Some(proof) = &proof {
+            let saved = spawn_write({
+                let (batch, proof, dir, storage) =
+                    (batch.clone(), proof.clone(), state.config.proofs.export_dir.clone(), state.config.storage.clone());
+                move || save_proof(Path::new(&dir), &batch, &proof, &storage)
+            })
+            .await;
+            state.record_storage(&saved);
+            match saved {
+                Ok(path) => info!("🔐 Proof exported to {}", path.display()),
+                Err(e) => error!("Failed to export proof: {}", e),
+            }
+        }
         
         // Post batch + proof to DA (verifiable attestation)
         if state.config.da_posting.enabled {
             info!("✅ Individual samples already posted to DA (detailed history)");
-            info!("📡 TODO: Post batch summary + ZK proof to DA (verifiable attestation)");
-            // TODO: Implement batch posting to DA
-            // post_batch_to_da(&batch, &proof, &state).await;
+            for (policy, pending) in &mut layers {
+                let namespace = policy.namespace.as_deref().unwrap_or_default();
+                if posted_windows.lock().unwrap().contains(namespace, &batch.window) {
+                    info!("⏭️  Window {}-{} already posted to DA [{} → {}], skipping",
+                          batch.window.start, batch.window.end, policy.name, namespace);
+                } else {
+                    pending.push(&state, batch.clone(), proof.clone(), now);
+                }
+            }
+            // Batches whose post failed stay queued and are retried here every window
+            for (i, (policy, pending)) in layers.iter_mut().enumerate() {
+                let namespace = policy.namespace.as_deref().unwrap_or_default();
+                for (posted, commitment) in pending.flush(&state, now).await {
+                    info!("📡 Posted batch {} to DA [{} → {}] (commitment {})",
+                          posted.content_hash.as_deref().unwrap_or("-"), policy.name, namespace, commitment);
+                    let marked = spawn_write({
+                        let (posted_windows, namespace, window, storage) =
+                            (posted_windows.clone(), namespace.to_string(), posted.window.clone(), state.config.storage.clone());
+                        move || posted_windows.lock().unwrap().mark_posted(&namespace, &window, &storage)
+                    })
+                    .await;
+                    state.record_storage(&marked);
+                    if let Err(e) = marked {
+                        error!("Failed to record posted window: {}", e);
+                    }
+                    // batch.json carries one commitment: the first batch policy's
+                    if i == 0 {
+                        let saved = spawn_write({
+                            let (posted, commitment, storage) = (posted.clone(), commitment.clone(), state.config.storage.clone());
+                            move || record_batch_commitment(&posted, &commitment, &storage)
+                        })
+                        .await;
+                        state.record_storage(&saved);
+                        if let Err(e) = saved {
+                            error!("Failed to record batch commitment: {}", e);
+                        }
+                    }
+                }
+            }
         } else {
             info!("📡 DA posting disabled - samples and batches stored locally only");
         }
     }
 }
 
+/// Compute a batch over the current ring buffer contents
+///
+/// Returns `None` (after logging why) if there are no countable samples yet.
+/// Shared by the scheduled generator and the on-demand admin endpoint.
+pub fn generate_batch(state: &AppState, now: u64) -> Option<(Batch, Vec<u8>)> {
+    // Get the ring buffer
+    let bits: Vec<SampleBit> = {
+        let ring_buffer = state.ring_buffer.lock().unwrap();
+        ring_buffer.iter().cloned().collect()
+    };
+    
+    if bits.is_empty() {
+        warn!("No samples in ring buffer yet, skipping batch");
+        return None;
+    }
+    
+    let total_bits = bits.len();
+    let bits = counted_bits(bits, state.config.sampling.no_data_policy);
+    if bits.len() < total_bits {
+        info!("⏸️  Excluded {} warmup/no-data samples from batch", total_bits - bits.len());
+    }
+    
+    if bits.is_empty() {
+        warn!("Only warmup/no-data samples in ring buffer, skipping batch");
+        return None;
+    }
+    
+    let proofs = ProofsConfig { threshold_percent: state.threshold_percent(), ..state.config.proofs.clone() };
+    let (mut batch, bitmap_bytes) = build_batch(&bits, &proofs, now);
+    stamp_content_hash(&mut batch, state);
+    Some((batch, bitmap_bytes))
+}
+
+/// Add a batch's leaf to the accumulator and stamp the batch with the new root
+///
+/// The root is part of the attested content, so the content hash is recomputed.
+fn accumulate(batch: &mut Batch, accumulator: &mut MerkleAccumulator, state: &AppState) {
+    let index = accumulator.add(&AccumulatorLeaf::of(batch));
+    batch.accumulator_root = accumulator.root();
+    stamp_content_hash(batch, state);
+    debug!("🌳 Batch is accumulator leaf {} (root {})", index, batch.accumulator_root.as_deref().unwrap_or("-"));
+}
+
+fn stamp_content_hash(batch: &mut Batch, state: &AppState) {
+    let (reader_id, namespace) = (&state.config.da_posting.reader_id, &state.config.celestia.namespace);
+    match batch_content_hash(batch, reader_id, namespace) {
+        Ok(hash) => batch.content_hash = Some(hash),
+        Err(e) => error!("Failed to hash batch: {}", e),
+    }
+}
+
+/// Build a batch and its bitmap from the counted samples of a window
+///
+/// Pure: no locking, I/O, or logging. An empty window yields `n = 0` with the
+/// window collapsed to `now`.
+pub fn build_batch(bits: &[SampleBit], cfg: &ProofsConfig, now: u64) -> (Batch, Vec<u8>) {
+    let n = bits.len();
+    let good = bits.iter().filter(|b| b.ok).count();
+    let reason_weighted = (!cfg.reason_weights.is_empty()).then(|| reason_weighted_sum(bits, &cfg.reason_weights));
+    let threshold = threshold_for(n, cfg.threshold_percent);
+    let warn_threshold = cfg.warn_threshold_percent.map(|p| threshold_for(n, p));
+    // Tolerate float error such as 0.1 * 10 summing to 0.999...
+    let credited = reason_weighted.map_or(good, |sum| (sum + 1e-9).floor() as usize);
+    let health = classify(credited, threshold, warn_threshold);
+    
+    let window_start = bits.first().map(|b| b.timestamp).unwrap_or(now);
+    let window_end = bits.last().map(|b| b.timestamp).unwrap_or(now);
+    
+    // Create bitmap (1 = ok, 0 = not ok)
+    let bitmap_bytes: Vec<u8> = bits.iter().map(|b| if b.ok { 1 } else { 0 }).collect();
+    
+    // Hash the bitmap
+    let bitmap_hash_hex = hash_bitmap(&bitmap_bytes, cfg.hash_algo);
+    let bitmap_hashes = bitmap_hashes(&bitmap_bytes, cfg);
+    
+    let mut categories = CategoryCounts::default();
+    for bit in bits {
+        match ReasonCategory::of(bit.ok, bit.reason_code) {
+            ReasonCategory::Ok => categories.ok += 1,
+            ReasonCategory::Stale => categories.stale += 1,
+            ReasonCategory::StuckHead => categories.stuck_head += 1,
+            ReasonCategory::Other => categories.other += 1,
+        }
+    }
+    let weighted_good = reason_weighted.or_else(|| {
+        bits.iter()
+            .any(|b| b.score.is_some())
+            .then(|| bits.iter().map(|b| b.score.unwrap_or(if b.ok { 1.0 } else { 0.0 })).sum())
+    });
+    let reason_bitmap_hash = cfg
+        .reason_bitmap
+        .then(|| hash_bitmap(&encode_reason_bitmap(bits), cfg.hash_algo));
+    
+    let batch = Batch {
+        n,
+        good,
+        threshold,
+        threshold_percent: Some(cfg.threshold_percent),
+        bitmap_hash: bitmap_hash_hex,
+        bitmap_hashes,
+        window: TimeWindow {
+            start: window_start,
+            end: window_end,
+        },
+        categories,
+        reason_bitmap_hash,
+        weighted_good,
+        content_hash: None,
+        warn_threshold,
+        health: Some(health),
+        da_commitment: None,
+        chain_anchor: None,
+        accumulator_root: None,
+    };
+    
+    (batch, bitmap_bytes)
+}
+
+/// Sum of each sample's `proofs.reason_weights` weight; unlisted reasons count 1 if ok, else 0
+fn reason_weighted_sum(bits: &[SampleBit], weights: &HashMap<SampleReason, f64>) -> f64 {
+    bits.iter()
+        .map(|b| match weights.get(&b.reason_code) {
+            Some(&weight) => weight,
+            None if b.ok => 1.0,
+            None => 0.0,
+        })
+        .sum()
+}
+
+/// Encode each sample's `ReasonCategory` as 2 bits (high bit first), one bit per byte
+/// like the ok bitmap, so it hashes with the same algorithms
+pub fn encode_reason_bitmap(bits: &[SampleBit]) -> Vec<u8> {
+    bits.iter()
+        .flat_map(|b| {
+            let code = ReasonCategory::of(b.ok, b.reason_code) as u8;
+            [(code >> 1) & 1, code & 1]
+        })
+        .collect()
+}
+
+/// Hashes of the bitmap under `hash_algo` and each of `hash_algos`; empty unless
+/// `hash_algos` is configured
+fn bitmap_hashes(bitmap: &[u8], cfg: &ProofsConfig) -> BTreeMap<HashAlgo, String> {
+    if cfg.hash_algos.is_empty() {
+        return BTreeMap::new();
+    }
+    std::iter::once(cfg.hash_algo)
+        .chain(cfg.hash_algos.iter().copied())
+        .map(|algo| (algo, hash_bitmap(bitmap, algo)))
+        .collect()
+}
+
+/// Rebuild a batch's bitmap from recorded samples (e.g. samples.json) and check it
+/// against `batch.bitmap_hash` (and every hash in `batch.bitmap_hashes`)
+///
+/// Samples inside the batch window are counted as the batch generator counts them
+/// (warmup excluded, no-data per `sampling.no_data_policy`) and hashed with
+/// `proofs.hash_algo`. Fails if no recorded sample falls inside the window.
+pub fn verify_batch_against_samples(batch: &Batch, samples: &[Sample], config: &Config) -> anyhow::Result<bool> {
+    let bits: Vec<SampleBit> = samples
+        .iter()
+        .filter(|s| s.timestamp >= batch.window.start && s.timestamp <= batch.window.end)
+        .map(|s| SampleBit {
+            timestamp: s.timestamp,
+            ok: s.ok,
+            reason: s.reason.clone(),
+            no_data: s.reason_code == SampleReason::NoData,
+            warmup: s.warmup,
+            reason_code: s.reason_code,
+            score: s.score,
+        })
+        .collect();
+    anyhow::ensure!(
+        !bits.is_empty(),
+        "no recorded samples in window {}-{}",
+        batch.window.start,
+        batch.window.end
+    );
+
+    let bits = counted_bits(bits, config.sampling.no_data_policy);
+    let (rebuilt, bitmap) = build_batch(&bits, &config.proofs, batch.window.end);
+    let extra_hashes_match = batch
+        .bitmap_hashes
+        .iter()
+        .all(|(algo, hash)| hash_bitmap(&bitmap, *algo) == *hash);
+    Ok(rebuilt.n == batch.n && rebuilt.bitmap_hash == batch.bitmap_hash && extra_hashes_match)
+}
+
+/// Samples that count toward the batch: warmup samples never count, no-data
+/// samples depend on the configured policy
+fn counted_bits(bits: Vec<SampleBit>, policy: NoDataPolicy) -> Vec<SampleBit> {
+    bits.into_iter()
+        .filter(|b| !b.warmup)
+        .filter(|b| policy == NoDataPolicy::Fail || !b.no_data)
+        .collect()
+}
+
+/// Three-state classification of `good` against the target and optional warn threshold
+pub fn classify(good: usize, threshold: usize, warn_threshold: Option<usize>) -> BatchHealth {
+    if good < threshold {
+        BatchHealth::Failed
+    } else if warn_threshold.is_some_and(|warn| good < warn) {
+        BatchHealth::Degraded
+    } else {
+        BatchHealth::Healthy
+    }
+}
+
+/// Minimum number of ok samples for a window of `n` samples to meet `threshold_percent`
+pub fn threshold_for(n: usize, threshold_percent: f64) -> usize {
+    ((n as f64) * threshold_percent).ceil() as usize
+}
+
+/// Why `threshold_percent` can't be represented exactly with `window_size` samples, if it can't
+///
+/// A window of n samples only expresses uptimes in steps of 1/n, so the threshold is
+/// effectively rounded up to the next step (99.9% over 20 samples means 20/20).
+pub fn threshold_granularity_warning(window_size: usize, threshold_percent: f64) -> Option<String> {
+    if window_size == 0 {
+        return None;
+    }
+    let required = threshold_for(window_size, threshold_percent);
+    let effective = required as f64 / window_size as f64;
+    if (effective - threshold_percent).abs() <= 1e-9 {
+        return None;
+    }
+    let all_or_nothing = if required == window_size { " (all or nothing)" } else { "" };
+    Some(format!(
+        "proofs.threshold_percent = {:.2}% can't be represented with {} samples per window \
+        (granularity {:.2}%); it effectively requires {}/{} = {:.2}%{}",
+        threshold_percent * 100.0,
+        window_size,
+        100.0 / window_size as f64,
+        required,
+        window_size,
+        effective * 100.0,
+        all_or_nothing
+    ))
+}
+
 /// Print batch summary for visual clarity
 fn print_batch_summary(batch: &Batch, bitmap_bytes: &[u8], state: &AppState, now: u64) {
     let uptime_percent = (batch.good as f64 / batch.n as f64) * 100.0;
-    let meets_threshold = batch.good >= batch.threshold;
+    let meets_threshold = batch.health.map_or(batch.good >= batch.threshold, |h| h != BatchHealth::Failed);
     
     println!("\n{}", "=".repeat(80));
     println!("📦 BATCH GENERATED FOR ZK PROOF");
@@ -129,35 +497,345 @@ fn print_batch_summary(batch: &Batch, bitmap_bytes: &[u8], state: &AppState, now
     println!("   Successful (OK):   {}", batch.good);
     println!("   Failed:            {}", batch.n - batch.good);
     println!("   Uptime:            {:.2}%", uptime_percent);
-    println!("   Threshold:         {} ({:.0}%)", batch.threshold, state.config.proofs.threshold_percent * 100.0);
+    println!("   Threshold:         {} ({:.0}%)", batch.threshold, state.threshold_percent() * 100.0);
+    if let (Some(warn), Some(percent)) = (batch.warn_threshold, state.config.proofs.warn_threshold_percent) {
+        println!("   Warn Threshold:    {} ({:.0}%)", warn, percent * 100.0);
+    }
     println!("   Meets Threshold:   {} {}", 
              if meets_threshold { "✅ YES" } else { "❌ NO" },
              if meets_threshold { "" } else { "(Would not generate proof)" });
+    match batch.health {
+        Some(BatchHealth::Healthy) => println!("   Status:            ✅ HEALTHY"),
+        Some(BatchHealth::Degraded) => println!("   Status:            🟡 DEGRADED (threshold met, below warn threshold)"),
+        Some(BatchHealth::Failed) => println!("   Status:            ❌ FAILED"),
+        None => {}
+    }
     println!("\n🔐 Cryptographic Data:");
     println!("   Bitmap Hash:       {}", batch.bitmap_hash);
     println!("   Bitmap Length:     {} bytes", bitmap_bytes.len());
+    if let Some(root) = &batch.accumulator_root {
+        println!("   Accumulator Root:  {}", root);
+    }
     println!("\n📄 Files Written:");
     println!("   - data/batch.json");
+    println!("   - data/batches/{}-{}.json", batch.window.start, batch.window.end);
     println!("   - data/bitmap.hex");
+    println!("   - data/accumulator_leaves.jsonl");
     println!("   - data/samples.json");
     println!("\n💾 What would be posted to DA:");
     
-    let da_payload = serde_json::json!({
-        "batch": {
-            "n": batch.n,
-            "good": batch.good,
-            "threshold": batch.threshold,
-            "bitmap_hash": batch.bitmap_hash,
-            "window": {
-                "start": batch.window.start,
-                "end": batch.window.end,
-            }
-        },
-        "namespace": state.config.celestia.namespace,
-        "timestamp": now,
-    });
+    let da_payload = build_batch_payload(
+        batch,
+        &state.config.da_posting.reader_id,
+        &state.config.celestia.namespace,
+        now,
+    );
     
     println!("{}", serde_json::to_string_pretty(&da_payload).unwrap());
     println!("{}\n", "=".repeat(80));
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::proofs::HashAlgo;
+
+    fn bit(timestamp: u64, ok: bool, no_data: bool) -> SampleBit {
+        SampleBit {
+            timestamp,
+            ok,
+            no_data,
+            ..Default::default()
+        }
+    }
+
+    fn no_data_stretch() -> Vec<SampleBit> {
+        vec![
+            bit(0, false, true),
+            bit(30, false, true),
+            bit(60, false, true),
+            bit(90, true, false),
+            bit(120, true, false),
+        ]
+    }
+
+    #[test]
+    fn test_no_data_policy_fail_counts_downtime() {
+        let bits = counted_bits(no_data_stretch(), NoDataPolicy::Fail);
+        assert_eq!(bits.len(), 5);
+        assert_eq!(bits.iter().filter(|b| b.ok).count(), 2);
+    }
+
+    #[test]
+    fn test_no_data_policy_neutral_excludes_ticks() {
+        let bits = counted_bits(no_data_stretch(), NoDataPolicy::Neutral);
+        assert_eq!(bits.len(), 2);
+        assert!(bits.iter().all(|b| b.ok));
+        assert_eq!(bits[0].timestamp, 90);
+    }
+
+    #[test]
+    fn test_warmup_samples_do_not_affect_ratio() {
+        let mut bits = vec![
+            SampleBit { timestamp: 0, ok: false, warmup: true, ..Default::default() },
+            SampleBit { timestamp: 30, ok: false, warmup: true, ..Default::default() },
+        ];
+        bits.extend((60..=150).step_by(30).map(|t| bit(t, true, false)));
+
+        let counted = counted_bits(bits, NoDataPolicy::Fail);
+        assert_eq!(counted.len(), 4);
+        assert_eq!(counted.iter().filter(|b| b.ok).count(), 4);
+    }
+
+    fn proofs_cfg() -> ProofsConfig {
+        Config::for_tests().proofs
+    }
+
+    #[test]
+    fn test_accumulate_stamps_root_into_attested_content() {
+        let state = AppState::new(std::sync::Arc::new(Config::for_tests()));
+        let mut accumulator = MerkleAccumulator::default();
+        let (mut first, _) = build_batch(&[bit(30, true, false)], &proofs_cfg(), 30);
+        accumulate(&mut first, &mut accumulator, &state);
+        let (mut second, _) = build_batch(&[bit(60, false, false)], &proofs_cfg(), 60);
+        stamp_content_hash(&mut second, &state);
+        let unaccumulated_hash = second.content_hash.clone();
+        accumulate(&mut second, &mut accumulator, &state);
+
+        assert_eq!(second.accumulator_root, accumulator.root());
+        assert_ne!(first.accumulator_root, second.accumulator_root);
+        // The root is covered by the content hash (and so by the signature)
+        assert_ne!(second.content_hash, unaccumulated_hash);
+        let leaves = [AccumulatorLeaf::of(&first), AccumulatorLeaf::of(&second)];
+        let proof = crate::proofs::prove_inclusion(&leaves, 0).unwrap();
+        assert!(proof.verify(&leaves[0], second.accumulator_root.as_deref().unwrap()));
+    }
+
+    #[test]
+    fn test_build_batch_empty_window() {
+        let (batch, bitmap) = build_batch(&[], &proofs_cfg(), 500);
+        assert_eq!((batch.n, batch.good, batch.threshold), (0, 0, 0));
+        assert_eq!((batch.window.start, batch.window.end), (500, 500));
+        assert!(bitmap.is_empty());
+        assert_eq!(batch.bitmap_hash, blake3::hash(&[]).to_hex().to_string());
+    }
+
+    #[test]
+    fn test_build_batch_all_ok() {
+        let bits: Vec<_> = (0..20).map(|i| bit(i * 30, true, false)).collect();
+        let (batch, bitmap) = build_batch(&bits, &proofs_cfg(), 9999);
+        assert_eq!((batch.n, batch.good, batch.threshold), (20, 20, 19));
+        assert_eq!((batch.window.start, batch.window.end), (0, 570));
+        assert_eq!(bitmap, vec![1; 20]);
+    }
+
+    #[test]
+    fn test_build_batch_all_fail() {
+        let bits: Vec<_> = (0..20).map(|i| bit(i * 30, false, false)).collect();
+        let (batch, bitmap) = build_batch(&bits, &proofs_cfg(), 9999);
+        assert_eq!((batch.n, batch.good, batch.threshold), (20, 0, 19));
+        assert_eq!(bitmap, vec![0; 20]);
+    }
+
+    #[test]
+    fn test_build_batch_mixed() {
+        let bits = vec![bit(100, true, false), bit(130, false, false), bit(160, true, false)];
+        let (batch, bitmap) = build_batch(&bits, &proofs_cfg(), 9999);
+        assert_eq!((batch.n, batch.good, batch.threshold), (3, 2, 3));
+        assert_eq!((batch.window.start, batch.window.end), (100, 160));
+        assert_eq!(bitmap, vec![1, 0, 1]);
+        assert_eq!(batch.bitmap_hash, hash_bitmap(&[1, 0, 1], HashAlgo::Blake3));
+    }
+
+    #[test]
+    fn test_reason_categories_counted_and_encoded() {
+        let reasoned = |timestamp, ok, reason_code| SampleBit { timestamp, ok, reason_code, ..Default::default() };
+        let bits = vec![
+            reasoned(0, true, SampleReason::Advancing),
+            reasoned(30, false, SampleReason::Stale),
+            reasoned(60, false, SampleReason::StuckHead),
+            reasoned(90, false, SampleReason::HeadersStalled),
+            reasoned(120, false, SampleReason::NoData),
+            reasoned(150, true, SampleReason::FreshData),
+        ];
+
+        let mut cfg = proofs_cfg();
+        let (batch, _) = build_batch(&bits, &cfg, 9999);
+        assert_eq!(
+            batch.categories,
+            CategoryCounts { ok: 2, stale: 2, stuck_head: 1, other: 1 }
+        );
+        assert_eq!(batch.reason_bitmap_hash, None);
+
+        // ok=00, stale=01, stuck=10, other=11
+        let encoded = encode_reason_bitmap(&bits);
+        assert_eq!(encoded, vec![0, 0, 0, 1, 1, 0, 1, 1, 0, 1, 0, 0]);
+
+        cfg.reason_bitmap = true;
+        let (batch, bitmap) = build_batch(&bits, &cfg, 9999);
+        assert_eq!(batch.reason_bitmap_hash, Some(hash_bitmap(&encoded, HashAlgo::Blake3)));
+        // The ok/fail bitmap is unchanged
+        assert_eq!(bitmap, vec![1, 0, 0, 0, 0, 1]);
+    }
+
+    fn mixed_reason_window() -> Vec<SampleBit> {
+        let reasoned = |timestamp, ok, reason_code| SampleBit { timestamp, ok, reason_code, ..Default::default() };
+        vec![
+            reasoned(0, true, SampleReason::Advancing),
+            reasoned(30, true, SampleReason::Advancing),
+            reasoned(60, true, SampleReason::FreshData),
+            reasoned(90, true, SampleReason::FreshData),
+            reasoned(120, false, SampleReason::HeadersStalled),
+            reasoned(150, false, SampleReason::HeadersStalled),
+            reasoned(180, false, SampleReason::Stale),
+            reasoned(210, false, SampleReason::StuckHead),
+        ]
+    }
+
+    #[test]
+    fn test_default_reason_weights_count_ok_samples() {
+        let (batch, _) = build_batch(&mixed_reason_window(), &proofs_cfg(), 9999);
+        assert_eq!(batch.good, 4);
+    }
+
+    #[test]
+    fn test_custom_reason_weights_weight_good_count() {
+        let mut cfg = proofs_cfg();
+        cfg.reason_weights = HashMap::from([
+            (SampleReason::FreshData, 0.75),
+            (SampleReason::HeadersStalled, 0.5),
+            (SampleReason::Stale, 0.0),
+        ]);
+        // 2 advancing (unlisted, ok) + 2 * 0.75 + 2 * 0.5 + stale 0 + stuck head (unlisted, failed) 0 = 4.5
+        let (batch, bitmap) = build_batch(&mixed_reason_window(), &cfg, 9999);
+        assert!((batch.weighted_good.unwrap() - 4.5).abs() < 1e-9);
+        // `good` stays the bitmap's popcount
+        assert_eq!(batch.good, 4);
+        assert_eq!(bitmap, vec![1, 1, 1, 1, 0, 0, 0, 0]);
+
+        // Health compares the weighted sum, rounded down, with the threshold
+        let stalled = |n: u64| (0..n)
+            .map(|t| SampleBit { timestamp: t, ok: true, reason_code: SampleReason::HeadersStalled, ..Default::default() })
+            .collect::<Vec<_>>();
+        cfg.threshold_percent = 0.5;
+        cfg.reason_weights.insert(SampleReason::HeadersStalled, 0.45);
+        let (batch, _) = build_batch(&stalled(10), &cfg, 9999);
+        assert_eq!((batch.good, batch.threshold), (10, 5));
+        assert_eq!(batch.health, Some(BatchHealth::Failed)); // 4.5
+
+        // Many small weights summing to a whole number aren't lost to float error
+        cfg.reason_weights.insert(SampleReason::HeadersStalled, 0.1);
+        cfg.threshold_percent = 0.1;
+        let (batch, _) = build_batch(&stalled(10), &cfg, 9999);
+        assert_eq!(batch.health, Some(BatchHealth::Healthy));
+    }
+
+    #[test]
+    fn test_weighted_good_sums_scores() {
+        let scored = |timestamp, ok, score| SampleBit { timestamp, ok, score, ..Default::default() };
+        let (batch, _) = build_batch(&[bit(0, true, false), bit(30, false, false)], &proofs_cfg(), 9999);
+        assert_eq!(batch.weighted_good, None);
+
+        let bits = vec![
+            scored(0, true, Some(1.0)),
+            scored(30, false, Some(0.7)),
+            scored(60, false, Some(0.0)),
+            // Recorded before weights were configured
+            scored(90, true, None),
+        ];
+        let (batch, _) = build_batch(&bits, &proofs_cfg(), 9999);
+        assert!((batch.weighted_good.unwrap() - 2.7).abs() < 1e-9);
+        assert_eq!(batch.good, 2);
+    }
+
+    #[test]
+    fn test_health_bands() {
+        let mut cfg = proofs_cfg();
+        cfg.warn_threshold_percent = Some(0.98);
+        // 100 samples: target 95 ok, warn 98 ok
+        let window = |good: u64| (0..100).map(|i| bit(i * 30, i < good, false)).collect::<Vec<_>>();
+        let health = |good| build_batch(&window(good), &cfg, 9999).0;
+
+        let healthy = health(99);
+        assert_eq!((healthy.threshold, healthy.warn_threshold), (95, Some(98)));
+        assert_eq!(healthy.health, Some(BatchHealth::Healthy));
+        assert_eq!(health(98).health, Some(BatchHealth::Healthy));
+        assert_eq!(health(96).health, Some(BatchHealth::Degraded));
+        assert_eq!(health(95).health, Some(BatchHealth::Degraded));
+        assert_eq!(health(94).health, Some(BatchHealth::Failed));
+
+        // Without a warn threshold there is no degraded band
+        let (batch, _) = build_batch(&window(96), &proofs_cfg(), 9999);
+        assert_eq!((batch.warn_threshold, batch.health), (None, Some(BatchHealth::Healthy)));
+    }
+
+    #[test]
+    fn test_verify_batch_against_samples() {
+        let config = Config::for_tests();
+        let samples: Vec<Sample> = (0..20)
+            .map(|i| Sample { timestamp: 1000 + i * 30, ok: i != 7, warmup: i == 0, ..Default::default() })
+            .collect();
+        let bits: Vec<SampleBit> = samples
+            .iter()
+            .filter(|s| !s.warmup)
+            .map(|s| bit(s.timestamp, s.ok, false))
+            .collect();
+        let (batch, _) = build_batch(&bits, &config.proofs, 9999);
+
+        // Samples outside the window don't matter
+        let mut history = samples.clone();
+        history.push(Sample { timestamp: 5000, ok: false, ..Default::default() });
+        assert!(verify_batch_against_samples(&batch, &history, &config).unwrap());
+
+        // A flipped sample no longer matches the committed bitmap
+        history[7].ok = true;
+        assert!(!verify_batch_against_samples(&batch, &history, &config).unwrap());
+
+        assert!(verify_batch_against_samples(&batch, &[], &config).is_err());
+    }
+
+    #[test]
+    fn test_dual_hashing_carries_both_algorithms() {
+        let bits = [bit(0, true, false), bit(30, false, false), bit(60, true, false)];
+        let bitmap = [1, 0, 1];
+
+        // Without hash_algos the batch format is unchanged
+        let (single, _) = build_batch(&bits, &proofs_cfg(), 60);
+        assert!(single.bitmap_hashes.is_empty());
+        assert!(!serde_json::to_string(&single).unwrap().contains("bitmap_hashes"));
+
+        let cfg = ProofsConfig { hash_algos: vec![HashAlgo::Poseidon], ..proofs_cfg() };
+        let (dual, _) = build_batch(&bits, &cfg, 60);
+        assert_eq!(dual.bitmap_hash, single.bitmap_hash);
+        assert_eq!(dual.bitmap_hashes.len(), 2);
+        assert_eq!(dual.bitmap_hashes[&HashAlgo::Blake3], blake3::hash(&bitmap).to_hex().to_string());
+        assert_eq!(dual.bitmap_hashes[&HashAlgo::Poseidon], hash_bitmap(&bitmap, HashAlgo::Poseidon));
+        let json = serde_json::to_value(&dual).unwrap();
+        assert_eq!(json["bitmap_hashes"]["poseidon"], hash_bitmap(&bitmap, HashAlgo::Poseidon));
+
+        // Each carried hash is checked when verifying against samples
+        let mut config = Config::for_tests();
+        config.proofs.hash_algos = vec![HashAlgo::Poseidon];
+        let samples: Vec<Sample> = bits.iter().map(|b| Sample { timestamp: b.timestamp, ok: b.ok, ..Default::default() }).collect();
+        assert!(verify_batch_against_samples(&dual, &samples, &config).unwrap());
+        let mut tampered = dual.clone();
+        tampered.bitmap_hashes.insert(HashAlgo::Poseidon, "00".repeat(32));
+        assert!(!verify_batch_against_samples(&tampered, &samples, &config).unwrap());
+    }
+
+    #[test]
+    fn test_threshold_granularity_warning() {
+        // 95% of 20 samples is exactly 19
+        assert_eq!(threshold_granularity_warning(20, 0.95), None);
+        assert_eq!(threshold_granularity_warning(20, 1.0), None);
+
+        let warning = threshold_granularity_warning(20, 0.999).unwrap();
+        assert!(warning.contains("granularity 5.00%"), "{}", warning);
+        assert!(warning.contains("20/20 = 100.00% (all or nothing)"), "{}", warning);
+
+        // Longer windows resolve the same threshold
+        assert_eq!(threshold_granularity_warning(1000, 0.999), None);
+    }
+}
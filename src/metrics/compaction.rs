@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, error, info, warn};
+use crate::config::StorageBackend;
+use crate::storage::{save_samples_to, save_summaries, spawn_write, SAMPLES_PATH, SUMMARIES_PATH};
+use crate::types::{AppState, Sample, SampleSummary};
+
+/// Background task: collapse raw samples older than `storage.downsample_after_secs`
+/// into per-bucket ok/total counts
+pub async fn run_compactor(state: AppState) {
+    let Some(horizon_secs) = state.config.storage.downsample_after_secs else {
+        return;
+    };
+    let bucket_secs = state.config.storage.downsample_bucket_secs;
+    let mut ticker = tokio::time::interval(Duration::from_secs(bucket_secs));
+    info!("🗜️  Compactor started (samples older than {}s kept as {}s buckets)", horizon_secs, bucket_secs);
+
+    loop {
+        ticker.tick().await;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let horizon = now.saturating_sub(horizon_secs);
+        let compacted = spawn_write({
+            let state = state.clone();
+            move || compact(&state, Path::new(SUMMARIES_PATH), Path::new(SAMPLES_PATH), horizon, bucket_secs)
+        })
+        .await;
+        state.record_storage(&compacted);
+        match compacted {
+            Ok(0) => {}
+            Ok(n) => debug!("🗜️  Downsampled {} samples into {} buckets", n, state.sample_summaries.lock().unwrap().len()),
+            Err(e) => error!("Failed to save sample summaries, keeping raw samples: {}", e),
+        }
+    }
+}
+
+/// One compaction pass: fold samples taken before `horizon` into the summaries and save
+/// them to `summaries_path`, and only then drop those samples from memory and from
+/// `samples_path`; returns how many samples were folded
+///
+/// If the summaries can't be saved nothing is dropped, so the raw history is never lost
+/// before its buckets are on disk.
+fn compact(
+    state: &AppState,
+    summaries_path: &Path,
+    samples_path: &Path,
+    horizon: u64,
+    bucket_secs: u64,
+) -> anyhow::Result<usize> {
+    let (old, summaries) = {
+        let samples = state.samples.lock().unwrap();
+        let mut summaries = state.sample_summaries.lock().unwrap().clone();
+        (summarize(&samples, &mut summaries, horizon, bucket_secs), summaries)
+    };
+    if old == 0 {
+        return Ok(0);
+    }
+    // Never moves back (e.g. downsample_after_secs raised across a restart): samples before
+    // the old watermark are counted already
+    let compacted_before = horizon.max(*state.compacted_before.lock().unwrap());
+    save_summaries(summaries_path, &summaries, compacted_before, &state.config.storage)?;
+    *state.sample_summaries.lock().unwrap() = summaries;
+    *state.compacted_before.lock().unwrap() = compacted_before;
+
+    // The summarized samples are the ones before `horizon`; the sampler may have appended
+    // (or, with the sqlite backend, dropped old samples) since
+    let remaining: Vec<Sample> = {
+        let mut samples = state.samples.lock().unwrap();
+        let summarized = samples.partition_point(|sample| sample.timestamp < horizon);
+        samples.drain(..summarized);
+        samples.clone()
+    };
+    if state.config.storage.backend == StorageBackend::Json {
+        let saved = save_samples_to(samples_path, &remaining, &state.config.storage);
+        state.telemetry.record_storage(&saved);
+        if let Err(e) = saved {
+            // The next regular flush drops them from the file
+            warn!("Failed to rewrite {} after compaction: {}", samples_path.display(), e);
+        }
+    }
+    Ok(old)
+}
+
+/// Count the leading samples taken before `horizon` into `bucket_secs`-wide summaries,
+/// returning how many there were
+///
+/// `samples` is in timestamp order, as the sampler appends it. Warmup samples count as
+/// summarized but go into no bucket, as they are excluded from uptime.
+fn summarize(
+    samples: &[Sample],
+    summaries: &mut BTreeMap<u64, SampleSummary>,
+    horizon: u64,
+    bucket_secs: u64,
+) -> usize {
+    let old = samples.iter().take_while(|s| s.timestamp < horizon).count();
+    for sample in samples[..old].iter().filter(|s| !s.warmup) {
+        let start = sample.timestamp - sample.timestamp % bucket_secs;
+        let bucket = summaries.entry(start).or_insert_with(|| SampleSummary { start, secs: bucket_secs, ..Default::default() });
+        bucket.total += 1;
+        bucket.ok += usize::from(sample.ok);
+    }
+    old
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::config::Config;
+    use crate::metrics::restore_samples;
+    use crate::storage::{init_db_at, insert_sample, load_samples_db, load_samples_from, load_summaries};
+
+    fn downsample(samples: &mut Vec<Sample>, summaries: &mut BTreeMap<u64, SampleSummary>, horizon: u64, bucket_secs: u64) -> usize {
+        let old = summarize(samples, summaries, horizon, bucket_secs);
+        samples.drain(..old);
+        old
+    }
+
+    #[test]
+    fn test_old_samples_downsampled_recent_kept_raw() {
+        // One sample every 30s for two hours; every 4th fails
+        let mut samples: Vec<Sample> = (0..240)
+            .map(|i| Sample { timestamp: i * 30, ok: i % 4 != 0, ..Default::default() })
+            .collect();
+        let mut summaries = BTreeMap::new();
+
+        let moved = downsample(&mut samples, &mut summaries, 3600, 3600);
+        assert_eq!(moved, 120);
+        assert_eq!(samples.len(), 120);
+        assert!(samples.iter().all(|s| s.timestamp >= 3600));
+
+        let hour = &summaries[&0];
+        assert_eq!((hour.total, hour.ok, hour.secs), (120, 90, 3600));
+
+        // A later pass only touches what has aged since
+        assert_eq!(downsample(&mut samples, &mut summaries, 3600, 3600), 0);
+    }
+
+    #[test]
+    fn test_downsample_merges_into_existing_bucket_and_skips_warmup() {
+        let mut summaries = BTreeMap::new();
+        let mut samples = vec![
+            Sample { timestamp: 0, ok: true, warmup: true, ..Default::default() },
+            Sample { timestamp: 30, ok: true, ..Default::default() },
+        ];
+        downsample(&mut samples, &mut summaries, 60, 60);
+        let mut samples = vec![Sample { timestamp: 45, ok: false, ..Default::default() }];
+        downsample(&mut samples, &mut summaries, 60, 60);
+
+        assert!(samples.is_empty());
+        assert_eq!((summaries[&0].total, summaries[&0].ok), (2, 1));
+    }
+
+    #[test]
+    fn test_buckets_survive_two_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let summaries_path = dir.path().join("sample_summaries.json");
+        let samples_path = dir.path().join("samples.json");
+        let config = Arc::new(Config::for_tests());
+        // What main does at startup: pick up saved buckets and the raw history
+        let start = || {
+            let state = AppState::new(config.clone());
+            let (summaries, compacted_before) = load_summaries(&summaries_path).unwrap();
+            *state.sample_summaries.lock().unwrap() = summaries;
+            *state.compacted_before.lock().unwrap() = compacted_before;
+            if samples_path.exists() {
+                *state.samples.lock().unwrap() = load_samples_from(&samples_path).unwrap();
+            }
+            state
+        };
+        let record_hour = |state: &AppState, hour: u64| {
+            let mut samples = state.samples.lock().unwrap();
+            samples.extend((0..120).map(|i| Sample { timestamp: hour * 3600 + i * 30, ok: i % 4 != 0, ..Default::default() }));
+        };
+
+        let state = start();
+        record_hour(&state, 0);
+        assert_eq!(compact(&state, &summaries_path, &samples_path, 3600, 3600).unwrap(), 120);
+
+        let state = start();
+        record_hour(&state, 1);
+        record_hour(&state, 2);
+        assert_eq!(compact(&state, &summaries_path, &samples_path, 7200, 3600).unwrap(), 120);
+        // Compacted samples are gone from the raw history, so a restart can't count them twice
+        assert_eq!(load_samples_from(&samples_path).unwrap().len(), 120);
+
+        let state = start();
+        let summaries = state.sample_summaries.lock().unwrap();
+        assert_eq!(summaries.keys().copied().collect::<Vec<_>>(), [0, 3600]);
+        assert!(summaries.values().all(|bucket| (bucket.total, bucket.ok) == (120, 90)));
+        assert_eq!(state.samples.lock().unwrap().len(), 120);
+    }
+
+    #[test]
+    fn test_sqlite_restart_does_not_recount_compacted_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let summaries_path = dir.path().join("sample_summaries.json");
+        let samples_path = dir.path().join("samples.json");
+        let mut config = Config::for_tests();
+        config.storage.backend = StorageBackend::Sqlite;
+        let config = Arc::new(config);
+        // samples.db keeps every row, compacted or not
+        let conn = init_db_at(&dir.path().join("samples.db")).unwrap();
+        // What main does at startup: summaries first, then the rows newer than the retention
+        // horizon (all of them here)
+        let restart = || {
+            let state = AppState::new(config.clone());
+            let (summaries, compacted_before) = load_summaries(&summaries_path).unwrap();
+            *state.sample_summaries.lock().unwrap() = summaries;
+            *state.compacted_before.lock().unwrap() = compacted_before;
+            restore_samples(&state, load_samples_db(&conn, 0).unwrap(), 7200);
+            state
+        };
+
+        let state = AppState::new(config.clone());
+        for i in 0..240 {
+            let sample = Sample { timestamp: i * 30, ok: i % 4 != 0, ..Default::default() };
+            insert_sample(&conn, &sample).unwrap();
+            state.samples.lock().unwrap().push(sample);
+        }
+        assert_eq!(compact(&state, &summaries_path, &samples_path, 3600, 3600).unwrap(), 120);
+
+        let state = restart();
+        assert_eq!(state.samples.lock().unwrap().len(), 120);
+        // The compactor's first tick after the restart, same horizon: nothing counted twice
+        assert_eq!(compact(&state, &summaries_path, &samples_path, 3600, 3600).unwrap(), 0);
+        // A later horizon folds only the second hour
+        assert_eq!(compact(&state, &summaries_path, &samples_path, 7200, 3600).unwrap(), 120);
+
+        let state = restart();
+        assert!(state.samples.lock().unwrap().is_empty());
+        assert_eq!(compact(&state, &summaries_path, &samples_path, 7200, 3600).unwrap(), 0);
+        let summaries = state.sample_summaries.lock().unwrap();
+        assert_eq!(summaries.keys().copied().collect::<Vec<_>>(), [0, 3600]);
+        assert!(summaries.values().all(|bucket| (bucket.total, bucket.ok) == (120, 90)));
+    }
+
+    #[test]
+    fn test_failed_save_keeps_raw_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        state.samples.lock().unwrap().extend((0..4).map(|i| Sample { timestamp: i * 30, ..Default::default() }));
+
+        let missing_dir = dir.path().join("missing").join("sample_summaries.json");
+        assert!(compact(&state, &missing_dir, &dir.path().join("samples.json"), 3600, 3600).is_err());
+        assert_eq!(state.samples.lock().unwrap().len(), 4);
+        assert!(state.sample_summaries.lock().unwrap().is_empty());
+    }
+}
@@ -1,6 +1,17 @@
 mod sampler;
 mod batch;
+mod alerts;
+mod secondary;
+mod reliability;
+mod compaction;
+mod watchdog;
+mod ring;
 
-pub use sampler::run_sampler;
-pub use batch::run_batch_generator;
+pub use sampler::{run_sampler, flush_samples, restore_samples};
+pub use batch::{run_batch_generator, generate_batch, threshold_for, threshold_granularity_warning, verify_batch_against_samples};
+pub use alerts::AlertState;
+pub use reliability::{compute_reliability, Reliability};
+pub use compaction::run_compactor;
+pub use watchdog::run_watchdog;
+pub use ring::SampleRing;
 
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use crate::types::Sample;
+
+/// Reliability KPIs derived from ok/fail transitions in the sample series
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reliability {
+    /// Mean time between failures: mean length of up periods that ended in a failure
+    pub mtbf_secs: Option<f64>,
+    /// Mean time to recovery: mean length of down periods that ended in a recovery
+    pub mttr_secs: Option<f64>,
+    /// ok -> fail transitions
+    pub failures: usize,
+    /// fail -> ok transitions
+    pub recoveries: usize,
+    /// Samples considered (warmup samples are skipped)
+    pub samples: usize,
+}
+
+/// Compute MTBF/MTTR from chronologically ordered samples
+///
+/// A period runs from its first sample to the first sample of the next state, so
+/// the current (unfinished) period never counts. With no completed up or down
+/// period the corresponding mean is `None`.
+pub fn compute_reliability(samples: &[Sample]) -> Reliability {
+    let counted: Vec<&Sample> = samples.iter().filter(|s| !s.warmup).collect();
+
+    let mut up_periods = Vec::new();
+    let mut down_periods = Vec::new();
+    if let Some(first) = counted.first() {
+        let (mut state, mut since) = (first.ok, first.timestamp);
+        for sample in &counted[1..] {
+            if sample.ok != state {
+                let length = sample.timestamp.saturating_sub(since) as f64;
+                if state { up_periods.push(length) } else { down_periods.push(length) }
+                state = sample.ok;
+                since = sample.timestamp;
+            }
+        }
+    }
+
+    Reliability {
+        mtbf_secs: mean(&up_periods),
+        mttr_secs: mean(&down_periods),
+        failures: up_periods.len(),
+        recoveries: down_periods.len(),
+        samples: counted.len(),
+    }
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One sample every 30s following the given ok pattern
+    fn series(pattern: &str) -> Vec<Sample> {
+        pattern
+            .chars()
+            .enumerate()
+            .map(|(i, c)| Sample { timestamp: 1000 + i as u64 * 30, ok: c == '1', ..Default::default() })
+            .collect()
+    }
+
+    #[test]
+    fn test_known_up_and_down_intervals() {
+        // up 4 ticks (120s), down 2 (60s), up 6 (180s), down 4 (120s), up (ongoing)
+        let reliability = compute_reliability(&series("1111001111110000111"));
+        assert_eq!(reliability.failures, 2);
+        assert_eq!(reliability.recoveries, 2);
+        assert_eq!(reliability.mtbf_secs, Some(150.0));
+        assert_eq!(reliability.mttr_secs, Some(90.0));
+        assert_eq!(reliability.samples, 19);
+    }
+
+    #[test]
+    fn test_no_transitions_has_no_means() {
+        for pattern in ["", "1111", "000"] {
+            let reliability = compute_reliability(&series(pattern));
+            assert_eq!((reliability.mtbf_secs, reliability.mttr_secs), (None, None), "{:?}", pattern);
+            assert_eq!((reliability.failures, reliability.recoveries), (0, 0));
+        }
+    }
+
+    #[test]
+    fn test_unfinished_outage_has_no_mttr() {
+        let reliability = compute_reliability(&series("11100"));
+        assert_eq!(reliability.mtbf_secs, Some(90.0));
+        assert_eq!(reliability.mttr_secs, None);
+    }
+
+    #[test]
+    fn test_warmup_samples_skipped() {
+        let mut samples = series("00111100");
+        samples[0].warmup = true;
+        samples[1].warmup = true;
+        let reliability = compute_reliability(&samples);
+        assert_eq!(reliability.failures, 1);
+        assert_eq!(reliability.recoveries, 0);
+        assert_eq!(reliability.mtbf_secs, Some(120.0));
+    }
+}
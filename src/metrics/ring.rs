@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+use crate::types::SampleBit;
+
+/// The current batch window of samples, with running ok/total counts so the
+/// window's uptime is O(1) to read however long the reader has been up
+///
+/// Warmup samples are kept but not counted, as they are excluded from uptime.
+#[derive(Debug, Default)]
+pub struct SampleRing {
+    bits: VecDeque<SampleBit>,
+    good: usize,
+    total: usize,
+}
+
+impl SampleRing {
+    /// Append `bit`, evicting the oldest samples beyond `capacity`
+    pub fn push(&mut self, bit: SampleBit, capacity: usize) {
+        self.count(&bit, true);
+        self.bits.push_back(bit);
+        while self.bits.len() > capacity {
+            if let Some(evicted) = self.bits.pop_front() {
+                self.count(&evicted, false);
+            }
+        }
+    }
+
+    fn count(&mut self, bit: &SampleBit, added: bool) {
+        if bit.warmup {
+            return;
+        }
+        let good = usize::from(bit.ok);
+        if added {
+            self.good += good;
+            self.total += 1;
+        } else {
+            self.good -= good;
+            self.total -= 1;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SampleBit> {
+        self.bits.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Ok and counted (non-warmup) samples in the window
+    pub fn counts(&self) -> (usize, usize) {
+        (self.good, self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn test_incremental_counts_match_full_recount() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut ring = SampleRing::default();
+        for i in 0..5_000u64 {
+            let bit = SampleBit { timestamp: i, ok: rng.gen_bool(0.8), warmup: rng.gen_bool(0.05), ..Default::default() };
+            // Shrinking the capacity now and then evicts several samples at once
+            let capacity = if i % 997 == 0 { 5 } else { 20 };
+            ring.push(bit, capacity);
+
+            let counted: Vec<_> = ring.iter().filter(|b| !b.warmup).collect();
+            let good = counted.iter().filter(|b| b.ok).count();
+            assert_eq!(ring.counts(), (good, counted.len()), "after push {}", i);
+        }
+        assert!(ring.len() <= 20);
+    }
+}
@@ -1,134 +1,263 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tokio::time::interval;
+use tokio::time::{interval_at, Instant, Interval};
 use tracing::{info, warn, debug, error};
-use crate::types::{AppState, Sample, SampleBit};
-use crate::storage::save_samples;
+use crate::config::{GraceBoundary, MetricWeights, MissedTicks, StorageBackend};
+use crate::types::{AppState, DasMetrics, Sample, SampleBit, SampleReason};
+use crate::storage::{init_db, insert_sample, save_samples, save_samples_to, spawn_write, SAMPLES_DB_PATH};
+use crate::telemetry::Liveness;
+use crate::da::spawn_sample_poster;
+use crate::utils::{is_stale, window_size};
+use super::secondary::{stale_verdict, NodeHeadProbe};
 
 /// Background task: samples metrics at fixed intervals
 pub async fn run_sampler(state: AppState) {
-    let tick_duration = Duration::from_secs(state.config.sampling.tick_secs);
-    let mut ticker = interval(tick_duration);
-    let window_size = (state.config.batching.window_secs / state.config.sampling.tick_secs) as usize;
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let mut ticker = sampler_interval(
+        state.config.sampling.tick_secs,
+        state.config.sampling.align_to_wallclock,
+        state.config.sampling.missed_ticks,
+        since_epoch,
+    );
+    let window_size = window_size(state.config.batching.window_secs, state.config.sampling.tick_secs)
+        .expect("tick_secs validated at startup");
     
     // Previous values to track advancement
     let mut prev_head: Option<i64> = None;
     let mut prev_headers: Option<i64> = None;
+    // When DAS data was first seen (starts the warmup period)
+    let mut first_data_at: Option<u64> = None;
+    // Last head reported by the node RPC during stale telemetry
+    let mut last_node_head: Option<i64> = None;
+    // Timestamp of the last recorded sample, including ones loaded from disk
+    let mut last_timestamp = state.samples.lock().unwrap().last().map(|s| s.timestamp);
+    let mut flush = FlushSchedule::new(state.config.storage.flush_every_n_samples);
+    // With the sqlite backend each sample is inserted on its own instead of rewriting samples.json
+    let db = match state.config.storage.backend {
+        StorageBackend::Json => None,
+        StorageBackend::Sqlite => match init_db() {
+            Ok(conn) => {
+                info!("🗄️  Storing samples in {}", SAMPLES_DB_PATH);
+                Some(Arc::new(Mutex::new(conn)))
+            }
+            Err(e) => {
+                error!("Failed to open {} ({}), falling back to data/samples.json", SAMPLES_DB_PATH, e);
+                None
+            }
+        },
+    };
+    let mut cooldown = RecoveryCooldown::new(state.config.sampling.recovery_cooldown_secs);
+    let sample_poster = state.config.da_posting.enabled.then(|| spawn_sample_poster(state.clone()));
+    let probe = match NodeHeadProbe::from_config(
+        &state.config.secondary_check,
+        state.config.celestia.auth_token.as_deref(),
+    ) {
+        Ok(probe) => probe,
+        Err(e) => {
+            error!("Secondary check disabled: {}", e);
+            NodeHeadProbe::Disabled
+        }
+    };
     
     info!("🔄 Sampler started (tick every {}s, window size: {})", 
           state.config.sampling.tick_secs, window_size);
     
     loop {
         ticker.tick().await;
+        Liveness::mark(&state.liveness.last_tick);
         
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
         
+        if !accept_timestamp(last_timestamp, now, state.config.sampling.enforce_monotonic) {
+            error!("🕰️  Clock went backward: sample at {} is not after the previous one at {} - not recording it (enforce_monotonic)",
+                   now, last_timestamp.unwrap_or_default());
+            continue;
+        }
+        last_timestamp = Some(now);
+        
         // Read current metrics
-        let (current_head, current_headers, last_update) = {
-            let das_metrics = state.das_metrics.lock().unwrap();
-            (das_metrics.head, das_metrics.headers, das_metrics.last_update)
-        };
+        let das_metrics = state.das_metrics.lock().unwrap().clone();
+        let (current_head, current_headers, last_update) =
+            (das_metrics.head, das_metrics.headers, das_metrics.last_update);
         
         // Check staleness
-        let is_stale = match last_update {
-            Some(update_time) => {
-                let age = now.saturating_sub(update_time);
-                age > state.config.sampling.max_staleness_secs
-            }
-            None => true,
-        };
+        let stale = is_stale(last_update, now, state.config.sampling.max_staleness_secs);
         
         // Check head advancement and reason
-        let (head_advanced, head_reason) = match (prev_head, current_head) {
+        let (head_advanced, head_reason, head_code) = match (prev_head, current_head) {
             (Some(prev), Some(curr)) => {
                 let diff = curr - prev;
                 // Head advanced: good!
                 if diff >= state.config.metrics.min_increment {
-                    (true, format!("+{} blocks", diff))
+                    (true, format!("+{} blocks", diff), SampleReason::Advancing)
                 } else {
                     // Head didn't advance, but check if data is fresh
                     // If metrics were just updated, give it a pass
                     // (Data is fresh, just sampled at wrong moment)
-                    let data_age = last_update.map(|u| now.saturating_sub(u)).unwrap_or(999);
-                    if data_age <= state.config.sampling.grace_period_secs {
-                        // Fresh data, can't judge advancement yet
-                        (true, format!("fresh data (age={}s)", data_age))
-                    } else {
-                        (false, format!("head stuck at {}", curr))
+                    // Without a last_update there is nothing fresh to excuse the stuck head
+                    match last_update.map(|u| now.saturating_sub(u)) {
+                        Some(data_age) if within_grace(
+                            data_age,
+                            state.config.sampling.grace_period_secs,
+                            state.config.sampling.grace_boundary,
+                        ) => {
+                            // Fresh data, can't judge advancement yet
+                            (true, format!("fresh data (age={}s)", data_age), SampleReason::FreshData)
+                        }
+                        _ => (false, format!("head stuck at {}", curr), SampleReason::StuckHead),
                     }
                 }
             }
             (None, Some(_)) => {
                 // First reading, consider it ok
-                (true, "first sample".to_string())
+                (true, "first sample".to_string(), SampleReason::FirstSample)
             }
-            _ => (false, "no head data".to_string()),
+            _ => (false, "no head data".to_string(), SampleReason::NoHead),
         };
         
         // Optional: Check if headers advanced
-        let headers_advanced = match (prev_headers, current_headers) {
-            (Some(prev), Some(curr)) => curr > prev,
-            (None, Some(_)) => true,
-            _ => false,
-        };
+        let headers_advanced = headers_advanced(
+            prev_headers,
+            current_headers,
+            state.config.metrics.min_headers_increment,
+        );
+        
+        // No DAS metrics received at all (e.g. collector not sending yet)
+        let no_data = current_head.is_none() && current_headers.is_none();
+        
+        if first_data_at.is_none() && !no_data {
+            first_data_at = Some(now);
+        }
         
         // Determine if this tick is "ok"
-        let (ok, reason) = if is_stale {
-            (false, format!("stale (age > {}s)", state.config.sampling.max_staleness_secs))
-        } else if !head_advanced {
-            (false, head_reason)
-        } else if !headers_advanced {
-            (false, format!("headers not advancing"))
+        let (ok, reason, reason_code) = if no_data {
+            (false, "no data received".to_string(), SampleReason::NoData)
+        } else if stale {
+            // Telemetry may be down while the node is fine: ask the node directly
+            let verdict = stale_verdict(
+                &probe,
+                prev_head.max(last_node_head),
+                state.config.metrics.min_increment,
+                state.config.sampling.max_staleness_secs,
+            )
+            .await;
+            if verdict.node_head.is_some() {
+                last_node_head = verdict.node_head;
+            }
+            (verdict.ok, verdict.reason, verdict.reason_code)
         } else {
-            (true, head_reason)
+            let verdict = combine_head_and_headers(
+                (head_advanced, head_reason, head_code),
+                headers_advanced,
+                state.config.sampling.head_stuck_headers_advancing_ok,
+            );
+            match (state.config.metrics.max_head_lag, current_head) {
+                (Some(max_lag), Some(head)) if verdict.0 => match state.da_poster.chain_head().await {
+                    Ok(network) => check_lag(
+                        verdict,
+                        (network.height as i64).saturating_sub(head),
+                        max_lag,
+                        in_warmup(first_data_at, now, state.config.metrics.lag_warmup_secs),
+                    ),
+                    Err(e) => {
+                        debug!("Network head unavailable, lag not evaluated: {}", e);
+                        verdict
+                    }
+                },
+                _ => verdict,
+            }
         };
         
+        // Shortly after a fail→ok recovery, a single bad tick is kept ok (recovery_cooldown_secs);
+        // it then counts like any ok tick, including under no_data_policy = "neutral"
+        let tolerated = cooldown.tolerate(ok, now);
+        let (ok, reason, reason_code, no_data) = if tolerated {
+            debug!("🩹 Tolerating a bad tick during recovery cooldown: {}", reason);
+            (true, format!("{} (tolerated during recovery cooldown)", reason), SampleReason::RecoveryCooldown, false)
+        } else {
+            (ok, reason, reason_code, no_data)
+        };
+        
+        let warmup = in_warmup(first_data_at, now, state.config.sampling.warmup_secs);
+        
+        // Weighted score: only meaningful when fresh per-metric signals exist
+        let score = state.config.metrics.weights.map(|weights| {
+            if tolerated || no_data || stale {
+                if ok { 1.0 } else { 0.0 }
+            } else {
+                evaluate_sample(head_advanced, headers_advanced, &weights)
+            }
+        });
+        
         // Create sample
         let sample = Sample {
-            timestamp: now,
-            head: current_head,
-            headers: current_headers,
-            ok,
-            reason: reason.clone(),
+            score,
+            ..build_sample(now, &das_metrics, ok, reason.clone(), reason_code, warmup)
         };
         
         let sample_bit = SampleBit {
             timestamp: now,
             ok,
             reason: reason.clone(),
+            no_data,
+            warmup,
+            reason_code,
+            score,
         };
         
         // Store sample
-        {
+        let to_flush = {
             let mut samples = state.samples.lock().unwrap();
             samples.push(sample.clone());
+            state.telemetry.record_sample(&sample);
             
-            // Save to file periodically
-            if let Err(e) = save_samples(&samples) {
-                error!("Failed to save samples: {}", e);
+            if db.is_some() {
+                // samples.db keeps the full history; memory only the recent part
+                if let Some(secs) = state.config.storage.memory_retention_secs() {
+                    drop_samples_before(&mut samples, now.saturating_sub(secs));
+                }
+                None
             } else {
-                debug!("💾 Saved {} samples to data/samples.json", samples.len());
+                // Save to file every `flush_every_n_samples` ticks
+                flush.record().then(|| samples.clone())
+            }
+        };
+        // Written outside the lock, so a slow or retried write never holds up ingestion
+        if let Some(db) = &db {
+            let inserted = spawn_write({
+                let (db, sample) = (db.clone(), sample.clone());
+                move || insert_sample(&db.lock().unwrap(), &sample)
+            })
+            .await;
+            state.record_storage(&inserted);
+            if let Err(e) = inserted {
+                error!("Failed to insert sample into {}: {}", SAMPLES_DB_PATH, e);
             }
         }
-        
-        // Add to ring buffer
-        {
-            let mut ring_buffer = state.ring_buffer.lock().unwrap();
-            ring_buffer.push_back(sample_bit.clone());
-            
-            // Maintain window size
-            while ring_buffer.len() > window_size {
-                ring_buffer.pop_front();
+        if let Some(samples) = to_flush {
+            let count = samples.len();
+            let storage = state.config.storage.clone();
+            let saved = spawn_write(move || save_samples(&samples, &storage)).await;
+            state.record_storage(&saved);
+            if let Err(e) = saved {
+                error!("Failed to save samples: {}", e);
+            } else {
+                debug!("💾 Saved {} samples to data/samples.json", count);
             }
         }
         
-        // Post sample to DA if enabled (detailed history)
-        if state.config.da_posting.enabled && state.config.da_posting.post_every_sample {
-            // TODO: Implement actual DA posting
-            // post_sample_to_da(&sample_bit, &state).await;
-            info!("📡 Posted sample to Celestia DA: ok={}, timestamp={}", sample_bit.ok, sample_bit.timestamp);
+        // Add to ring buffer (evicting beyond the window size keeps its ok/total counts current)
+        state.ring_buffer.lock().unwrap().push(sample_bit.clone(), window_size);
+        
+        // Hand the sample to the per-sample DA layers (detailed history) without waiting on DA
+        if let Some(poster) = &sample_poster {
+            if poster.try_send(sample_bit.clone()).is_err() {
+                warn!("📡 DA sample posting is behind, sample at {} not posted", now);
+            }
         }
         
         // Show all samples at info level for better DevX
@@ -137,7 +266,15 @@ pub async fn run_sampler(state: AppState) {
             buffer.len()
         };
         
-        if ok {
+        if warmup {
+            info!(
+                "⏳ Sample during warmup (excluded from batches) - ok={} ({}) | Head: {:?}, Headers: {:?}",
+                ok,
+                reason,
+                current_head,
+                current_headers
+            );
+        } else if ok {
             info!(
                 "✅ Sample OK - Head: {:?} ({}), Headers: {:?} | Buffer: {}/{} samples",
                 current_head,
@@ -161,3 +298,572 @@ pub async fn run_sampler(state: AppState) {
     }
 }
 
+/// Ticker for the sampler; the first tick fires immediately unless aligned to the wall clock
+fn sampler_interval(tick_secs: u64, align_to_wallclock: bool, missed_ticks: MissedTicks, since_epoch: Duration) -> Interval {
+    let delay = if align_to_wallclock {
+        delay_to_boundary(since_epoch, tick_secs)
+    } else {
+        Duration::ZERO
+    };
+    if !delay.is_zero() {
+        info!("⏱️  Aligning sampler to wall clock: first tick in {:.3}s", delay.as_secs_f64());
+    }
+    let mut ticker = interval_at(Instant::now() + delay, Duration::from_secs(tick_secs));
+    ticker.set_missed_tick_behavior(missed_ticks.behavior());
+    ticker
+}
+
+/// Time from `since_epoch` until the next multiple of `tick_secs` since the Unix epoch
+fn delay_to_boundary(since_epoch: Duration, tick_secs: u64) -> Duration {
+    let tick = Duration::from_secs(tick_secs.max(1));
+    let into_tick = Duration::from_nanos((since_epoch.as_nanos() % tick.as_nanos()) as u64);
+    if into_tick.is_zero() {
+        Duration::ZERO
+    } else {
+        tick - into_tick
+    }
+}
+
+/// Drop the samples taken before `cutoff` (`samples` is in timestamp order)
+fn drop_samples_before(samples: &mut Vec<Sample>, cutoff: u64) {
+    let stale = samples.partition_point(|sample| sample.timestamp < cutoff);
+    samples.drain(..stale);
+}
+
+/// Write all in-memory samples to `path`, e.g. on shutdown so samples not yet
+/// flushed by the sampler aren't lost
+pub fn flush_samples(state: &AppState, path: &Path) -> anyhow::Result<usize> {
+    let samples = state.samples.lock().unwrap();
+    save_samples_to(path, &samples, &state.config.storage)?;
+    Ok(samples.len())
+}
+
+/// Seed state with samples persisted before a restart, so the batch window in progress
+/// survives it; returns how many of them went back into the ring buffer
+///
+/// Only samples from the last `batching.window_secs` before `now` (and at most a window's
+/// worth) re-enter the ring buffer: older ones belong to windows already batched, or
+/// missed while the reader was down. Samples before `state.compacted_before` are left out
+/// of the raw history, as the restored summaries already count them.
+pub fn restore_samples(state: &AppState, mut samples: Vec<Sample>, now: u64) -> usize {
+    let window_size = window_size(state.config.batching.window_secs, state.config.sampling.tick_secs)
+        .expect("tick_secs validated at startup");
+    let since = now.saturating_sub(state.config.batching.window_secs);
+    let recent: Vec<SampleBit> = samples
+        .iter()
+        .filter(|sample| sample.timestamp > since)
+        .rev()
+        .take(window_size)
+        .map(SampleBit::from)
+        .collect();
+    let mut ring_buffer = state.ring_buffer.lock().unwrap();
+    for bit in recent.iter().rev() {
+        ring_buffer.push(bit.clone(), window_size);
+    }
+    let compacted_before = *state.compacted_before.lock().unwrap();
+    samples.retain(|sample| sample.timestamp >= compacted_before);
+    *state.samples.lock().unwrap() = samples;
+    recent.len()
+}
+
+/// Decides on which sampler ticks samples.json is rewritten
+struct FlushSchedule {
+    every: usize,
+    pending: usize,
+}
+
+impl FlushSchedule {
+    fn new(every: usize) -> Self {
+        Self { every: every.max(1), pending: 0 }
+    }
+
+    /// Record a new sample; returns true when it's time to flush
+    fn record(&mut self) -> bool {
+        self.pending += 1;
+        if self.pending >= self.every {
+            self.pending = 0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Tracks the last fail→ok recovery so one bad tick within `cooldown_secs` of it is tolerated
+struct RecoveryCooldown {
+    cooldown_secs: u64,
+    last_ok: Option<bool>,
+    /// When the series last went from failing to ok
+    recovered_at: Option<u64>,
+    /// Whether this recovery's one tolerated tick is spent
+    spent: bool,
+}
+
+impl RecoveryCooldown {
+    fn new(cooldown_secs: u64) -> Self {
+        Self { cooldown_secs, last_ok: None, recovered_at: None, spent: false }
+    }
+
+    /// Record this tick's verdict; returns true if a failing tick is tolerated (kept ok)
+    fn tolerate(&mut self, ok: bool, now: u64) -> bool {
+        let in_cooldown = self
+            .recovered_at
+            .is_some_and(|at| now.saturating_sub(at) <= self.cooldown_secs);
+        let tolerated = !ok && in_cooldown && !self.spent && self.cooldown_secs > 0;
+        let effective = ok || tolerated;
+
+        if tolerated {
+            self.spent = true;
+        } else if !effective {
+            self.recovered_at = None;
+        } else if self.last_ok == Some(false) {
+            self.recovered_at = Some(now);
+            self.spent = false;
+        }
+        self.last_ok = Some(effective);
+        tolerated
+    }
+}
+
+/// Build the persisted sample from the metrics snapshot taken this tick
+fn build_sample(
+    now: u64,
+    das_metrics: &DasMetrics,
+    ok: bool,
+    reason: String,
+    reason_code: SampleReason,
+    warmup: bool,
+) -> Sample {
+    Sample {
+        timestamp: now,
+        head: das_metrics.head,
+        headers: das_metrics.headers,
+        ok,
+        reason,
+        reason_code,
+        warmup,
+        attributes: das_metrics.source_attributes.clone(),
+        labels: das_metrics.labels.clone(),
+        score: None,
+    }
+}
+
+/// Score a sample as the weighted share of healthy metrics, in [0, 1]
+///
+/// Weights are normalized by their sum, so `{ head = 2, headers = 1 }` behaves like
+/// `{ head = 0.67, headers = 0.33 }`.
+fn evaluate_sample(head_healthy: bool, headers_healthy: bool, weights: &MetricWeights) -> f64 {
+    let total = weights.head + weights.headers;
+    if total <= 0.0 {
+        return 0.0;
+    }
+    let healthy = |signal: bool, weight: f64| if signal { weight } else { 0.0 };
+    (healthy(head_healthy, weights.head) + healthy(headers_healthy, weights.headers)) / total
+}
+
+/// Verdict for a tick with fresh data from the head verdict and headers advancement
+///
+/// A stuck head fails before headers are considered, except that a stuck head with
+/// advancing headers gets its own reason and counts as `stuck_with_headers_ok`.
+fn combine_head_and_headers(
+    (head_advanced, head_reason, head_code): (bool, String, SampleReason),
+    headers_advanced: bool,
+    stuck_with_headers_ok: bool,
+) -> (bool, String, SampleReason) {
+    if head_advanced && headers_advanced {
+        (true, head_reason, head_code)
+    } else if head_advanced {
+        (false, "headers not advancing".to_string(), SampleReason::HeadersStalled)
+    } else if headers_advanced && head_code == SampleReason::StuckHead {
+        (
+            stuck_with_headers_ok,
+            "head stuck but headers advancing".to_string(),
+            SampleReason::HeadStuckHeadersAdvancing,
+        )
+    } else {
+        (false, head_reason, head_code)
+    }
+}
+
+/// Fail an otherwise ok tick whose head is more than `max_lag` blocks behind the network head
+///
+/// During the lag warmup (`metrics.lag_warmup_secs`) the tick stays ok and the lag is only noted.
+fn check_lag(
+    verdict: (bool, String, SampleReason),
+    lag: i64,
+    max_lag: u64,
+    lag_warmup: bool,
+) -> (bool, String, SampleReason) {
+    if lag <= max_lag as i64 {
+        return verdict;
+    }
+    if lag_warmup {
+        let (ok, reason, code) = verdict;
+        (ok, format!("{} (head {} blocks behind, catching up)", reason, lag), code)
+    } else {
+        (false, format!("head {} blocks behind network head", lag), SampleReason::HeadLagging)
+    }
+}
+
+/// Whether data `data_age` seconds old is still inside the grace period
+fn within_grace(data_age: u64, grace_period_secs: u64, boundary: GraceBoundary) -> bool {
+    match boundary {
+        GraceBoundary::Inclusive => data_age <= grace_period_secs,
+        GraceBoundary::Exclusive => data_age < grace_period_secs,
+    }
+}
+
+/// Check if sampled headers advanced by at least `min_increment` since the previous tick
+fn headers_advanced(prev: Option<i64>, curr: Option<i64>, min_increment: i64) -> bool {
+    match (prev, curr) {
+        (Some(prev), Some(curr)) => curr - prev >= min_increment,
+        (None, Some(_)) => true,
+        _ => false,
+    }
+}
+
+/// Whether a sample taken at `now` may be recorded after one taken at `last`
+///
+/// With `enforce_monotonic`, timestamps must strictly increase; otherwise anything goes.
+fn accept_timestamp(last: Option<u64>, now: u64, enforce_monotonic: bool) -> bool {
+    !enforce_monotonic || last.is_none_or(|last| now > last)
+}
+
+/// Whether `now` falls inside the warmup period that starts when DAS data first arrives
+fn in_warmup(first_data_at: Option<u64>, now: u64, warmup_secs: u64) -> bool {
+    match first_data_at {
+        Some(start) => now < start.saturating_add(warmup_secs),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::config::Config;
+    use crate::otlp::ingest_payload;
+
+    #[test]
+    fn test_sqlite_backend_bounds_in_memory_history() {
+        let mut storage = Config::for_tests().storage;
+        assert_eq!(storage.memory_retention_secs(), None);
+        storage.backend = StorageBackend::Sqlite;
+        storage.sqlite_memory_secs = 600;
+        assert_eq!(storage.memory_retention_secs(), Some(600));
+        // Compaction gets to summarize samples before they leave memory
+        storage.downsample_after_secs = Some(3600);
+        storage.downsample_bucket_secs = 60;
+        assert_eq!(storage.memory_retention_secs(), Some(3660));
+
+        let mut samples: Vec<Sample> = (0..10).map(|i| Sample { timestamp: 1000 + i * 30, ..Default::default() }).collect();
+        drop_samples_before(&mut samples, 1090);
+        assert_eq!(samples.first().map(|s| s.timestamp), Some(1090));
+        assert_eq!(samples.len(), 7);
+        drop_samples_before(&mut samples, 0);
+        assert_eq!(samples.len(), 7);
+    }
+
+    #[test]
+    fn test_configured_resource_attribute_captured_in_sample() {
+        let mut config = Config::for_tests();
+        config.metrics.sample_resource_attributes = vec!["service.instance.id".to_string()];
+        let state = AppState::new(Arc::new(config));
+
+        let body = r#"{"resourceMetrics":[{"resource":{"attributes":[
+            {"key":"service.instance.id","value":{"stringValue":"node-7"}},
+            {"key":"host.name","value":{"stringValue":"box"}}]},
+            "scopeMetrics":[{"metrics":[{"name":"das_sampled_chain_head",
+            "gauge":{"dataPoints":[{"asInt":"42","timeUnixNano":"1"}]}}]}]}]}"#;
+        assert!(ingest_payload(&state, body.as_bytes(), true).das_updated);
+
+        let das_metrics = state.das_metrics.lock().unwrap().clone();
+        let sample = build_sample(100, &das_metrics, true, "first sample".to_string(), SampleReason::FirstSample, false);
+        assert_eq!(sample.head, Some(42));
+        assert_eq!(sample.attributes.len(), 1);
+        assert_eq!(sample.attributes["service.instance.id"], "node-7");
+    }
+
+    #[test]
+    fn test_configured_attribute_propagated_to_sample_label() {
+        let mut config = Config::for_tests();
+        config.metrics.sample_labels.insert("network".to_string(), "network".to_string());
+        config.metrics.sample_labels.insert("node.type".to_string(), "node_type".to_string());
+        let state = AppState::new(Arc::new(config));
+
+        let body = r#"{"resourceMetrics":[{"resource":{"attributes":[
+            {"key":"node.type","value":{"stringValue":"light"}},
+            {"key":"network","value":{"stringValue":"ignored"}}]},
+            "scopeMetrics":[{"metrics":[{"name":"das_sampled_chain_head",
+            "gauge":{"dataPoints":[{"asInt":"42","timeUnixNano":"1",
+            "attributes":[{"key":"network","value":{"stringValue":"mocha-4"}}]}]}}]}]}]}"#;
+        assert!(ingest_payload(&state, body.as_bytes(), true).das_updated);
+
+        let das_metrics = state.das_metrics.lock().unwrap().clone();
+        let sample = build_sample(100, &das_metrics, true, "first sample".to_string(), SampleReason::FirstSample, false);
+        assert_eq!(sample.labels.len(), 2);
+        // The data point attribute wins over the resource attribute
+        assert_eq!(sample.labels["network"], "mocha-4");
+        assert_eq!(sample.labels["node_type"], "light");
+        assert!(sample.attributes.is_empty());
+    }
+
+    #[test]
+    fn test_head_stuck_but_headers_advancing() {
+        let stuck = || (false, "head stuck at 100".to_string(), SampleReason::StuckHead);
+
+        let (ok, reason, code) = combine_head_and_headers(stuck(), true, false);
+        assert!(!ok);
+        assert_eq!(reason, "head stuck but headers advancing");
+        assert_eq!(code, SampleReason::HeadStuckHeadersAdvancing);
+
+        // Configured to count as ok
+        assert!(combine_head_and_headers(stuck(), true, true).0);
+
+        // Both stuck: plain head-stuck failure, whatever the setting
+        let (ok, reason, code) = combine_head_and_headers(stuck(), false, true);
+        assert!(!ok);
+        assert_eq!((reason.as_str(), code), ("head stuck at 100", SampleReason::StuckHead));
+    }
+
+    #[test]
+    fn test_head_and_headers_verdicts_otherwise_unchanged() {
+        let advancing = || (true, "+2 blocks".to_string(), SampleReason::Advancing);
+        assert_eq!(combine_head_and_headers(advancing(), true, false), advancing());
+        assert_eq!(
+            combine_head_and_headers(advancing(), false, false),
+            (false, "headers not advancing".to_string(), SampleReason::HeadersStalled)
+        );
+        let no_head = (false, "no head data".to_string(), SampleReason::NoHead);
+        assert_eq!(combine_head_and_headers(no_head.clone(), true, true), no_head);
+    }
+
+    #[test]
+    fn test_grace_boundary_inclusive() {
+        assert!(within_grace(45, 45, GraceBoundary::Inclusive));
+        assert!(!within_grace(46, 45, GraceBoundary::Inclusive));
+    }
+
+    #[test]
+    fn test_grace_boundary_exclusive() {
+        assert!(within_grace(44, 45, GraceBoundary::Exclusive));
+        assert!(!within_grace(45, 45, GraceBoundary::Exclusive));
+        assert!(!within_grace(46, 45, GraceBoundary::Exclusive));
+    }
+
+    #[test]
+    fn test_headers_advanced_min_increment() {
+        // At the threshold
+        assert!(headers_advanced(Some(100), Some(105), 5));
+        // Below the threshold
+        assert!(!headers_advanced(Some(100), Some(104), 5));
+        assert!(!headers_advanced(Some(100), Some(100), 5));
+        // Above the threshold
+        assert!(headers_advanced(Some(100), Some(120), 5));
+    }
+
+    #[test]
+    fn test_headers_advanced_default_matches_strict_increase() {
+        assert!(headers_advanced(Some(100), Some(101), 1));
+        assert!(!headers_advanced(Some(100), Some(100), 1));
+        assert!(!headers_advanced(Some(100), Some(99), 1));
+        assert!(headers_advanced(None, Some(1), 1));
+        assert!(!headers_advanced(Some(1), None, 1));
+    }
+
+    fn weights(head: f64, headers: f64) -> MetricWeights {
+        MetricWeights { head, headers }
+    }
+
+    #[test]
+    fn test_evaluate_sample_weights_head_over_headers() {
+        let w = weights(0.7, 0.3);
+        assert_eq!(evaluate_sample(true, true, &w), 1.0);
+        assert!((evaluate_sample(true, false, &w) - 0.7).abs() < 1e-9);
+        assert!((evaluate_sample(false, true, &w) - 0.3).abs() < 1e-9);
+        assert_eq!(evaluate_sample(false, false, &w), 0.0);
+    }
+
+    #[test]
+    fn test_evaluate_sample_normalizes_weights_not_summing_to_one() {
+        // Sum of 3: same ratios as 2/3 and 1/3
+        let w = weights(2.0, 1.0);
+        assert!((evaluate_sample(true, false, &w) - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(evaluate_sample(true, true, &w), 1.0);
+
+        // A zero weight ignores that metric entirely
+        let head_only = weights(5.0, 0.0);
+        assert_eq!(evaluate_sample(true, false, &head_only), 1.0);
+        assert_eq!(evaluate_sample(false, true, &head_only), 0.0);
+
+        // Degenerate weights (rejected by config validation) never divide by zero
+        assert_eq!(evaluate_sample(true, true, &weights(0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn test_flush_schedule_every_n_samples() {
+        let mut flush = FlushSchedule::new(3);
+        let flushed: Vec<usize> = (1..=7).filter(|_| flush.record()).collect();
+        assert_eq!(flushed, vec![3, 6]);
+
+        // Default of 1 flushes every tick
+        let mut every_tick = FlushSchedule::new(1);
+        assert!((0..3).all(|_| every_tick.record()));
+    }
+
+    #[test]
+    fn test_flush_samples_on_shutdown_writes_unflushed_samples() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let mut flush = FlushSchedule::new(5);
+        for t in 0..3u64 {
+            state.samples.lock().unwrap().push(Sample { timestamp: t * 30, ok: true, ..Default::default() });
+            assert!(!flush.record());
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.json");
+        assert_eq!(flush_samples(&state, &path).unwrap(), 3);
+        let saved = crate::storage::load_samples_from(&path).unwrap();
+        assert_eq!(saved.iter().map(|s| s.timestamp).collect::<Vec<_>>(), vec![0, 30, 60]);
+    }
+
+    #[test]
+    fn test_restore_samples_rebuilds_current_window() {
+        // 600s windows of 30s ticks: 20 samples per window
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let now = 100_000;
+        let samples: Vec<Sample> = (0..50u64)
+            .map(|i| Sample { timestamp: now - 1470 + i * 30, ok: i % 5 != 0, head: Some(i as i64), ..Default::default() })
+            .collect();
+
+        // Only the samples of the last 600s go back into the window; all stay in history
+        assert_eq!(restore_samples(&state, samples, now), 20);
+        assert_eq!(state.samples.lock().unwrap().len(), 50);
+        let ring = state.ring_buffer.lock().unwrap();
+        let timestamps: Vec<u64> = ring.iter().map(|bit| bit.timestamp).collect();
+        assert_eq!(timestamps, (30..50u64).map(|i| now - 1470 + i * 30).collect::<Vec<_>>());
+        assert_eq!(ring.counts(), (16, 20));
+    }
+
+    #[test]
+    fn test_delay_to_boundary() {
+        let at = |secs: u64, millis: u64| Duration::from_secs(secs) + Duration::from_millis(millis);
+        assert_eq!(delay_to_boundary(at(1729785617, 250), 30), at(12, 750));
+        assert_eq!(delay_to_boundary(at(1729785600, 0), 30), Duration::ZERO);
+        assert_eq!(delay_to_boundary(at(1729785659, 999), 60), Duration::from_millis(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_first_aligned_tick_lands_on_boundary() {
+        // 17.25s past the minute with 30s ticks: first tick at :30, then :00
+        let since_epoch = Duration::from_secs(1729785617) + Duration::from_millis(250);
+        let start = Instant::now();
+        let mut ticker = sampler_interval(30, true, MissedTicks::Skip, since_epoch);
+
+        let first = ticker.tick().await;
+        assert_eq!(first - start, Duration::from_millis(12_750));
+        assert_eq!((since_epoch + (first - start)).as_secs() % 30, 0);
+        let second = ticker.tick().await;
+        assert_eq!(second - first, Duration::from_secs(30));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_unaligned_first_tick_is_immediate() {
+        let start = Instant::now();
+        let mut ticker = sampler_interval(30, false, MissedTicks::Skip, Duration::from_millis(1729785617250));
+        assert_eq!(ticker.tick().await, start);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_delayed_ticker_does_not_burst() {
+        for (missed_ticks, resume_after) in [(MissedTicks::Skip, 15), (MissedTicks::Delay, 30)] {
+            let start = Instant::now();
+            let mut ticker = sampler_interval(30, false, missed_ticks, Duration::ZERO);
+            ticker.tick().await;
+
+            // The sampler is held up for 3.5 periods (ticks due at 30, 60 and 90 are missed)
+            tokio::time::advance(Duration::from_secs(105)).await;
+            ticker.tick().await;
+            assert_eq!(Instant::now() - start, Duration::from_secs(105));
+
+            // One late tick, then the next waits: Skip resumes at 120, Delay at 105 + 30
+            ticker.tick().await;
+            assert_eq!(Instant::now() - start, Duration::from_secs(105 + resume_after), "{:?}", missed_ticks);
+        }
+    }
+
+    #[test]
+    fn test_lone_bad_tick_after_recovery_tolerated() {
+        // Fail, recover at 60, one bad tick at 90 (tolerated), fail again at 150 and 180
+        let ticks = [(0, false), (30, false), (60, true), (90, false), (120, true), (150, false), (180, false)];
+        let mut cooldown = RecoveryCooldown::new(120);
+        let effective: Vec<bool> = ticks.iter().map(|&(now, ok)| cooldown.tolerate(ok, now) || ok).collect();
+        // Only one tick per recovery is forgiven, so the sustained failure at 150/180 still shows
+        assert_eq!(effective, [false, false, true, true, true, false, false]);
+
+        // A bad tick outside the cooldown isn't tolerated
+        let mut cooldown = RecoveryCooldown::new(30);
+        for (now, ok) in [(0, false), (30, true), (120, false)] {
+            assert!(!cooldown.tolerate(ok, now));
+        }
+
+        // Disabled by default
+        let mut cooldown = RecoveryCooldown::new(0);
+        for (now, ok) in [(0, false), (30, true), (60, false)] {
+            assert!(!cooldown.tolerate(ok, now));
+        }
+    }
+
+    #[test]
+    fn test_backward_clock_jump() {
+        // Clock steps back 60s after the second sample, then catches up
+        let clock = [1000, 1030, 970, 1000, 1060];
+        let recorded = |enforce| {
+            let mut last = None;
+            let mut kept = Vec::new();
+            for now in clock {
+                if accept_timestamp(last, now, enforce) {
+                    last = Some(now);
+                    kept.push(now);
+                }
+            }
+            kept
+        };
+        assert_eq!(recorded(false), clock);
+        assert_eq!(recorded(true), [1000, 1030, 1060]);
+        assert!(!accept_timestamp(Some(1000), 1000, true));
+    }
+
+    #[test]
+    fn test_lag_tolerated_only_during_lag_warmup() {
+        let first_data_at = Some(1000);
+        let lag_warmup_secs = 120;
+        let advancing = || (true, "+3 blocks".to_string(), SampleReason::Advancing);
+        let at = |now: u64, lag: i64| check_lag(advancing(), lag, 20, in_warmup(first_data_at, now, lag_warmup_secs));
+
+        // Catching up right after a restart: lagging but still ok
+        let (ok, reason, code) = at(1030, 500);
+        assert!(ok);
+        assert_eq!(code, SampleReason::Advancing);
+        assert!(reason.contains("500 blocks behind"));
+
+        // The same lag once the lag warmup is over fails
+        let (ok, reason, code) = at(1120, 500);
+        assert!(!ok);
+        assert_eq!(code, SampleReason::HeadLagging);
+        assert_eq!(reason, "head 500 blocks behind network head");
+
+        // Lag within max_head_lag, or a head ahead of the network head, is fine
+        assert_eq!(at(1120, 20), advancing());
+        assert_eq!(at(1120, -5), advancing());
+    }
+
+    #[test]
+    fn test_in_warmup() {
+        assert!(!in_warmup(None, 100, 60));
+        assert!(in_warmup(Some(100), 100, 60));
+        assert!(in_warmup(Some(100), 159, 60));
+        assert!(!in_warmup(Some(100), 160, 60));
+        assert!(!in_warmup(Some(100), 100, 0));
+    }
+}
@@ -0,0 +1,207 @@
+use std::time::Duration;
+use axum::body::Bytes;
+use axum::http::{header, Method, Request, Uri};
+use http_body_util::{BodyExt, Full};
+use hyper_util::client::legacy::{connect::HttpConnector, Client};
+use hyper_util::rt::TokioExecutor;
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+use crate::config::SecondaryCheckConfig;
+use crate::types::SampleReason;
+
+/// Source of the node's chain head independent of the OTLP pipeline
+pub enum NodeHeadProbe {
+    Disabled,
+    /// celestia-node JSON-RPC `header.LocalHead`
+    Rpc {
+        client: Box<Client<HttpConnector, Full<Bytes>>>,
+        url: Uri,
+        timeout: Duration,
+        /// `celestia.auth_token`, sent as a bearer token
+        auth_token: Option<String>,
+    },
+    #[cfg(test)]
+    Fixed(Option<i64>),
+}
+
+impl NodeHeadProbe {
+    pub fn from_config(cfg: &SecondaryCheckConfig, auth_token: Option<&str>) -> anyhow::Result<Self> {
+        if !cfg.enabled {
+            return Ok(NodeHeadProbe::Disabled);
+        }
+        Ok(NodeHeadProbe::Rpc {
+            client: Box::new(Client::builder(TokioExecutor::new()).build_http()),
+            url: cfg.node_rpc_url.parse()?,
+            timeout: Duration::from_secs(cfg.timeout_secs),
+            auth_token: auth_token.map(str::to_string),
+        })
+    }
+
+    /// Ask the node for its head; `None` if disabled or the node can't be reached
+    pub async fn head(&self) -> Option<i64> {
+        match self {
+            NodeHeadProbe::Disabled => None,
+            NodeHeadProbe::Rpc { client, url, timeout, auth_token } => {
+                match tokio::time::timeout(*timeout, rpc_local_head(client, url, auth_token.as_deref())).await {
+                    Ok(Ok(head)) => Some(head),
+                    Ok(Err(e)) => {
+                        warn!("Secondary check: node RPC head query failed: {}", e);
+                        None
+                    }
+                    Err(_) => {
+                        warn!("Secondary check: node RPC timed out after {:?}", timeout);
+                        None
+                    }
+                }
+            }
+            #[cfg(test)]
+            NodeHeadProbe::Fixed(head) => *head,
+        }
+    }
+}
+
+async fn rpc_local_head(
+    client: &Client<HttpConnector, Full<Bytes>>,
+    url: &Uri,
+    auth_token: Option<&str>,
+) -> anyhow::Result<i64> {
+    let body = json!({ "jsonrpc": "2.0", "id": 1, "method": "header.LocalHead", "params": [] });
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri(url.clone())
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(token) = auth_token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let request = request.body(Full::new(Bytes::from(body.to_string())))?;
+
+    let response = client.request(request).await?;
+    let status = response.status();
+    let bytes = response.into_body().collect().await?.to_bytes();
+    anyhow::ensure!(status.is_success(), "node RPC returned {}", status);
+
+    let reply: Value = serde_json::from_slice(&bytes)?;
+    if let Some(error) = reply.get("error") {
+        anyhow::bail!("node RPC error: {}", error);
+    }
+    // ExtendedHeader heights are JSON strings
+    let height = &reply["result"]["header"]["height"];
+    height
+        .as_str()
+        .and_then(|h| h.parse().ok())
+        .or_else(|| height.as_i64())
+        .ok_or_else(|| anyhow::anyhow!("no header.height in node RPC reply"))
+}
+
+/// Outcome for a tick whose OTLP telemetry is stale
+pub struct StaleVerdict {
+    pub ok: bool,
+    pub reason: String,
+    pub reason_code: SampleReason,
+    /// Head reported by the node RPC, if it answered
+    pub node_head: Option<i64>,
+}
+
+/// Judge a stale tick: failed, unless the node's RPC head has advanced past `reference`
+/// (the last head seen from any source), i.e. only the telemetry pipeline is down
+pub async fn stale_verdict(
+    probe: &NodeHeadProbe,
+    reference: Option<i64>,
+    min_increment: i64,
+    max_staleness_secs: u64,
+) -> StaleVerdict {
+    let node_head = probe.head().await;
+    match node_head {
+        Some(head) if reference.is_none_or(|r| head - r >= min_increment) => {
+            debug!("Secondary check: node head {} advancing despite stale telemetry", head);
+            StaleVerdict {
+                ok: true,
+                reason: format!("telemetry stale, node RPC head {} advancing", head),
+                reason_code: SampleReason::NodeRpcAdvancing,
+                node_head,
+            }
+        }
+        _ => StaleVerdict {
+            ok: false,
+            reason: format!("stale (age > {}s)", max_staleness_secs),
+            reason_code: SampleReason::Stale,
+            node_head,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::HeaderMap, routing::post, Json, Router};
+
+    #[tokio::test]
+    async fn test_secondary_check_overrides_stale_verdict() {
+        let verdict = stale_verdict(&NodeHeadProbe::Fixed(Some(110)), Some(100), 1, 120).await;
+        assert!(verdict.ok);
+        assert_eq!(verdict.node_head, Some(110));
+        assert!(verdict.reason.contains("node RPC head 110"));
+    }
+
+    #[tokio::test]
+    async fn test_stale_verdict_stands_when_node_stuck_or_unreachable() {
+        let stuck = stale_verdict(&NodeHeadProbe::Fixed(Some(100)), Some(100), 1, 120).await;
+        assert!(!stuck.ok);
+        assert_eq!(stuck.reason, "stale (age > 120s)");
+
+        let unreachable = stale_verdict(&NodeHeadProbe::Fixed(None), Some(100), 1, 120).await;
+        assert!(!unreachable.ok);
+
+        let disabled = stale_verdict(&NodeHeadProbe::Disabled, None, 1, 120).await;
+        assert!(!disabled.ok);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_probe_reads_local_head() {
+        let app = Router::new().route(
+            "/",
+            post(|Json(req): Json<Value>| async move {
+                assert_eq!(req["method"], "header.LocalHead");
+                Json(json!({ "jsonrpc": "2.0", "id": 1, "result": { "header": { "height": "8549697" } } }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let cfg = SecondaryCheckConfig {
+            enabled: true,
+            node_rpc_url: format!("http://{}/", addr),
+            timeout_secs: 5,
+        };
+        let probe = NodeHeadProbe::from_config(&cfg, None).unwrap();
+        assert_eq!(probe.head().await, Some(8549697));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_probe_sends_auth_token() {
+        let app = Router::new().route(
+            "/",
+            post(|headers: HeaderMap| async move {
+                if headers.get(header::AUTHORIZATION).is_some_and(|v| v == "Bearer node-jwt") {
+                    Json(json!({ "jsonrpc": "2.0", "id": 1, "result": { "header": { "height": "42" } } }))
+                } else {
+                    Json(json!({ "jsonrpc": "2.0", "id": 1, "error": { "message": "missing permissions" } }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let cfg = SecondaryCheckConfig {
+            enabled: true,
+            node_rpc_url: format!("http://{}/", addr),
+            timeout_secs: 5,
+        };
+        let with_token = NodeHeadProbe::from_config(&cfg, Some("node-jwt")).unwrap();
+        assert_eq!(with_token.head().await, Some(42));
+        let without = NodeHeadProbe::from_config(&cfg, None).unwrap();
+        assert_eq!(without.head().await, None);
+    }
+}
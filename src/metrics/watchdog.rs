@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+use crate::types::AppState;
+
+/// A task is considered wedged after this many of its periods without progress
+const WEDGED_AFTER_PERIODS: u64 = 3;
+
+/// A background task that has stopped making progress
+#[derive(Debug, PartialEq, Eq)]
+pub struct Stall {
+    pub task: &'static str,
+    /// Seconds since its last progress (or since startup if it never progressed)
+    pub age_secs: u64,
+    /// How often it is expected to progress
+    pub period_secs: u64,
+}
+
+/// Background task: warn when a task is alive but stuck (e.g. deadlocked), which
+/// panic-based restarts never notice, and again once it recovers
+pub async fn run_watchdog(state: AppState) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(state.config.sampling.tick_secs));
+    let mut wedged: HashSet<&'static str> = HashSet::new();
+
+    loop {
+        ticker.tick().await;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let stalls = stalled_tasks(&state, now);
+
+        for stall in &stalls {
+            if wedged.insert(stall.task) {
+                warn!("🧊 {} has made no progress for {}s (expected every {}s) - wedged?",
+                      stall.task, stall.age_secs, stall.period_secs);
+            }
+        }
+        wedged.retain(|task| {
+            let still_stalled = stalls.iter().any(|s| s.task == *task);
+            if !still_stalled {
+                info!("💚 {} is making progress again", task);
+            }
+            still_stalled
+        });
+    }
+}
+
+/// Tasks whose last progress is more than `WEDGED_AFTER_PERIODS` periods old
+///
+/// The sampler should progress every tick, the batch generator and DA posting every
+/// window (DA posting only when enabled).
+pub fn stalled_tasks(state: &AppState, now: u64) -> Vec<Stall> {
+    let config = &state.config;
+    let live = &state.liveness;
+    live.task_progress()
+        .into_iter()
+        .filter_map(|(task, last)| {
+            let period_secs = match task {
+                "sampler" => config.sampling.tick_secs,
+                "da_posting" if !config.da_posting.enabled => return None,
+                _ => config.batching.window_secs,
+            };
+            let age_secs = now.saturating_sub(last.unwrap_or(live.started_at));
+            (age_secs > WEDGED_AFTER_PERIODS * period_secs).then_some(Stall { task, age_secs, period_secs })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering;
+    use crate::config::Config;
+    use crate::telemetry::Liveness;
+
+    #[test]
+    fn test_stalled_task_timestamp_ages() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let start = state.liveness.started_at;
+        state.liveness.last_tick.store(start, Ordering::Relaxed);
+        state.liveness.last_batch.store(start, Ordering::Relaxed);
+        assert!(stalled_tasks(&state, start + 60).is_empty());
+
+        // The sampler stops ticking: its timestamp stays put and keeps ageing
+        let stall = |now| stalled_tasks(&state, now).into_iter().find(|s| s.task == "sampler");
+        assert_eq!(stall(start + 100), Some(Stall { task: "sampler", age_secs: 100, period_secs: 30 }));
+        assert_eq!(stall(start + 400).unwrap().age_secs, 400);
+        assert_eq!(Liveness::get(&state.liveness.last_tick), Some(start));
+
+        // A tick clears it
+        state.liveness.last_tick.store(start + 400, Ordering::Relaxed);
+        assert_eq!(stall(start + 410), None);
+    }
+
+    #[test]
+    fn test_da_posting_watched_only_when_enabled() {
+        let mut config = Config::for_tests();
+        let state = AppState::new(Arc::new(config.clone()));
+        let later = state.liveness.started_at + 10 * 600;
+        assert!(stalled_tasks(&state, later).iter().all(|s| s.task != "da_posting"));
+
+        config.da_posting.enabled = true;
+        let state = AppState::new(Arc::new(config));
+        let later = state.liveness.started_at + 10 * 600;
+        assert!(stalled_tasks(&state, later).iter().any(|s| s.task == "da_posting"));
+    }
+}
@@ -0,0 +1,119 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::config::DebugConfig;
+
+/// Prefix of dump file names; only these files are ever rotated out
+const DUMP_PREFIX: &str = "failed-";
+
+/// Write an undecodable request body to the dump directory for offline analysis
+///
+/// The oldest dumps are deleted first so the directory stays within `dump_max_files`
+/// files and `dump_max_bytes` bytes. Returns the path of the written file, or `None`
+/// if dumping is disabled or the body alone exceeds the caps.
+pub fn dump_failed_request(
+    config: &DebugConfig,
+    content_type: &str,
+    body: &[u8],
+) -> anyhow::Result<Option<PathBuf>> {
+    let dir = match &config.dump_failed_requests_dir {
+        Some(dir) => Path::new(dir),
+        None => return Ok(None),
+    };
+
+    if config.dump_max_files == 0 || body.len() as u64 > config.dump_max_bytes {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(dir)?;
+    rotate(dir, config.dump_max_files - 1, config.dump_max_bytes - body.len() as u64)?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    let extension = if content_type.contains("json") { "json" } else { "bin" };
+    let path = dir.join(format!(
+        "{}{}-{:09}.{}",
+        DUMP_PREFIX,
+        now.as_secs(),
+        now.subsec_nanos(),
+        extension
+    ));
+
+    fs::write(&path, body)?;
+    Ok(Some(path))
+}
+
+/// Delete the oldest dumps until at most `max_files` files and `max_bytes` bytes remain
+fn rotate(dir: &Path, max_files: usize, max_bytes: u64) -> anyhow::Result<()> {
+    let mut dumps = existing_dumps(dir)?;
+    let mut total_bytes: u64 = dumps.iter().map(|(_, len)| len).sum();
+
+    // Names embed the write time, so name order is age order
+    dumps.sort();
+    let mut dumps = dumps.into_iter();
+    let mut remaining = dumps.len();
+    while remaining > max_files || total_bytes > max_bytes {
+        let Some((path, len)) = dumps.next() else { break };
+        fs::remove_file(&path)?;
+        remaining -= 1;
+        total_bytes -= len;
+    }
+    Ok(())
+}
+
+/// Dump files in the directory with their sizes
+fn existing_dumps(dir: &Path) -> anyhow::Result<Vec<(PathBuf, u64)>> {
+    let mut dumps = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() && entry.file_name().to_string_lossy().starts_with(DUMP_PREFIX) {
+            dumps.push((entry.path(), metadata.len()));
+        }
+    }
+    Ok(dumps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump_config(dir: &Path, max_files: usize, max_bytes: u64) -> DebugConfig {
+        DebugConfig {
+            dump_failed_requests_dir: Some(dir.to_string_lossy().into_owned()),
+            dump_max_files: max_files,
+            dump_max_bytes: max_bytes,
+            ..Default::default()
+        }
+    }
+
+    fn remaining_contents(dir: &Path) -> Vec<Vec<u8>> {
+        let mut dumps = existing_dumps(dir).unwrap();
+        dumps.sort();
+        dumps.into_iter().map(|(path, _)| fs::read(path).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_file_cap_evicts_oldest_dumps() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dump_config(dir.path(), 2, 1024);
+        for body in [b"one", b"two", b"six"] {
+            dump_failed_request(&config, "application/json", body).unwrap().unwrap();
+        }
+        assert_eq!(remaining_contents(dir.path()), vec![b"two".to_vec(), b"six".to_vec()]);
+    }
+
+    #[test]
+    fn test_byte_cap_evicts_oldest_dumps_and_keeps_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), b"not a dump").unwrap();
+        let config = dump_config(dir.path(), 100, 10);
+        for body in [b"aaaa", b"bbbb", b"cccc"] {
+            dump_failed_request(&config, "application/x-protobuf", body).unwrap().unwrap();
+        }
+        assert_eq!(remaining_contents(dir.path()), vec![b"bbbb".to_vec(), b"cccc".to_vec()]);
+        assert!(dir.path().join("notes.txt").exists());
+
+        // A body larger than the whole cap is never written
+        assert!(dump_failed_request(&config, "application/json", &[0; 11]).unwrap().is_none());
+    }
+}
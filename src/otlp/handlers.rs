@@ -3,18 +3,38 @@ use opentelemetry_proto::tonic::collector::metrics::v1::{
     ExportMetricsServiceRequest, ExportMetricsServiceResponse,
 };
 use prost::Message;
+use std::collections::BTreeMap;
 use std::io::Read;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::{debug, info, warn};
+use std::sync::LazyLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn, error};
 use flate2::read::GzDecoder;
-use crate::types::{AppState, NormalizedMetric, MetricValue};
+use crate::config::{DecodeOrder, MetricType, MetricsConfig, ResourcelessPolicy, ServerConfig};
+use crate::telemetry::Liveness;
+use crate::types::{AppState, NormalizedMetric, MetricValue, SummaryQuantile};
 use super::{normalize_metrics, print_normalized_metrics};
+use super::dump::dump_failed_request;
+use super::lenient::lenient_normalize_json;
+use super::stream::for_each_resource;
+use super::normalized_log::append_normalized_metrics;
 
 /// Accept OTLP/HTTP metrics (JSON or protobuf) and extract DAS metrics
 pub async fn handle_metrics(
     State(state): State<AppState>,
     headers: HeaderMap,
     body: axum::body::Bytes,
+) -> (StatusCode, axum::body::Bytes) {
+    let started = Instant::now();
+    let response = process_export(&state, &headers, body);
+    state.telemetry.ingest_duration.observe(started.elapsed());
+    Liveness::mark(&state.liveness.last_ingest);
+    response
+}
+
+fn process_export(
+    state: &AppState,
+    headers: &HeaderMap,
+    body: axum::body::Bytes,
 ) -> (StatusCode, axum::body::Bytes) {
     // Log incoming request details
     debug!("Received request with {} bytes", body.len());
@@ -54,81 +74,225 @@ pub async fn handle_metrics(
         body
     };
     
-    // Try to decode based on content type
-    let result = if is_json {
-        // Try JSON decoding
-        match serde_json::from_slice::<ExportMetricsServiceRequest>(&decoded_body) {
-            Ok(req) => {
-                debug!("Successfully decoded JSON metrics");
-                Ok(req)
-            }
-            Err(e) => {
-                warn!("Failed to decode OTLP JSON: {e}");
-                debug!("Body preview: {:?}", String::from_utf8_lossy(&decoded_body[..decoded_body.len().min(200)]));
-                Err(())
-            }
+    // Health-checkers and misconfigured clients POST nothing; don't try to decode it
+    if decoded_body.is_empty() {
+        debug!("Rejecting empty OTLP request body");
+        return (StatusCode::BAD_REQUEST, axum::body::Bytes::from("empty body"));
+    }
+    
+    // Only keep every normalized metric around when it's going to be printed or logged
+    let print_normalized = tracing::enabled!(tracing::Level::DEBUG);
+    let retain_normalized = print_normalized || state.config.debug.normalized_metrics_file.is_some();
+    let IngestOutcome { normalized, metric_count, das_updated } =
+        ingest_payload_with(state, &decoded_body, is_json, retain_normalized);
+
+    if normalized.is_none() {
+        match dump_failed_request(&state.config.debug, content_type, &decoded_body) {
+            Ok(Some(path)) => info!("🗂️  Dumped undecodable request body to {}", path.display()),
+            Ok(None) => {}
+            Err(e) => error!("Failed to dump undecodable request body: {}", e),
         }
-    } else {
-        // Try protobuf decoding
-        match ExportMetricsServiceRequest::decode(decoded_body.clone()) {
-            Ok(req) => {
-                debug!("Successfully decoded protobuf metrics");
-                Ok(req)
-            }
-            Err(e) => {
-                warn!("Failed to decode OTLP protobuf: {e}");
-                // If protobuf fails, try JSON as fallback
-                match serde_json::from_slice::<ExportMetricsServiceRequest>(&decoded_body) {
-                    Ok(req) => {
-                        debug!("Successfully decoded JSON metrics (fallback)");
-                        Ok(req)
-                    }
-                    Err(e2) => {
-                        warn!("Failed to decode as JSON too: {e2}");
-                        debug!("Body preview: {:?}", String::from_utf8_lossy(&decoded_body[..decoded_body.len().min(200)]));
-                        Err(())
-                    }
-                }
-            }
+
+        if state.config.server.decode_order == DecodeOrder::Strict {
+            return (
+                StatusCode::BAD_REQUEST,
+                axum::body::Bytes::from(format!("body is not valid OTLP for Content-Type {:?}", content_type)),
+            );
         }
-    };
-    
-    if let Ok(req) = result {
-        let normalized = normalize_metrics(req);
-        
-        // Extract DAS-specific metrics and store them
-        let das_updated = extract_das_metrics(&normalized, &state);
-        
+    }
+
+    if let Some(normalized) = normalized {
         // Log successful metric ingestion
         if das_updated {
             info!("📥 Received OTLP metrics from DAS node - Stored internally");
         } else {
-            debug!("📥 Received {} OTLP metrics (no DAS-specific metrics found)", normalized.len());
+            debug!("📥 Received {} OTLP metrics (no DAS-specific metrics found)", metric_count);
         }
         
         // Only print detailed metrics in debug mode
-        if tracing::enabled!(tracing::Level::DEBUG) {
+        if print_normalized {
             print_normalized_metrics(&normalized);
         }
+        
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        if let Err(e) = append_normalized_metrics(&state.config.debug, &normalized, now) {
+            error!("Failed to write normalized metrics file: {}", e);
+        }
     }
 
     // Reply with appropriate response format
-    let resp = ExportMetricsServiceResponse { partial_success: None };
+    (StatusCode::OK, success_response(is_json))
+}
+
+/// Result of ingesting one OTLP payload
+pub struct IngestOutcome {
+    /// Normalized metrics (empty unless retained), or `None` if the body couldn't be decoded at all
+    pub normalized: Option<Vec<NormalizedMetric>>,
+    /// Number of normalized metrics, whether retained or not
+    pub metric_count: usize,
+    /// Whether any DAS metric was updated
+    pub das_updated: bool,
+}
+
+/// Decode an OTLP payload, normalize it and store any DAS metrics in `state`
+pub fn ingest_payload(state: &AppState, body: &[u8], is_json: bool) -> IngestOutcome {
+    ingest_payload_with(state, body, is_json, true)
+}
+
+/// Like `ingest_payload`, but only keeps the normalized metrics if `retain_normalized`
+///
+/// Without retaining, only the DAS metrics of each resource are kept as it's decoded, so
+/// large exports don't need all their normalized metrics in memory at once. They are
+/// applied once the whole body has decoded.
+pub fn ingest_payload_with(state: &AppState, body: &[u8], is_json: bool, retain_normalized: bool) -> IngestOutcome {
+    let order = state.config.server.decode_order;
+    let decoded = decode_request(body, is_json, &state.config.server, |collected: &mut Collected, metrics| {
+        collected.metric_count += metrics.len();
+        if retain_normalized {
+            collected.kept.extend(metrics);
+        } else {
+            collected.kept.extend(metrics.into_iter().filter(|metric| is_das_metric(metric, &state.config.metrics)));
+        }
+    });
+
+    // Extract DAS-specific metrics and store them
+    let (mut normalized, mut metric_count, mut das_updated) = match decoded {
+        Ok(collected) => {
+            let das_updated = extract_das_metrics(&collected.kept, state);
+            let kept = if retain_normalized { collected.kept } else { Vec::new() };
+            (Some(kept), collected.metric_count, das_updated)
+        }
+        Err(()) => (None, 0, false),
+    };
+
+    // Strict OTLP/JSON decoding either fails outright or silently drops values it
+    // can't deserialize (e.g. string-encoded asInt), so retry leniently before giving up
+    if !das_updated && needs_lenient_retry(normalized.is_some(), body, is_json, order, &state.config.metrics) {
+        if let Some(recovered) = lenient_normalize_json(body) {
+            das_updated = extract_das_metrics(&recovered, state);
+            if das_updated || normalized.is_none() {
+                if das_updated {
+                    warn!("Recovered DAS metrics via lenient OTLP/JSON parsing");
+                }
+                metric_count = recovered.len();
+                normalized = Some(if retain_normalized { recovered } else { Vec::new() });
+            }
+        }
+    }
+
+    IngestOutcome { normalized, metric_count, das_updated }
+}
+
+/// Whether an ingest that updated no DAS metric should be retried with the lenient parser
+///
+/// Only after a hard decode failure, or when a decoded body still names a configured
+/// head/headers metric (its values were dropped): the many exports without DAS metrics
+/// are not parsed twice. Never for a protobuf-declared body in strict mode.
+fn needs_lenient_retry(decoded: bool, body: &[u8], is_json: bool, order: DecodeOrder, config: &MetricsConfig) -> bool {
+    if !is_json && order == DecodeOrder::Strict {
+        return false;
+    }
+    !decoded
+        || config
+            .head_metric
+            .iter()
+            .chain(config.headers_metric.iter())
+            .any(|name| !name.is_empty() && body.windows(name.len()).any(|window| window == name.as_bytes()))
+}
+
+/// Normalized metrics collected by one decode attempt
+#[derive(Default)]
+struct Collected {
+    /// All of them, or only the DAS metrics when not retaining
+    kept: Vec<NormalizedMetric>,
+    metric_count: usize,
+}
+
+/// Decode an OTLP export request in the order given by `server.decode_order`,
+/// handing normalized metrics to `on_metrics` (per resource for protobuf)
+///
+/// Each attempt collects into a fresh `T`, returned only if that attempt decoded the
+/// whole body, so a format that fails partway leaves nothing behind.
+fn decode_request<T: Default>(
+    body: &[u8],
+    is_json: bool,
+    server: &ServerConfig,
+    mut on_metrics: impl FnMut(&mut T, Vec<NormalizedMetric>),
+) -> Result<T, ()> {
+    let (order, max_buckets) = (server.decode_order, server.max_buckets_per_point);
+    let attempts: &[Format] = match (is_json, order) {
+        (true, _) => &[Format::Json],
+        (false, DecodeOrder::ProtobufFirst) => &[Format::Protobuf, Format::Json],
+        (false, DecodeOrder::JsonFirst) => &[Format::Json, Format::Protobuf],
+        (false, DecodeOrder::Strict) => &[Format::Protobuf],
+    };
+
+    for (i, format) in attempts.iter().enumerate() {
+        let fallback = if i > 0 { " (fallback)" } else { "" };
+        let mut collected = T::default();
+        let result = match format {
+            Format::Protobuf => for_each_resource(body, max_buckets, |metrics| on_metrics(&mut collected, metrics))
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            Format::Json => serde_json::from_slice::<ExportMetricsServiceRequest>(body)
+                .map(|req| on_metrics(&mut collected, normalize_metrics(req, max_buckets)))
+                .map_err(|e| e.to_string()),
+        };
+        match result {
+            Ok(()) => {
+                debug!("Successfully decoded {:?} metrics{}", format, fallback);
+                return Ok(collected);
+            }
+            Err(e) => warn!("Failed to decode OTLP {:?}{}: {}", format, fallback, e),
+        }
+    }
+
+    debug!("Body preview: {:?}", String::from_utf8_lossy(&body[..body.len().min(200)]));
+    Err(())
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Protobuf,
+    Json,
+}
+
+/// Success responses never change, so encode them once and hand out cheap clones
+static JSON_SUCCESS: LazyLock<axum::body::Bytes> =
+    LazyLock::new(|| encode_response(&ExportMetricsServiceResponse { partial_success: None }, true));
+static PROTOBUF_SUCCESS: LazyLock<axum::body::Bytes> =
+    LazyLock::new(|| encode_response(&ExportMetricsServiceResponse { partial_success: None }, false));
+
+/// Pre-encoded full-success response in the request's format
+fn success_response(is_json: bool) -> axum::body::Bytes {
     if is_json {
-        let json = serde_json::to_vec(&resp).unwrap();
-        (StatusCode::OK, axum::body::Bytes::from(json))
+        JSON_SUCCESS.clone()
     } else {
-        let mut buf = Vec::new();
-        prost::Message::encode(&resp, &mut buf).unwrap();
-        (StatusCode::OK, axum::body::Bytes::from(buf))
+        PROTOBUF_SUCCESS.clone()
     }
 }
 
+/// Encode an export response (used directly when `partial_success` is set)
+fn encode_response(resp: &ExportMetricsServiceResponse, is_json: bool) -> axum::body::Bytes {
+    if is_json {
+        axum::body::Bytes::from(serde_json::to_vec(resp).unwrap())
+    } else {
+        axum::body::Bytes::from(resp.encode_to_vec())
+    }
+}
+
+/// Whether `metric` goes by a configured head or headers name (`extract_das_metrics`
+/// ignores everything else)
+fn is_das_metric(metric: &NormalizedMetric, config: &MetricsConfig) -> bool {
+    config.head_metric.matches(&metric.name) || config.headers_metric.matches(&metric.name)
+}
+
 /// Extract DAS-specific metrics and update state
 /// Returns true if any DAS metrics were updated
 fn extract_das_metrics(metrics: &[NormalizedMetric], state: &AppState) -> bool {
     let config = &state.config.metrics;
     let mut das_metrics = state.das_metrics.lock().unwrap();
+    let mut previous_values = state.previous_values.lock().unwrap();
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
@@ -137,20 +301,58 @@ fn extract_das_metrics(metrics: &[NormalizedMetric], state: &AppState) -> bool {
     let mut updated = false;
     
     for metric in metrics {
+        let of_type = |wanted: Option<MetricType>| wanted.is_none_or(|t| t.matches(&metric.metric_type));
+        let is_head = config.head_metric.matches(&metric.name) && of_type(config.head_metric_type);
+        let is_headers = config.headers_metric.matches(&metric.name) && of_type(config.headers_metric_type);
+        if !(is_head || is_headers) {
+            continue;
+        }
+        if metric.resource_attributes.is_empty() {
+            match config.resourceless_metrics {
+                ResourcelessPolicy::Accept => debug!("{} has no resource attributes, accepting it", metric.name),
+                ResourcelessPolicy::Reject => {
+                    warn!("🚫 Ignoring {}: no resource attributes (metrics.resourceless_metrics = \"reject\")", metric.name);
+                    continue;
+                }
+            }
+        }
+
+        let previous = match metric.value {
+            MetricValue::Int(value) => previous_values.record(&metric.name, value as f64),
+            MetricValue::Double(value) => previous_values.record(&metric.name, value),
+            _ => None,
+        };
+
         // Extract das_sampled_chain_head
-        if metric.name == config.head_metric {
-            if let MetricValue::Int(value) = metric.value {
+        if is_head {
+            let head = match &metric.value {
+                MetricValue::Int(value) => Some(*value),
+                MetricValue::Summary { quantiles, .. } => config
+                    .head_summary_quantile
+                    .and_then(|q| interpolate_quantile(quantiles, q))
+                    .map(|v| v.round() as i64),
+                _ => None,
+            };
+
+            if let Some(value) = head {
                 das_metrics.head = Some(value);
                 das_metrics.last_update = Some(now);
+                das_metrics.source_attributes = select_attributes(metric, &config.sample_resource_attributes);
+                das_metrics.labels = select_labels(metric, &config.sample_labels);
                 debug!("Updated DAS head: {}", value);
                 updated = true;
             }
         }
         
         // Extract das_total_sampled_headers
-        if metric.name == config.headers_metric {
+        if is_headers {
             if let MetricValue::Int(value) = metric.value {
+                if let Some(prev) = previous.filter(|&prev| (value as f64) < prev) {
+                    warn!("🔁 {} went backwards ({} -> {}), node likely restarted", metric.name, prev, value);
+                }
                 das_metrics.headers = Some(value);
+                das_metrics.source_attributes = select_attributes(metric, &config.sample_resource_attributes);
+                das_metrics.labels = select_labels(metric, &config.sample_labels);
                 debug!("Updated DAS headers: {}", value);
                 updated = true;
             }
@@ -160,3 +362,453 @@ fn extract_das_metrics(metrics: &[NormalizedMetric], state: &AppState) -> bool {
     updated
 }
 
+/// Pick the configured resource attributes off the metric that drove an update
+fn select_attributes(metric: &NormalizedMetric, keys: &[String]) -> BTreeMap<String, String> {
+    keys.iter()
+        .filter_map(|key| {
+            metric
+                .resource_attributes
+                .get(key)
+                .map(|value| (key.clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Map the configured metric attributes onto sample labels
+///
+/// A data point attribute wins over a resource attribute with the same key.
+fn select_labels(metric: &NormalizedMetric, mapping: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    mapping
+        .iter()
+        .filter_map(|(key, label)| {
+            metric
+                .attributes
+                .get(key)
+                .or_else(|| metric.resource_attributes.get(key))
+                .map(|value| (label.clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Read the value at quantile `q` from a summary, interpolating linearly between
+/// the neighbouring quantile points when `q` isn't exported exactly
+///
+/// Quantiles outside the exported range clamp to the nearest exported point.
+fn interpolate_quantile(quantiles: &[SummaryQuantile], q: f64) -> Option<f64> {
+    let mut points: Vec<&SummaryQuantile> = quantiles.iter().collect();
+    points.sort_by(|a, b| a.quantile.total_cmp(&b.quantile));
+
+    let first = points.first()?;
+    let last = points.last()?;
+    if q <= first.quantile {
+        return Some(first.value);
+    }
+    if q >= last.quantile {
+        return Some(last.value);
+    }
+
+    points.windows(2).find_map(|pair| {
+        let (lo, hi) = (pair[0], pair[1]);
+        if q < lo.quantile || q > hi.quantile {
+            return None;
+        }
+        if hi.quantile == lo.quantile {
+            return Some(lo.value);
+        }
+        let fraction = (q - lo.quantile) / (hi.quantile - lo.quantile);
+        Some(lo.value + fraction * (hi.value - lo.value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::config::Config;
+
+    #[tokio::test]
+    async fn test_failed_decode_writes_dump_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::for_tests();
+        config.debug.dump_failed_requests_dir = Some(dir.path().to_string_lossy().into_owned());
+        let state = AppState::new(Arc::new(config));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        let body = axum::body::Bytes::from_static(b"{not valid otlp");
+
+        handle_metrics(State(state), headers, body).await;
+
+        let dumps: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(dumps.len(), 1);
+        let path = dumps[0].as_ref().unwrap().path();
+        assert_eq!(path.extension().unwrap(), "json");
+        assert_eq!(std::fs::read(path).unwrap(), b"{not valid otlp");
+    }
+
+    #[tokio::test]
+    async fn test_ingest_appends_normalized_metrics_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("normalized.jsonl");
+        let mut config = Config::for_tests();
+        config.debug.normalized_metrics_file = Some(path.to_string_lossy().into_owned());
+        let state = AppState::new(Arc::new(config));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        let body = r#"{"resourceMetrics":[{"scopeMetrics":[{"metrics":[
+            {"name":"das_sampled_chain_head","gauge":{"dataPoints":[{"asInt":"42"}]}},
+            {"name":"unrelated_metric","gauge":{"dataPoints":[{"asDouble":1.5}]}}]}]}]}"#;
+        for _ in 0..2 {
+            handle_metrics(State(state.clone()), headers.clone(), axum::body::Bytes::from(body)).await;
+        }
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["name"], "das_sampled_chain_head");
+        assert_eq!(lines[0]["value"], 42);
+        assert_eq!(lines[1]["name"], "unrelated_metric");
+        assert!(lines[0]["received_at"].as_u64().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_dump_respects_file_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::for_tests();
+        config.debug.dump_failed_requests_dir = Some(dir.path().to_string_lossy().into_owned());
+        config.debug.dump_max_files = 1;
+        let state = AppState::new(Arc::new(config));
+
+        for _ in 0..3 {
+            let mut headers = HeaderMap::new();
+            headers.insert("content-type", "application/json".parse().unwrap());
+            handle_metrics(State(state.clone()), headers, axum::body::Bytes::from_static(b"garbage")).await;
+        }
+
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    fn summary_head(quantiles: &[(f64, f64)]) -> NormalizedMetric {
+        NormalizedMetric {
+            name: "das_sampled_chain_head".to_string(),
+            metric_type: "Summary".to_string(),
+            value: MetricValue::Summary {
+                count: 1,
+                sum: 0.0,
+                quantiles: quantiles
+                    .iter()
+                    .map(|&(quantile, value)| SummaryQuantile { quantile, value })
+                    .collect(),
+            },
+            attributes: Default::default(),
+            resource_attributes: Default::default(),
+            scope_name: None,
+            scope_version: None,
+            time_unix_nano: None,
+            start_time_unix_nano: None,
+        }
+    }
+
+    #[test]
+    fn test_summary_head_exact_quantile() {
+        let mut config = Config::for_tests();
+        config.metrics.head_summary_quantile = Some(0.5);
+        let state = AppState::new(Arc::new(config));
+
+        let metric = summary_head(&[(0.0, 100.0), (0.5, 150.0), (1.0, 200.0)]);
+        assert!(extract_das_metrics(&[metric], &state));
+        assert_eq!(state.das_metrics.lock().unwrap().head, Some(150));
+    }
+
+    #[test]
+    fn test_summary_head_interpolated_quantile() {
+        let mut config = Config::for_tests();
+        config.metrics.head_summary_quantile = Some(0.9);
+        let state = AppState::new(Arc::new(config));
+
+        // 0.9 lies 80% of the way from 0.5 to 1.0
+        let metric = summary_head(&[(1.0, 200.0), (0.5, 150.0)]);
+        assert!(extract_das_metrics(&[metric], &state));
+        assert_eq!(state.das_metrics.lock().unwrap().head, Some(190));
+    }
+
+    #[test]
+    fn test_summary_head_ignored_without_quantile_config() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let metric = summary_head(&[(0.5, 150.0)]);
+        assert!(!extract_das_metrics(&[metric], &state));
+        assert_eq!(state.das_metrics.lock().unwrap().head, None);
+    }
+
+    #[test]
+    fn test_headers_metric_matched_under_alias() {
+        let mut config = Config::for_tests();
+        config.metrics.headers_metric =
+            serde_json::from_value(serde_json::json!(["das_total_sampled_headers", "das_sampled_headers_total"])).unwrap();
+        let state = AppState::new(Arc::new(config));
+
+        let renamed = NormalizedMetric {
+            name: "das_sampled_headers_total".to_string(),
+            metric_type: "Sum".to_string(),
+            value: MetricValue::Int(1234),
+            ..summary_head(&[])
+        };
+        assert!(extract_das_metrics(&[renamed], &state));
+        assert_eq!(state.das_metrics.lock().unwrap().headers, Some(1234));
+    }
+
+    #[test]
+    fn test_head_metric_matched_under_second_name() {
+        let mut config = Config::for_tests();
+        config.metrics.head_metric =
+            toml::from_str::<crate::config::MetricsConfig>(
+                "head_metric = [\"das_sampled_chain_head\", \"hdr_sync_head\"]\nheaders_metric = \"das_total_sampled_headers\"\nmin_increment = 1",
+            )
+            .unwrap()
+            .head_metric;
+        let state = AppState::new(Arc::new(config));
+
+        // e.g. a node version that only exports the header-sync name
+        let renamed = NormalizedMetric {
+            name: "hdr_sync_head".to_string(),
+            metric_type: "Gauge".to_string(),
+            value: MetricValue::Int(8549697),
+            ..summary_head(&[])
+        };
+        assert!(extract_das_metrics(&[renamed], &state));
+        assert_eq!(state.das_metrics.lock().unwrap().head, Some(8549697));
+    }
+
+    #[test]
+    fn test_metric_type_disambiguates_shared_name() {
+        let mut config = Config::for_tests();
+        config.metrics.head_metric_type = Some(MetricType::Gauge);
+        let state = AppState::new(Arc::new(config));
+
+        // Same name exported as a Sum (wrong) and a Gauge (right), Sum last so it would win
+        let gauge = NormalizedMetric {
+            metric_type: "Gauge".to_string(),
+            value: MetricValue::Int(500),
+            ..summary_head(&[])
+        };
+        let sum = NormalizedMetric {
+            metric_type: "Sum".to_string(),
+            value: MetricValue::Int(99_999),
+            ..summary_head(&[])
+        };
+        assert!(extract_das_metrics(&[gauge, sum.clone()], &state));
+        assert_eq!(state.das_metrics.lock().unwrap().head, Some(500));
+
+        // Only the wrong type: nothing extracted, nor remembered as the previous value
+        let state = AppState::new(state.config.clone());
+        assert!(!extract_das_metrics(&[sum], &state));
+        assert_eq!(state.das_metrics.lock().unwrap().head, None);
+        assert_eq!(state.previous_values.lock().unwrap().get("das_sampled_chain_head"), None);
+    }
+
+    #[test]
+    fn test_interpolate_quantile_clamps_and_empty() {
+        let points = [
+            SummaryQuantile { quantile: 0.25, value: 10.0 },
+            SummaryQuantile { quantile: 0.75, value: 30.0 },
+        ];
+        assert_eq!(interpolate_quantile(&points, 0.0), Some(10.0));
+        assert_eq!(interpolate_quantile(&points, 1.0), Some(30.0));
+        assert_eq!(interpolate_quantile(&points, 0.5), Some(20.0));
+        assert_eq!(interpolate_quantile(&[], 0.5), None);
+    }
+
+    fn head_request(value: i64) -> ExportMetricsServiceRequest {
+        use opentelemetry_proto::tonic::metrics::v1::{
+            metric::Data, number_data_point, Gauge, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics,
+        };
+
+        ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                scope_metrics: vec![ScopeMetrics {
+                    metrics: vec![Metric {
+                        name: "das_sampled_chain_head".to_string(),
+                        data: Some(Data::Gauge(Gauge {
+                            data_points: vec![NumberDataPoint {
+                                value: Some(number_data_point::Value::AsInt(value)),
+                                ..Default::default()
+                            }],
+                        })),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_protobuf_with_unknown_field_decodes() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+
+        let mut body = Vec::new();
+        head_request(42).encode(&mut body).unwrap();
+        // Field 15 (varint) doesn't exist in the message we compiled against
+        body.extend_from_slice(&[0x78, 0x01]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/x-protobuf".parse().unwrap());
+        handle_metrics(State(state.clone()), headers, axum::body::Bytes::from(body)).await;
+
+        assert_eq!(state.das_metrics.lock().unwrap().head, Some(42));
+    }
+
+    #[tokio::test]
+    async fn test_json_with_unknown_field_decodes() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let body = r#"{"resourceMetrics":[{"scopeMetrics":[{"metrics":[{"name":"das_sampled_chain_head","futureField":1,
+            "gauge":{"dataPoints":[{"asInt":"42","timeUnixNano":"1"}]}}]}]}],"futureTopLevel":true}"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        handle_metrics(State(state.clone()), headers, axum::body::Bytes::from(body)).await;
+
+        assert_eq!(state.das_metrics.lock().unwrap().head, Some(42));
+    }
+
+    #[test]
+    fn test_resourceless_metrics_under_both_policies() {
+        let resourceless = br#"{"resourceMetrics":[{"scopeMetrics":[{"metrics":[{"name":"das_sampled_chain_head",
+            "gauge":{"dataPoints":[{"asInt":"42"}]}}]}]}]}"#;
+        let identified = br#"{"resourceMetrics":[{"resource":{"attributes":[{"key":"service.instance.id",
+            "value":{"stringValue":"node-1"}}]},"scopeMetrics":[{"metrics":[{"name":"das_sampled_chain_head",
+            "gauge":{"dataPoints":[{"asInt":"43"}]}}]}]}]}"#;
+
+        let accepting = AppState::new(Arc::new(Config::for_tests()));
+        assert!(ingest_payload(&accepting, resourceless, true).das_updated);
+        assert_eq!(accepting.das_metrics.lock().unwrap().head, Some(42));
+
+        let mut config = Config::for_tests();
+        config.metrics.resourceless_metrics = ResourcelessPolicy::Reject;
+        let rejecting = AppState::new(Arc::new(config));
+        assert!(!ingest_payload(&rejecting, resourceless, true).das_updated);
+        assert_eq!(rejecting.das_metrics.lock().unwrap().head, None);
+        assert!(ingest_payload(&rejecting, identified, true).das_updated);
+        assert_eq!(rejecting.das_metrics.lock().unwrap().head, Some(43));
+    }
+
+    #[tokio::test]
+    async fn test_json_hard_failure_recovered_leniently() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        // A field shape strict decoding can't handle: the metric data is silently dropped
+        let body = r#"{"resourceMetrics":[{"scopeMetrics":[{"metrics":[{"name":"das_sampled_chain_head",
+            "gauge":{"dataPoints":[{"asInt":"42","timeUnixNano":{"seconds":1}}]}}]}]}]}"#;
+        let strict = serde_json::from_str::<ExportMetricsServiceRequest>(body).unwrap();
+        assert!(normalize_metrics(strict, usize::MAX).is_empty());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        handle_metrics(State(state.clone()), headers, axum::body::Bytes::from(body)).await;
+
+        assert_eq!(state.das_metrics.lock().unwrap().head, Some(42));
+    }
+
+    #[test]
+    fn test_lenient_retry_only_when_strict_decoding_may_have_lost_das_metrics() {
+        let metrics = &Config::for_tests().metrics;
+        let non_das = br#"{"resourceMetrics":[{"scopeMetrics":[{"metrics":[{"name":"http_requests",
+            "gauge":{"dataPoints":[{"asDouble":5.0,"timeUnixNano":"1"}]}}]}]}]}"#;
+        assert!(!needs_lenient_retry(true, non_das, true, DecodeOrder::ProtobufFirst, metrics));
+        // Decoded, but the DAS metric's value may have been dropped
+        assert!(needs_lenient_retry(true, &json_head_body(), true, DecodeOrder::Strict, metrics));
+        // Hard failures are always retried, except protobuf under strict
+        assert!(needs_lenient_retry(false, b"{not valid", true, DecodeOrder::Strict, metrics));
+        assert!(needs_lenient_retry(false, b"{not valid", false, DecodeOrder::ProtobufFirst, metrics));
+        assert!(!needs_lenient_retry(false, b"{not valid", false, DecodeOrder::Strict, metrics));
+
+        // End to end: a clean non-DAS export decodes as usual
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let outcome = ingest_payload(&state, non_das, true);
+        assert!(!outcome.das_updated);
+        assert_eq!(outcome.metric_count, 1);
+    }
+
+    async fn post_with_order(order: DecodeOrder, content_type: &'static str, body: Vec<u8>) -> (StatusCode, AppState) {
+        let mut config = Config::for_tests();
+        config.server.decode_order = order;
+        let state = AppState::new(Arc::new(config));
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", content_type.parse().unwrap());
+        let (status, _) = handle_metrics(State(state.clone()), headers, axum::body::Bytes::from(body)).await;
+        (status, state)
+    }
+
+    fn json_head_body() -> Vec<u8> {
+        br#"{"resourceMetrics":[{"scopeMetrics":[{"metrics":[{"name":"das_sampled_chain_head",
+            "gauge":{"dataPoints":[{"asInt":"42","timeUnixNano":"1"}]}}]}]}]}"#.to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_strict_rejects_json_declared_as_protobuf() {
+        let (status, state) = post_with_order(DecodeOrder::Strict, "application/x-protobuf", json_head_body()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(state.das_metrics.lock().unwrap().head, None);
+    }
+
+    #[tokio::test]
+    async fn test_strict_rejects_protobuf_declared_as_json() {
+        let mut body = Vec::new();
+        head_request(42).encode(&mut body).unwrap();
+        let (status, state) = post_with_order(DecodeOrder::Strict, "application/json", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(state.das_metrics.lock().unwrap().head, None);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_orders_accept_mismatched_body() {
+        for order in [DecodeOrder::ProtobufFirst, DecodeOrder::JsonFirst] {
+            let (status, state) = post_with_order(order, "application/x-protobuf", json_head_body()).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(state.das_metrics.lock().unwrap().head, Some(42));
+        }
+
+        let mut body = Vec::new();
+        head_request(7).encode(&mut body).unwrap();
+        let (status, state) = post_with_order(DecodeOrder::JsonFirst, "application/x-protobuf", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(state.das_metrics.lock().unwrap().head, Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_ingest_duration_recorded() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        handle_metrics(State(state.clone()), headers, axum::body::Bytes::from(json_head_body())).await;
+
+        let rendered = state.telemetry.render();
+        assert!(rendered.contains("da_reader_ingest_duration_seconds_count 1\n"));
+        assert!(rendered.contains("da_reader_ingest_duration_seconds_bucket{le=\"+Inf\"} 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_body_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::for_tests();
+        config.debug.dump_failed_requests_dir = Some(dir.path().to_string_lossy().into_owned());
+        let state = AppState::new(Arc::new(config));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/x-protobuf".parse().unwrap());
+        let (status, body) = handle_metrics(State(state), headers, axum::body::Bytes::new()).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(&body[..], b"empty body");
+        // Never reached the decoder, so nothing was dumped
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_cached_responses_match_fresh_encoding() {
+        let resp = ExportMetricsServiceResponse { partial_success: None };
+        assert_eq!(success_response(true), encode_response(&resp, true));
+        assert_eq!(success_response(false), encode_response(&resp, false));
+    }
+}
@@ -0,0 +1,113 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use crate::types::{NormalizedMetric, MetricValue};
+
+/// Best-effort extraction of Gauge/Sum number data points from an OTLP/JSON body
+///
+/// Used only when strict decoding fails (e.g. a collector on a newer OTLP version sends
+/// a field shape we can't deserialize). Walks the raw JSON and keeps whatever metric
+/// names and numeric values it can find, skipping anything it doesn't understand.
+/// Returns `None` if the body isn't JSON or no number data points were found.
+pub fn lenient_normalize_json(body: &[u8]) -> Option<Vec<NormalizedMetric>> {
+    let root: Value = serde_json::from_slice(body).ok()?;
+    let mut normalized = Vec::new();
+
+    for resource_metric in array(&root, "resourceMetrics") {
+        let resource_attrs = resource_metric
+            .get("resource")
+            .map(|r| attributes(r.get("attributes")))
+            .unwrap_or_default();
+
+        for scope_metric in array(resource_metric, "scopeMetrics") {
+            let scope = scope_metric.get("scope");
+            let scope_name = scope.and_then(|s| s.get("name")).and_then(Value::as_str).map(String::from);
+            let scope_version = scope.and_then(|s| s.get("version")).and_then(Value::as_str).map(String::from);
+
+            for metric in array(scope_metric, "metrics") {
+                let Some(name) = metric.get("name").and_then(Value::as_str) else {
+                    continue;
+                };
+
+                for metric_type in ["gauge", "sum"] {
+                    let Some(data) = metric.get(metric_type) else {
+                        continue;
+                    };
+
+                    for dp in array(data, "dataPoints") {
+                        let Some(value) = number_value(dp) else {
+                            continue;
+                        };
+
+                        normalized.push(NormalizedMetric {
+                            name: name.to_string(),
+                            metric_type: if metric_type == "gauge" { "Gauge" } else { "Sum" }.to_string(),
+                            value,
+                            attributes: attributes(dp.get("attributes")),
+                            resource_attributes: resource_attrs.clone(),
+                            scope_name: scope_name.clone(),
+                            scope_version: scope_version.clone(),
+                            time_unix_nano: dp.get("timeUnixNano").and_then(as_u64),
+                            start_time_unix_nano: dp.get("startTimeUnixNano").and_then(as_u64),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    if normalized.is_empty() {
+        None
+    } else {
+        Some(normalized)
+    }
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+/// OTLP/JSON encodes 64-bit integers as strings, but accept plain numbers too
+fn as_i64(value: &Value) -> Option<i64> {
+    value.as_i64().or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+}
+
+fn as_u64(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+}
+
+fn number_value(dp: &Value) -> Option<MetricValue> {
+    if let Some(i) = dp.get("asInt").and_then(as_i64) {
+        return Some(MetricValue::Int(i));
+    }
+    dp.get("asDouble")
+        .and_then(|d| d.as_f64().or_else(|| d.as_str().and_then(|s| s.parse().ok())))
+        .map(MetricValue::Double)
+}
+
+fn attributes(value: Option<&Value>) -> HashMap<String, String> {
+    value
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|kv| {
+            let key = kv.get("key")?.as_str()?;
+            let any = kv.get("value")?;
+            let value = if let Some(s) = any.get("stringValue").and_then(Value::as_str) {
+                s.to_string()
+            } else if let Some(i) = any.get("intValue").and_then(as_i64) {
+                i.to_string()
+            } else if let Some(d) = any.get("doubleValue").and_then(Value::as_f64) {
+                d.to_string()
+            } else if let Some(b) = any.get("boolValue").and_then(Value::as_bool) {
+                b.to_string()
+            } else {
+                return None;
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
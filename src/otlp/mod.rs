@@ -1,11 +1,33 @@
+// OTLP/HTTP metrics ingestion
+//
+// Decoding uses the OTLP v1 messages from `opentelemetry-proto` 0.31 (OTLP 1.x).
+// Payloads from newer OTLP 1.x exporters are tolerated:
+// - protobuf: prost skips unknown fields, so added fields decode fine
+// - JSON: unknown keys are ignored; if strict decoding fails or yields no DAS
+//   metrics, a lenient parser recovers Gauge/Sum number data points (see `lenient`)
+//
+// Non-JSON bodies are tried as protobuf then JSON by default; `server.decode_order`
+// can flip that order or (`strict`) reject bodies that don't match their Content-Type.
+// Protobuf is decoded one resource at a time (see `stream`) to bound peak memory.
+
 mod handlers;
+mod dump;
+mod lenient;
+mod previous;
+mod stream;
+mod normalized_log;
+mod self_test;
 
-pub use handlers::handle_metrics;
+pub use handlers::{handle_metrics, ingest_payload};
+pub use self_test::run_self_test;
+pub use previous::PreviousValues;
 
 use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
 use opentelemetry_proto::tonic::common::v1::KeyValue;
+use opentelemetry_proto::tonic::metrics::v1::ExponentialHistogramDataPoint;
+use opentelemetry_proto::tonic::metrics::v1::exponential_histogram_data_point::Buckets;
 use std::collections::HashMap;
-use tracing::debug;
+use tracing::{debug, warn};
 use crate::types::{NormalizedMetric, MetricValue, HistogramBucket, SummaryQuantile};
 
 /// Helper function to convert OTLP KeyValue attributes to HashMap
@@ -28,7 +50,10 @@ pub fn attributes_to_map(attributes: Vec<KeyValue>) -> HashMap<String, String> {
 }
 
 /// Normalize OTLP metrics into a simpler, more processable structure
-pub fn normalize_metrics(req: ExportMetricsServiceRequest) -> Vec<NormalizedMetric> {
+///
+/// At most `max_buckets_per_point` histogram buckets or summary quantiles are kept
+/// per data point, so a hostile exporter can't make normalization allocate without bound.
+pub fn normalize_metrics(req: ExportMetricsServiceRequest, max_buckets_per_point: usize) -> Vec<NormalizedMetric> {
     let mut normalized_metrics = Vec::new();
 
     for resource_metric in req.resource_metrics {
@@ -88,10 +113,12 @@ pub fn normalize_metrics(req: ExportMetricsServiceRequest) -> Vec<NormalizedMetr
                         }
                         Data::Histogram(histogram) => {
                             for dp in histogram.data_points {
+                                warn_if_truncated(&metric_name, "buckets", dp.bucket_counts.len(), max_buckets_per_point);
                                 let buckets = dp
                                     .bucket_counts
                                     .iter()
                                     .zip(dp.explicit_bounds.iter())
+                                    .take(max_buckets_per_point)
                                     .map(|(count, bound)| HistogramBucket {
                                         count: *count,
                                         upper_bound: *bound,
@@ -117,9 +144,11 @@ pub fn normalize_metrics(req: ExportMetricsServiceRequest) -> Vec<NormalizedMetr
                         }
                         Data::Summary(summary) => {
                             for dp in summary.data_points {
+                                warn_if_truncated(&metric_name, "quantiles", dp.quantile_values.len(), max_buckets_per_point);
                                 let quantiles = dp
                                     .quantile_values
                                     .iter()
+                                    .take(max_buckets_per_point)
                                     .map(|qv| SummaryQuantile {
                                         quantile: qv.quantile,
                                         value: qv.value,
@@ -143,9 +172,27 @@ pub fn normalize_metrics(req: ExportMetricsServiceRequest) -> Vec<NormalizedMetr
                                 });
                             }
                         }
-                        Data::ExponentialHistogram(_) => {
-                            // ExponentialHistogram is less common, you can implement if needed
-                            debug!("ExponentialHistogram not yet implemented for {}", metric_name);
+                        Data::ExponentialHistogram(histogram) => {
+                            for dp in histogram.data_points {
+                                warn_if_truncated(&metric_name, "buckets", exponential_bucket_count(&dp), max_buckets_per_point);
+                                let buckets = exponential_buckets(&dp).take(max_buckets_per_point).collect();
+
+                                normalized_metrics.push(NormalizedMetric {
+                                    name: metric_name.clone(),
+                                    metric_type: "Histogram".to_string(),
+                                    value: MetricValue::Histogram {
+                                        count: dp.count,
+                                        sum: dp.sum,
+                                        buckets,
+                                    },
+                                    attributes: attributes_to_map(dp.attributes),
+                                    resource_attributes: resource_attrs.clone(),
+                                    scope_name: scope_name.clone(),
+                                    scope_version: scope_version.clone(),
+                                    time_unix_nano: Some(dp.time_unix_nano),
+                                    start_time_unix_nano: Some(dp.start_time_unix_nano),
+                                });
+                            }
                         }
                     }
                 }
@@ -156,15 +203,56 @@ pub fn normalize_metrics(req: ExportMetricsServiceRequest) -> Vec<NormalizedMetr
     normalized_metrics
 }
 
+/// Log a data point whose buckets/quantiles exceed `server.max_buckets_per_point`
+fn warn_if_truncated(metric_name: &str, what: &str, len: usize, max: usize) {
+    if len > max {
+        warn!("✂️  {} has {} {} in one data point, keeping the first {}", metric_name, len, what, max);
+    }
+}
+
+/// An exponential histogram's buckets as explicit ones, in ascending `upper_bound` order
+///
+/// With `base = 2^(2^-scale)`, positive bucket `offset + i` holds values in
+/// `(base^(offset+i), base^(offset+i+1)]` and negative bucket `offset + i` the mirror image,
+/// so their upper bounds are `base^(offset+i+1)` and `-base^(offset+i)`. The zero bucket's
+/// upper bound is `zero_threshold`; it is left out when empty.
+///
+/// Yielded lazily (negative buckets from the highest index down, then zero, then positive)
+/// so an oversized data point can be capped without materializing every bucket.
+fn exponential_buckets(dp: &ExponentialHistogramDataPoint) -> impl Iterator<Item = HistogramBucket> + '_ {
+    // base^index, computed directly rather than by repeated multiplication to avoid drift
+    let power = move |index: i64| 2f64.powf(index as f64 * 2f64.powi(-dp.scale));
+
+    let negative = indexed_counts(dp.negative.as_ref())
+        .rev()
+        .map(move |(index, count)| HistogramBucket { count, upper_bound: -power(index) });
+    let zero = (dp.zero_count > 0).then_some(HistogramBucket { count: dp.zero_count, upper_bound: dp.zero_threshold });
+    let positive = indexed_counts(dp.positive.as_ref())
+        .map(move |(index, count)| HistogramBucket { count, upper_bound: power(index + 1) });
+    negative.chain(zero).chain(positive)
+}
+
+/// How many buckets `exponential_buckets` yields for `dp`
+fn exponential_bucket_count(dp: &ExponentialHistogramDataPoint) -> usize {
+    let side = |buckets: Option<&Buckets>| buckets.map_or(0, |b| b.bucket_counts.len());
+    side(dp.negative.as_ref()) + usize::from(dp.zero_count > 0) + side(dp.positive.as_ref())
+}
+
+/// `(bucket index, count)` pairs of one side of an exponential histogram, by ascending index
+fn indexed_counts(buckets: Option<&Buckets>) -> impl DoubleEndedIterator<Item = (i64, u64)> + '_ {
+    let (offset, counts) = buckets.map_or((0, &[][..]), |b| (b.offset, b.bucket_counts.as_slice()));
+    counts.iter().enumerate().map(move |(i, count)| (offset as i64 + i as i64, *count))
+}
+
 /// Extract numeric value from OTLP NumberDataPoint value
 fn extract_number_value(
     value: &Option<opentelemetry_proto::tonic::metrics::v1::number_data_point::Value>,
 ) -> Option<MetricValue> {
     use opentelemetry_proto::tonic::metrics::v1::number_data_point::Value;
     
-    value.as_ref().and_then(|v| match v {
-        Value::AsInt(i) => Some(MetricValue::Int(*i)),
-        Value::AsDouble(d) => Some(MetricValue::Double(*d)),
+    value.as_ref().map(|v| match v {
+        Value::AsInt(i) => MetricValue::Int(*i),
+        Value::AsDouble(d) => MetricValue::Double(*d),
     })
 }
 
@@ -181,10 +269,12 @@ pub fn print_normalized_metrics(metrics: &[NormalizedMetric]) {
                 debug!("  {} [{}] = {:.2}", metric.name, metric.metric_type, d);
             }
             MetricValue::Histogram { count, sum, .. } => {
-                if let Some(s) = sum {
-                    debug!("  {} [Histogram] count={}, sum={:.2}", metric.name, count, s);
-                } else {
-                    debug!("  {} [Histogram] count={}", metric.name, count);
+                match (sum, metric.value.average()) {
+                    (Some(s), Some(avg)) => {
+                        debug!("  {} [Histogram] count={}, sum={:.2}, avg={:.2}", metric.name, count, s, avg)
+                    }
+                    (Some(s), None) => debug!("  {} [Histogram] count={}, sum={:.2}", metric.name, count, s),
+                    (None, _) => debug!("  {} [Histogram] count={}, sum unavailable (no average)", metric.name, count),
                 }
             }
             MetricValue::Summary { count, sum, .. } => {
@@ -194,3 +284,109 @@ pub fn print_normalized_metrics(metrics: &[NormalizedMetric]) {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::metrics::v1::{
+        metric, ExponentialHistogram, Histogram, HistogramDataPoint, Metric, ResourceMetrics,
+        ScopeMetrics, Summary, SummaryDataPoint, summary_data_point::ValueAtQuantile,
+    };
+
+    fn request(data: metric::Data) -> ExportMetricsServiceRequest {
+        ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                scope_metrics: vec![ScopeMetrics {
+                    metrics: vec![Metric { name: "huge".to_string(), data: Some(data), ..Default::default() }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn test_oversized_histogram_and_summary_truncated() {
+        let histogram = metric::Data::Histogram(Histogram {
+            data_points: vec![HistogramDataPoint {
+                count: 1_000_000,
+                bucket_counts: vec![1; 100_001],
+                explicit_bounds: (0..100_000).map(f64::from).collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        let normalized = normalize_metrics(request(histogram), 16);
+        let MetricValue::Histogram { count, buckets, .. } = &normalized[0].value else {
+            panic!("expected a histogram");
+        };
+        assert_eq!(buckets.len(), 16);
+        assert_eq!(buckets[15].upper_bound, 15.0);
+        // The data point's totals are kept as exported
+        assert_eq!(*count, 1_000_000);
+
+        let summary = metric::Data::Summary(Summary {
+            data_points: vec![SummaryDataPoint {
+                quantile_values: (0..50).map(|i| ValueAtQuantile { quantile: i as f64 / 50.0, value: 1.0 }).collect(),
+                ..Default::default()
+            }],
+        });
+        let normalized = normalize_metrics(request(summary), 16);
+        let MetricValue::Summary { quantiles, .. } = &normalized[0].value else {
+            panic!("expected a summary");
+        };
+        assert_eq!(quantiles.len(), 16);
+
+        let exponential = metric::Data::ExponentialHistogram(ExponentialHistogram {
+            data_points: vec![ExponentialHistogramDataPoint {
+                zero_count: 1,
+                positive: Some(Buckets { offset: 0, bucket_counts: vec![1; 100_000] }),
+                negative: Some(Buckets { offset: 0, bucket_counts: vec![2; 10] }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        let normalized = normalize_metrics(request(exponential), 16);
+        let MetricValue::Histogram { buckets, .. } = &normalized[0].value else {
+            panic!("expected a histogram");
+        };
+        // The lowest 16: all 10 negative, the zero bucket, then the first 5 positive
+        let counts: Vec<u64> = buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, [[2; 10].as_slice(), &[1], &[1; 5]].concat());
+        assert!(buckets.windows(2).all(|w| w[0].upper_bound <= w[1].upper_bound));
+    }
+
+    #[test]
+    fn test_exponential_histogram_materialized() {
+        // scale 1: base = 2^(2^-1) = sqrt(2)
+        let base = 2f64.powf(0.5);
+        let histogram = metric::Data::ExponentialHistogram(ExponentialHistogram {
+            data_points: vec![ExponentialHistogramDataPoint {
+                count: 10,
+                sum: Some(4.0),
+                scale: 1,
+                zero_count: 1,
+                positive: Some(Buckets { offset: -1, bucket_counts: vec![1, 2, 3] }),
+                negative: Some(Buckets { offset: 2, bucket_counts: vec![2, 1] }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        let normalized = normalize_metrics(request(histogram), 16);
+        assert_eq!(normalized[0].metric_type, "Histogram");
+        let MetricValue::Histogram { count, sum, buckets } = &normalized[0].value else {
+            panic!("expected a histogram");
+        };
+        assert_eq!((*count, *sum), (10, Some(4.0)));
+
+        let counts: Vec<u64> = buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, [1, 2, 1, 1, 2, 3]);
+        // Negative buckets 3 and 2 end at -base^3 and -base^2, positive buckets -1..=1
+        // (each spanning (base^(offset+i), base^(offset+i+1)]) at base^0..=base^2
+        let expected = [-base.powi(3), -base.powi(2), 0.0, base.powi(0), base.powi(1), base.powi(2)];
+        for (bucket, bound) in buckets.iter().zip(expected) {
+            assert!((bucket.upper_bound - bound).abs() < 1e-12, "{} != {}", bucket.upper_bound, bound);
+        }
+        assert!(buckets.windows(2).all(|w| w[0].upper_bound <= w[1].upper_bound));
+    }
+}
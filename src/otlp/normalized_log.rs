@@ -0,0 +1,125 @@
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::config::DebugConfig;
+use crate::types::{MetricValue, NormalizedMetric};
+
+/// One JSONL line: a normalized metric and when it was ingested
+#[derive(Serialize)]
+struct NormalizedLine<'a> {
+    received_at: u64,
+    #[serde(flatten)]
+    metric: &'a NormalizedMetric,
+    /// Mean of a histogram or summary; absent (not 0) when a histogram came without its sum
+    #[serde(skip_serializing_if = "Option::is_none")]
+    average: Option<f64>,
+}
+
+/// Append one ingest's normalized metrics to `debug.normalized_metrics_file` as JSONL
+///
+/// Once the file would exceed `normalized_metrics_max_bytes` it is rotated to
+/// `<file>.1` (replacing the previous rotation) and a fresh file is started.
+pub fn append_normalized_metrics(
+    config: &DebugConfig,
+    metrics: &[NormalizedMetric],
+    received_at: u64,
+) -> anyhow::Result<()> {
+    let Some(path) = config.normalized_metrics_file.as_deref().map(Path::new) else {
+        return Ok(());
+    };
+
+    let mut lines = Vec::new();
+    for metric in metrics {
+        let average = match metric.value {
+            MetricValue::Histogram { .. } | MetricValue::Summary { .. } => metric.value.average(),
+            MetricValue::Int(_) | MetricValue::Double(_) => None,
+        };
+        serde_json::to_writer(&mut lines, &NormalizedLine { received_at, metric, average })?;
+        lines.push(b'\n');
+    }
+
+    let current_len = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if current_len > 0 && current_len + lines.len() as u64 > config.normalized_metrics_max_bytes {
+        fs::rename(path, rotated_path(path))?;
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)?.write_all(&lines)?;
+    Ok(())
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gauge(name: &str, value: i64) -> NormalizedMetric {
+        NormalizedMetric {
+            name: name.to_string(),
+            metric_type: "Gauge".to_string(),
+            value: MetricValue::Int(value),
+            attributes: Default::default(),
+            resource_attributes: Default::default(),
+            scope_name: None,
+            scope_version: None,
+            time_unix_nano: None,
+            start_time_unix_nano: None,
+        }
+    }
+
+    #[test]
+    fn test_rotates_when_cap_exceeded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("normalized.jsonl");
+        let config = DebugConfig {
+            normalized_metrics_file: Some(path.to_string_lossy().into_owned()),
+            normalized_metrics_max_bytes: 500,
+            ..Default::default()
+        };
+
+        for i in 0..3 {
+            append_normalized_metrics(&config, &[gauge("das_sampled_chain_head", i)], 100 + i as u64).unwrap();
+        }
+
+        let current = fs::read_to_string(&path).unwrap();
+        let rotated = fs::read_to_string(dir.path().join("normalized.jsonl.1")).unwrap();
+        assert!(current.len() <= 500 && rotated.len() <= 500);
+        assert_eq!(rotated.lines().count(), 2);
+        // Nothing lost: every ingest is in one of the two files
+        let lines: Vec<&str> = rotated.lines().chain(current.lines()).collect();
+        assert_eq!(lines.len(), 3);
+        let last: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(last["received_at"], 102);
+    }
+
+    #[test]
+    fn test_histogram_average_logged_only_when_sum_known() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("normalized.jsonl");
+        let config = DebugConfig {
+            normalized_metrics_file: Some(path.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let histogram = |sum| NormalizedMetric {
+            metric_type: "Histogram".to_string(),
+            value: MetricValue::Histogram { count: 4, sum, buckets: Vec::new() },
+            ..gauge("das_sample_latency", 0)
+        };
+
+        append_normalized_metrics(&config, &[histogram(Some(10.0)), histogram(None), gauge("das_sampled_chain_head", 7)], 100).unwrap();
+
+        let lines: Vec<serde_json::Value> = fs::read_to_string(&path).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines[0]["average"], 2.5);
+        // No sum: no average, rather than a misleading 0
+        assert!(lines[1].get("average").is_none());
+        assert!(lines[2].get("average").is_none());
+    }
+}
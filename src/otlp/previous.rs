@@ -0,0 +1,104 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Last numeric value seen per metric name, bounded to `capacity` names
+///
+/// When a new name would exceed the cap, the least-recently-seen name is evicted.
+/// Pinned names (the configured DAS metrics) are never evicted, so a source emitting
+/// thousands of unique names can't push out the values we actually track.
+#[derive(Debug)]
+pub struct PreviousValues {
+    capacity: usize,
+    pinned: HashSet<String>,
+    entries: HashMap<String, (f64, u64)>,
+    /// Recency index: seen-counter -> name, oldest first
+    order: BTreeMap<u64, String>,
+    counter: u64,
+}
+
+impl PreviousValues {
+    pub fn new(capacity: usize, pinned: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            capacity,
+            pinned: pinned.into_iter().collect(),
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            counter: 0,
+        }
+    }
+
+    /// Record `value` for `name`, returning the value it replaces
+    pub fn record(&mut self, name: &str, value: f64) -> Option<f64> {
+        self.counter += 1;
+        let previous = match self.entries.get_mut(name) {
+            Some(entry) => {
+                self.order.remove(&entry.1);
+                Some(std::mem::replace(entry, (value, self.counter)).0)
+            }
+            None => {
+                self.entries.insert(name.to_string(), (value, self.counter));
+                None
+            }
+        };
+        self.order.insert(self.counter, name.to_string());
+
+        while self.entries.len() > self.capacity && self.evict_oldest() {}
+        previous
+    }
+
+    /// Evict the least-recently-seen unpinned name; false if only pinned names remain
+    fn evict_oldest(&mut self) -> bool {
+        let Some((&seen, _)) = self.order.iter().find(|(_, name)| !self.pinned.contains(*name)) else {
+            return false;
+        };
+        if let Some(name) = self.order.remove(&seen) {
+            self.entries.remove(&name);
+        }
+        true
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get(&self, name: &str) -> Option<f64> {
+        self.entries.get(name).map(|(value, _)| *value)
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exceeding_cap_evicts_oldest_but_keeps_pinned() {
+        let mut values = PreviousValues::new(3, ["das_sampled_chain_head".to_string()]);
+        values.record("das_sampled_chain_head", 100.0);
+        values.record("noise_a", 1.0);
+        values.record("noise_b", 2.0);
+        // Touch noise_a so noise_b becomes the least recently seen unpinned name
+        assert_eq!(values.record("noise_a", 3.0), Some(1.0));
+
+        for i in 0..10 {
+            values.record(&format!("explosion_{}", i), i as f64);
+        }
+
+        assert_eq!(values.len(), 3);
+        assert_eq!(values.get("das_sampled_chain_head"), Some(100.0));
+        assert_eq!(values.get("noise_a"), None);
+        assert_eq!(values.get("noise_b"), None);
+        assert_eq!(values.get("explosion_9"), Some(9.0));
+        assert_eq!(values.get("explosion_8"), Some(8.0));
+    }
+
+    #[test]
+    fn test_pinned_names_may_exceed_cap() {
+        let mut values = PreviousValues::new(1, ["head".to_string(), "headers".to_string()]);
+        values.record("head", 1.0);
+        values.record("headers", 2.0);
+        values.record("other", 3.0);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values.get("other"), None);
+    }
+}
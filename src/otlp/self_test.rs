@@ -0,0 +1,164 @@
+use axum::{extract::State, http::{HeaderMap, StatusCode}};
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::metrics::v1::{
+    metric::Data, number_data_point, Gauge, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics, Sum,
+};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use prost::Message;
+use std::sync::Arc;
+use crate::config::Config;
+use crate::types::AppState;
+use super::handle_metrics;
+
+/// Values the synthetic head/headers data points carry; distinct so a name read
+/// into the wrong field is caught
+const SELF_TEST_HEAD: i64 = 1_000_001;
+const SELF_TEST_HEADERS: i64 = 2_000_002;
+
+/// Push a synthetic OTLP export through `handle_metrics` against a throwaway state,
+/// once per configured head/headers metric name, and check both values come out
+///
+/// Catches metric names that no exporter can send (OTel instrument name syntax) and
+/// head/headers names that overlap, before any real traffic arrives.
+pub async fn run_self_test(config: &Config) -> anyhow::Result<()> {
+    let metrics = &config.metrics;
+    for name in metrics.head_metric.iter().chain(metrics.headers_metric.iter()) {
+        anyhow::ensure!(
+            is_valid_instrument_name(name),
+            "metric name {:?} is not a valid OTel instrument name, no exporter will send it",
+            name
+        );
+    }
+
+    let first_headers = metrics.headers_metric.iter().next();
+    let first_head = metrics.head_metric.iter().next();
+    for head in metrics.head_metric.iter() {
+        if let Some(headers) = first_headers {
+            probe(config, head, headers).await?;
+        }
+    }
+    for headers in metrics.headers_metric.iter() {
+        if let Some(head) = first_head {
+            probe(config, head, headers).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Ingest one export carrying `head_name` and `headers_name` and check where the values landed
+async fn probe(config: &Config, head_name: &str, headers_name: &str) -> anyhow::Result<()> {
+    // Nothing from the probe may reach the real data/ directory
+    let mut config = config.clone();
+    config.debug.dump_failed_requests_dir = None;
+    config.debug.normalized_metrics_file = None;
+    let state = AppState::new(Arc::new(config));
+
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", "application/x-protobuf".parse()?);
+    let body = synthetic_request(head_name, headers_name).encode_to_vec();
+    let (status, _) = handle_metrics(State(state.clone()), headers, body.into()).await;
+    anyhow::ensure!(status == StatusCode::OK, "ingest of the synthetic export returned {}", status);
+
+    let das = state.das_metrics.lock().unwrap();
+    anyhow::ensure!(
+        das.head == Some(SELF_TEST_HEAD),
+        "head read from {:?} as {:?}, expected {} (check metrics.head_metric / metrics.headers_metric)",
+        head_name,
+        das.head,
+        SELF_TEST_HEAD
+    );
+    anyhow::ensure!(
+        das.headers == Some(SELF_TEST_HEADERS),
+        "headers read from {:?} as {:?}, expected {} (check metrics.head_metric / metrics.headers_metric)",
+        headers_name,
+        das.headers,
+        SELF_TEST_HEADERS
+    );
+    Ok(())
+}
+
+/// Minimal export shaped like celestia-node's: head as a gauge, headers as a cumulative sum,
+/// under a resource naming the service so `metrics.resourceless_metrics = "reject"` keeps it
+fn synthetic_request(head_name: &str, headers_name: &str) -> ExportMetricsServiceRequest {
+    let point = |value| NumberDataPoint {
+        value: Some(number_data_point::Value::AsInt(value)),
+        ..Default::default()
+    };
+    let head = Metric {
+        name: head_name.to_string(),
+        data: Some(Data::Gauge(Gauge { data_points: vec![point(SELF_TEST_HEAD)] })),
+        ..Default::default()
+    };
+    let headers = Metric {
+        name: headers_name.to_string(),
+        data: Some(Data::Sum(Sum {
+            data_points: vec![point(SELF_TEST_HEADERS)],
+            is_monotonic: true,
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+    ExportMetricsServiceRequest {
+        resource_metrics: vec![ResourceMetrics {
+            resource: Some(Resource {
+                attributes: vec![KeyValue {
+                    key: "service.name".to_string(),
+                    value: Some(AnyValue { value: Some(any_value::Value::StringValue("da-reader-self-test".to_string())) }),
+                }],
+                ..Default::default()
+            }),
+            scope_metrics: vec![ScopeMetrics { metrics: vec![head, headers], ..Default::default() }],
+            ..Default::default()
+        }],
+    }
+}
+
+/// OTel instrument name syntax: a letter, then up to 254 of `[A-Za-z0-9_.-/]`
+fn is_valid_instrument_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.len() <= 255
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{MetricNames, ResourcelessPolicy};
+
+    fn names(json: &str) -> MetricNames {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_self_test_passes_with_shipped_names() {
+        let mut config = Config::for_tests();
+        run_self_test(&config).await.unwrap();
+
+        config.metrics.headers_metric = names(r#"["das_total_sampled_headers", "das_sampled_headers_total"]"#);
+        run_self_test(&config).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_self_test_passes_when_resourceless_metrics_rejected() {
+        let mut config = Config::for_tests();
+        config.metrics.resourceless_metrics = ResourcelessPolicy::Reject;
+        run_self_test(&config).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_self_test_fails_on_misconfigured_metric_name() {
+        // Trailing space: a typo no exporter can ever match
+        let mut config = Config::for_tests();
+        config.metrics.head_metric = names(r#""das_sampled_chain_head ""#);
+        let err = run_self_test(&config).await.unwrap_err();
+        assert!(err.to_string().contains("not a valid OTel instrument name"));
+
+        // Headers pointed at the head metric: both fields read the same value
+        let mut config = Config::for_tests();
+        config.metrics.headers_metric = names(r#""das_sampled_chain_head""#);
+        let err = run_self_test(&config).await.unwrap_err();
+        assert!(err.to_string().contains("headers_metric"), "{}", err);
+    }
+}
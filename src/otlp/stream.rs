@@ -0,0 +1,136 @@
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::metrics::v1::ResourceMetrics;
+use prost::bytes::Buf;
+use prost::encoding::{check_wire_type, decode_key, skip_field, DecodeContext, WireType};
+use prost::{DecodeError, Message};
+use crate::types::NormalizedMetric;
+use super::normalize_metrics;
+
+/// Field number of `ExportMetricsServiceRequest.resource_metrics`
+const RESOURCE_METRICS_TAG: u32 = 1;
+
+/// Decode a protobuf export request one `ResourceMetrics` at a time
+///
+/// Each resource is normalized and handed to `on_resource` before the next is decoded,
+/// so neither the full decoded request nor all normalized metrics are held at once, and
+/// the body is decoded only once. A later resource can still fail to decode after earlier
+/// ones were handed off: callers buffer what they need and apply it only on `Ok`, so a
+/// corrupt body never applies a partial export. Returns the number of resources decoded.
+pub fn for_each_resource(
+    mut body: &[u8],
+    max_buckets_per_point: usize,
+    mut on_resource: impl FnMut(Vec<NormalizedMetric>),
+) -> Result<usize, DecodeError> {
+    let mut resources = 0;
+    while body.has_remaining() {
+        let (tag, wire_type) = decode_key(&mut body)?;
+        if tag == RESOURCE_METRICS_TAG {
+            check_wire_type(WireType::LengthDelimited, wire_type)?;
+            let resource = ResourceMetrics::decode_length_delimited(&mut body)?;
+            let request = ExportMetricsServiceRequest { resource_metrics: vec![resource] };
+            on_resource(normalize_metrics(request, max_buckets_per_point));
+            resources += 1;
+        } else {
+            skip_field(wire_type, tag, &mut body, DecodeContext::default())?;
+        }
+    }
+    Ok(resources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::metrics::v1::{
+        metric, number_data_point, Gauge, Metric, NumberDataPoint, ScopeMetrics,
+    };
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use std::sync::Arc;
+    use crate::config::Config;
+    use crate::otlp::handlers::ingest_payload_with;
+    use crate::types::AppState;
+
+    /// One resource per node, each exporting `per_resource` gauges
+    fn multi_resource_request(resources: usize, per_resource: usize) -> ExportMetricsServiceRequest {
+        let resource_metrics = (0..resources)
+            .map(|r| ResourceMetrics {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue {
+                        key: "service.instance.id".to_string(),
+                        value: Some(AnyValue { value: Some(any_value::Value::StringValue(format!("node-{}", r))) }),
+                    }],
+                    ..Default::default()
+                }),
+                scope_metrics: vec![ScopeMetrics {
+                    metrics: (0..per_resource)
+                        .map(|m| Metric {
+                            name: format!("node_metric_{}", m),
+                            data: Some(metric::Data::Gauge(Gauge {
+                                data_points: vec![NumberDataPoint {
+                                    value: Some(number_data_point::Value::AsInt((r * per_resource + m) as i64)),
+                                    ..Default::default()
+                                }],
+                            })),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+            .collect();
+        ExportMetricsServiceRequest { resource_metrics }
+    }
+
+    #[test]
+    fn test_each_resource_handed_off_separately() {
+        let body = multi_resource_request(50, 4).encode_to_vec();
+        let mut batch_sizes = Vec::new();
+        assert_eq!(for_each_resource(&body, usize::MAX, |metrics| batch_sizes.push(metrics.len())).unwrap(), 50);
+        assert_eq!(batch_sizes, vec![4; 50]);
+    }
+
+    #[test]
+    fn test_large_multi_resource_request_ingested_without_retaining_metrics() {
+        let mut req = multi_resource_request(2000, 10);
+        // The DAS node is the last resource in the export
+        let last = req.resource_metrics.last_mut().unwrap();
+        last.scope_metrics[0].metrics[0].name = "das_sampled_chain_head".to_string();
+        let body = req.encode_to_vec();
+        assert!(body.len() > 500_000);
+
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let outcome = ingest_payload_with(&state, &body, false, false);
+        assert!(outcome.das_updated);
+        assert_eq!(outcome.metric_count, 20_000);
+        assert_eq!(outcome.normalized.map(|n| n.len()), Some(0));
+        assert_eq!(state.das_metrics.lock().unwrap().head, Some(19_990));
+
+        // Retaining gives the same metrics as a whole-request decode
+        let retained = ingest_payload_with(&state, &body, false, true).normalized.unwrap();
+        assert_eq!(retained.len(), normalize_metrics(req, usize::MAX).len());
+    }
+
+    #[test]
+    fn test_truncated_body_is_an_error() {
+        let body = multi_resource_request(3, 2).encode_to_vec();
+        let mut seen = 0;
+        assert!(for_each_resource(&body[..body.len() - 5], usize::MAX, |_| seen += 1).is_err());
+        // Resources before the corrupt one were handed off; the error tells callers to drop them
+        assert_eq!(seen, 2);
+    }
+
+    #[test]
+    fn test_corrupt_export_leaves_state_untouched() {
+        let mut req = multi_resource_request(3, 2);
+        req.resource_metrics[0].scope_metrics[0].metrics[0].name = "das_sampled_chain_head".to_string();
+        let body = req.encode_to_vec();
+
+        let mut config = Config::for_tests();
+        config.server.decode_order = crate::config::DecodeOrder::Strict;
+        let state = AppState::new(Arc::new(config));
+        let outcome = ingest_payload_with(&state, &body[..body.len() - 5], false, false);
+        assert!(!outcome.das_updated);
+        assert_eq!(state.das_metrics.lock().unwrap().head, None);
+    }
+}
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use crate::types::{Batch, TimeWindow};
+
+/// Domain separation so a leaf can never be passed off as an inner node (or vice versa)
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// What the accumulator commits to for one batch: its window and bitmap hash
+///
+/// Binding the window means a proof shows which window a bitmap hash was attested for,
+/// not only that it was attested at some point.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccumulatorLeaf {
+    pub window: TimeWindow,
+    pub bitmap_hash: String,
+}
+
+impl AccumulatorLeaf {
+    pub fn of(batch: &Batch) -> Self {
+        Self { window: batch.window.clone(), bitmap_hash: batch.bitmap_hash.clone() }
+    }
+
+    /// `blake3(0x00 ‖ start ‖ end ‖ bitmap_hash)`, window bounds as big-endian u64
+    fn hash(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&[LEAF_PREFIX]);
+        hasher.update(&self.window.start.to_be_bytes());
+        hasher.update(&self.window.end.to_be_bytes());
+        hasher.update(self.bitmap_hash.as_bytes());
+        *hasher.finalize().as_bytes()
+    }
+}
+
+/// Append-only Merkle mountain range over every batch's leaf, oldest first
+///
+/// Only the frontier is kept: the roots ("peaks") of the perfect subtrees covering all
+/// leaves so far, one per set bit of the leaf count, so state and the work per window
+/// stay O(log n). The root bags the peaks right to left. It is posted with each window's
+/// batch, and any earlier window can be proven against the latest root with
+/// `prove_inclusion` over the leaf history.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MerkleAccumulator {
+    leaf_count: usize,
+    /// Peak hashes, largest (oldest) subtree first
+    peaks: Vec<[u8; 32]>,
+}
+
+/// Path from one leaf up to the root of the accumulator it was proven against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    /// Position of the leaf (0 = first batch)
+    pub index: usize,
+    /// Leaves in the accumulator the proof was made for
+    pub leaf_count: usize,
+    /// Sibling hashes from the leaf up to its peak, as hex; which side each sits on
+    /// follows from `index`
+    pub siblings: Vec<String>,
+    /// All peaks of that accumulator, as hex, largest first
+    pub peaks: Vec<String>,
+}
+
+impl MerkleAccumulator {
+    /// Frontier over `leaves`, oldest first
+    pub fn from_leaves<'a>(leaves: impl IntoIterator<Item = &'a AccumulatorLeaf>) -> Self {
+        let mut accumulator = Self::default();
+        for leaf in leaves {
+            accumulator.add(leaf);
+        }
+        accumulator
+    }
+
+    /// Append a batch's leaf, returning its index
+    pub fn add(&mut self, leaf: &AccumulatorLeaf) -> usize {
+        let mut node = leaf.hash();
+        // Each trailing set bit of the count is a peak of the height being carried up
+        let mut count = self.leaf_count;
+        while count & 1 == 1 {
+            let left = self.peaks.pop().expect("one peak per set bit");
+            node = node_hash(&left, &node);
+            count >>= 1;
+        }
+        self.peaks.push(node);
+        self.leaf_count += 1;
+        self.leaf_count - 1
+    }
+
+    /// Current root as hex, `None` while empty
+    pub fn root(&self) -> Option<String> {
+        bag(&self.peaks).map(hex::encode)
+    }
+}
+
+/// Proof that `leaves[index]` is in the accumulator over all of `leaves`
+pub fn prove_inclusion(leaves: &[AccumulatorLeaf], index: usize) -> Option<InclusionProof> {
+    let (peak, position, _) = locate(leaves.len(), index)?;
+    let hashes: Vec<[u8; 32]> = leaves.iter().map(AccumulatorLeaf::hash).collect();
+    let mut peaks = Vec::new();
+    let mut siblings = Vec::new();
+    let mut offset = 0;
+    for size in mountain_sizes(leaves.len()) {
+        let mut level = hashes[offset..offset + size].to_vec();
+        let mut position = (peaks.len() == peak).then_some(position);
+        while level.len() > 1 {
+            if let Some(pos) = position {
+                siblings.push(hex::encode(level[pos ^ 1]));
+                position = Some(pos / 2);
+            }
+            level = level.chunks(2).map(|pair| node_hash(&pair[0], &pair[1])).collect();
+        }
+        peaks.push(hex::encode(level[0]));
+        offset += size;
+    }
+    Some(InclusionProof { index, leaf_count: leaves.len(), siblings, peaks })
+}
+
+impl InclusionProof {
+    /// Whether `leaf` is leaf `index` of the accumulator with root `root` (hex)
+    pub fn verify(&self, leaf: &AccumulatorLeaf, root: &str) -> bool {
+        let Some((peak, mut position, height)) = locate(self.leaf_count, self.index) else {
+            return false;
+        };
+        if self.siblings.len() != height || self.peaks.len() != self.leaf_count.count_ones() as usize {
+            return false;
+        }
+        let mut node = leaf.hash();
+        for sibling in &self.siblings {
+            let Some(sibling) = decode(sibling) else {
+                return false;
+            };
+            node = if position & 1 == 1 { node_hash(&sibling, &node) } else { node_hash(&node, &sibling) };
+            position /= 2;
+        }
+        let mut peaks = Vec::with_capacity(self.peaks.len());
+        for (i, hash) in self.peaks.iter().enumerate() {
+            match decode(hash) {
+                Some(_) if i == peak => peaks.push(node),
+                Some(hash) => peaks.push(hash),
+                None => return false,
+            }
+        }
+        bag(&peaks).is_some_and(|computed| hex::encode(computed) == root)
+    }
+}
+
+/// Sizes of the perfect subtrees covering `leaf_count` leaves, largest first
+fn mountain_sizes(leaf_count: usize) -> impl Iterator<Item = usize> {
+    (0..usize::BITS).rev().map(|bit| 1usize << bit).filter(move |size| leaf_count & size != 0)
+}
+
+/// Which peak leaf `index` sits under, its position within that subtree and the
+/// subtree's height
+fn locate(leaf_count: usize, index: usize) -> Option<(usize, usize, usize)> {
+    let mut offset = 0;
+    for (peak, size) in mountain_sizes(leaf_count).enumerate() {
+        if index < offset + size {
+            return Some((peak, index - offset, size.trailing_zeros() as usize));
+        }
+        offset += size;
+    }
+    None
+}
+
+/// Fold peaks into one root, right to left: `P(p0, P(p1, ... P(pk-1, pk)))`
+fn bag(peaks: &[[u8; 32]]) -> Option<[u8; 32]> {
+    peaks.iter().rev().copied().reduce(|right, left| node_hash(&left, &right))
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    *hasher.finalize().as_bytes()
+}
+
+fn decode(hash: &str) -> Option<[u8; 32]> {
+    hex::decode(hash).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(i: u64) -> AccumulatorLeaf {
+        AccumulatorLeaf {
+            window: TimeWindow { start: i * 600, end: (i + 1) * 600 },
+            bitmap_hash: blake3::hash(&[i as u8]).to_hex().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_every_window_provable_against_latest_root() {
+        let mut accumulator = MerkleAccumulator::default();
+        assert_eq!(accumulator.root(), None);
+
+        let leaves: Vec<AccumulatorLeaf> = (0..7).map(leaf).collect();
+        let mut roots = Vec::new();
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert_eq!(accumulator.add(leaf), i);
+            roots.push(accumulator.root().unwrap());
+            // The frontier stays one peak per set bit of the count
+            assert_eq!(accumulator.peaks.len(), (i + 1).count_ones() as usize);
+        }
+        // Each window moves the root
+        roots.dedup();
+        assert_eq!(roots.len(), 7);
+
+        let root = accumulator.root().unwrap();
+        assert_eq!(MerkleAccumulator::from_leaves(&leaves), accumulator);
+        for i in 0..7 {
+            let proof = prove_inclusion(&leaves, i).unwrap();
+            assert!(proof.siblings.len() <= 2, "proof of {} has {} steps", i, proof.siblings.len());
+            assert!(proof.verify(&leaves[i], &root));
+            // ...and only for that window's bitmap hash
+            assert!(!proof.verify(&leaf(i as u64 + 1), &root));
+        }
+        assert!(prove_inclusion(&leaves, 7).is_none());
+    }
+
+    #[test]
+    fn test_leaf_binds_window() {
+        let leaves: Vec<AccumulatorLeaf> = (0..4).map(leaf).collect();
+        let root = MerkleAccumulator::from_leaves(&leaves).root().unwrap();
+        let proof = prove_inclusion(&leaves, 2).unwrap();
+        assert!(proof.verify(&leaves[2], &root));
+
+        // Same bitmap hash, claimed for another window
+        let moved = AccumulatorLeaf { window: TimeWindow { start: 0, end: 600 }, ..leaves[2].clone() };
+        assert!(!proof.verify(&moved, &root));
+        // The proof doesn't carry over to another position either
+        let shifted = InclusionProof { index: 3, ..proof };
+        assert!(!shifted.verify(&leaves[2], &root));
+    }
+
+    #[test]
+    fn test_old_proof_fails_against_stale_root() {
+        let leaves: Vec<AccumulatorLeaf> = (0..3).map(leaf).collect();
+        let old_root = MerkleAccumulator::from_leaves(&leaves[..2]).root().unwrap();
+        let root = MerkleAccumulator::from_leaves(&leaves).root().unwrap();
+
+        let proof = prove_inclusion(&leaves, 1).unwrap();
+        assert_eq!(proof.leaf_count, 3);
+        assert!(proof.verify(&leaves[1], &root));
+        assert!(!proof.verify(&leaves[1], &old_root));
+    }
+}
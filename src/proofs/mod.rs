@@ -0,0 +1,140 @@
+// Proof-related commitments over batch bitmaps
+//
+// The batch `bitmap_hash` is computed with the configured `proofs.hash_algo`:
+// - blake3: fast, default
+// - poseidon: SNARK-friendly commitment over BN254 field elements (circomlib parameters),
+//   far cheaper to recompute inside a ZK circuit
+// - sha256: for verifiers (e.g. EVM contracts) with a native sha256
+//
+// While migrating between algorithms, `proofs.hash_algos` adds further hashes of the same
+// bitmap to the batch (`bitmap_hashes`), so verifiers can check whichever they support.
+//
+// Each batch's window and bitmap hash are also folded into a Merkle mountain range (see
+// `accumulator`) whose root is posted each window, for logarithmic proofs over the whole
+// history; only its O(log n) frontier is kept in memory, the leaves are logged on disk.
+
+mod accumulator;
+
+pub use accumulator::{prove_inclusion, AccumulatorLeaf, InclusionProof, MerkleAccumulator};
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use light_poseidon::{Poseidon, PoseidonHasher};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+
+/// Bits packed into each field element (31 bytes, safely below the BN254 modulus)
+const BITS_PER_ELEMENT: usize = 248;
+
+/// Hash algorithm used for the batch bitmap commitment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgo {
+    #[default]
+    Blake3,
+    Poseidon,
+    Sha256,
+}
+
+/// Hash a bitmap (one byte per sample, 1 = ok) with the given algorithm, as hex
+pub fn hash_bitmap(bitmap: &[u8], algo: HashAlgo) -> String {
+    match algo {
+        HashAlgo::Blake3 => blake3::hash(bitmap).to_hex().to_string(),
+        HashAlgo::Poseidon => hex::encode(poseidon_bitmap_commitment(bitmap).into_bigint().to_bytes_be()),
+        HashAlgo::Sha256 => hex::encode(Sha256::digest(bitmap)),
+    }
+}
+
+/// Poseidon commitment over the bitmap
+///
+/// Sample bits are packed MSB-first into 248-bit field elements, then absorbed in a
+/// chain starting from the sample count: `acc = P(n)`, `acc = P(acc, e_i)` for each
+/// element. Seeding with `n` binds the length, so trailing failed samples change the hash.
+pub fn poseidon_bitmap_commitment(bitmap: &[u8]) -> Fr {
+    let mut single = Poseidon::<Fr>::new_circom(1).expect("circom parameters exist for 1 input");
+    let mut pair = Poseidon::<Fr>::new_circom(2).expect("circom parameters exist for 2 inputs");
+
+    let mut acc = single
+        .hash(&[Fr::from(bitmap.len() as u64)])
+        .expect("input count matches parameters");
+
+    for element in pack_bits(bitmap) {
+        acc = pair.hash(&[acc, element]).expect("input count matches parameters");
+    }
+
+    acc
+}
+
+/// Pack one-bit-per-byte samples into field elements, MSB-first
+fn pack_bits(bitmap: &[u8]) -> Vec<Fr> {
+    bitmap
+        .chunks(BITS_PER_ELEMENT)
+        .map(|chunk| {
+            let mut bytes = vec![0u8; BITS_PER_ELEMENT / 8];
+            for (i, &bit) in chunk.iter().enumerate() {
+                if bit != 0 {
+                    bytes[i / 8] |= 0x80 >> (i % 8);
+                }
+            }
+            Fr::from_be_bytes_mod_order(&bytes)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poseidon_matches_circomlib_vector() {
+        // Reference value from circomlib's poseidon([1, 2])
+        let mut poseidon = Poseidon::<Fr>::new_circom(2).unwrap();
+        let hash = poseidon.hash(&[Fr::from(1u64), Fr::from(2u64)]).unwrap();
+        assert_eq!(
+            hex::encode(hash.into_bigint().to_bytes_be()),
+            "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+        );
+    }
+
+    #[test]
+    fn test_poseidon_bitmap_vector() {
+        let bitmap = [1, 1, 0, 1];
+
+        // Recompute by hand: P(4), then absorb 0b1101 packed into the top bits of 31 bytes
+        let mut packed = [0u8; 31];
+        packed[0] = 0b1101_0000;
+        let mut single = Poseidon::<Fr>::new_circom(1).unwrap();
+        let mut pair = Poseidon::<Fr>::new_circom(2).unwrap();
+        let seed = single.hash(&[Fr::from(4u64)]).unwrap();
+        let expected = pair.hash(&[seed, Fr::from_be_bytes_mod_order(&packed)]).unwrap();
+
+        assert_eq!(poseidon_bitmap_commitment(&bitmap), expected);
+        assert_eq!(
+            hash_bitmap(&bitmap, HashAlgo::Poseidon),
+            "2f5fb0f023adfd112da87cff8336357de563910dd81cee3942cffd2d1c2942c3"
+        );
+    }
+
+    #[test]
+    fn test_poseidon_binds_length_and_content() {
+        let base = hash_bitmap(&[1, 1, 0, 1], HashAlgo::Poseidon);
+        assert_ne!(base, hash_bitmap(&[1, 1, 0, 1, 0], HashAlgo::Poseidon));
+        assert_ne!(base, hash_bitmap(&[1, 1, 1, 1], HashAlgo::Poseidon));
+        assert_eq!(base.len(), 64);
+    }
+
+    #[test]
+    fn test_sha256_vector() {
+        // sha256 of the bytes 01 00 01
+        assert_eq!(
+            hash_bitmap(&[1, 0, 1], HashAlgo::Sha256),
+            "85f90dfea1d8027e1463e5ca971a250110a20df0119d204a74220bc63516d15b"
+        );
+    }
+
+    #[test]
+    fn test_blake3_unchanged() {
+        let bitmap = [1, 0, 1];
+        assert_eq!(hash_bitmap(&bitmap, HashAlgo::Blake3), blake3::hash(&bitmap).to_hex().to_string());
+    }
+}
@@ -0,0 +1,130 @@
+// HTTP server for the OTLP listener and API routes
+//
+// `axum::serve` doesn't expose socket options, so connections are accepted here and
+// served with hyper directly:
+// - TCP keepalive probes detect peers that vanished without a FIN
+// - connections that send no request headers for `idle_timeout_secs` are closed,
+//   so a silent collector can't hold a file descriptor forever (before the first
+//   request this is enforced here; between keep-alive requests by hyper's HTTP/1
+//   header read timeout)
+
+use axum::Router;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use socket2::{SockRef, TcpKeepalive};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{debug, warn};
+use crate::config::ServerConfig;
+
+/// Accept connections on `listener` and serve `app` until the process exits
+pub async fn serve(listener: TcpListener, app: Router, cfg: &ServerConfig) -> anyhow::Result<()> {
+    let keepalive = TcpKeepalive::new()
+        .with_time(Duration::from_secs(cfg.tcp_keepalive_secs))
+        .with_interval(Duration::from_secs(cfg.tcp_keepalive_secs));
+    let idle_timeout = Duration::from_secs(cfg.idle_timeout_secs);
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                // e.g. EMFILE: back off instead of spinning
+                warn!("Failed to accept connection: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        if let Err(e) = SockRef::from(&stream).set_tcp_keepalive(&keepalive) {
+            debug!("Failed to enable TCP keepalive for {}: {}", peer, e);
+        }
+
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            // Protocol detection waits for the first bytes without a timer, so bound it here
+            let mut first = [0u8; 1];
+            if !matches!(tokio::time::timeout(idle_timeout, stream.peek(&mut first)).await, Ok(Ok(1..))) {
+                debug!("Closing connection from {} (no request within {:?})", peer, idle_timeout);
+                return;
+            }
+
+            let mut builder = auto::Builder::new(TokioExecutor::new());
+            builder
+                .http1()
+                .timer(TokioTimer::new())
+                .header_read_timeout(idle_timeout);
+
+            if let Err(e) = builder.serve_connection(TokioIo::new(stream), service).await {
+                debug!("Connection from {} closed: {}", peer, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    async fn spawn_server(idle_timeout_secs: u64) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let cfg = ServerConfig { idle_timeout_secs, ..Default::default() };
+        let app = Router::new().route("/", get(|| async { "ok" }));
+        tokio::spawn(async move { serve(listener, app, &cfg).await });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_idle_connection_is_closed() {
+        let addr = spawn_server(1).await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        // Send nothing: the server should hang up after the idle timeout (read returns EOF)
+        let mut buf = [0u8; 64];
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+            .await
+            .expect("server did not close the idle connection");
+        assert_eq!(read.unwrap_or(0), 0);
+    }
+
+    #[tokio::test]
+    async fn test_idle_keepalive_connection_is_closed() {
+        let addr = spawn_server(1).await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+
+        // Read the keep-alive response, then expect EOF once the connection sits idle
+        let mut response = Vec::new();
+        let mut buf = [0u8; 256];
+        let closed = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => response.extend_from_slice(&buf[..n]),
+                }
+            }
+        })
+        .await;
+        assert!(closed.is_ok(), "server did not close the idle keep-alive connection");
+        assert!(String::from_utf8_lossy(&response).starts_with("HTTP/1.1 200"));
+    }
+
+    #[tokio::test]
+    async fn test_requests_still_served() {
+        let addr = spawn_server(5).await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("ok"));
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::types::{Batch, Sample, TimeWindow};
+
+/// Current data directory layout, recorded in `LAYOUT_VERSION`
+///
+/// - v0: flat `samples.json`, `batch.json`, `bitmap.hex` only
+/// - v1: adds the `LAYOUT_VERSION` marker and one file per batch under `batches/`
+pub const LAYOUT_VERSION: u32 = 1;
+
+const VERSION_FILE: &str = "LAYOUT_VERSION";
+const LEGACY_FILES: [&str; 3] = ["samples.json", "batch.json", "bitmap.hex"];
+/// Where the original v0 files are copied before migrating
+const LEGACY_BACKUP_DIR: &str = "legacy-v0";
+
+/// Path of a batch's file in the per-batch archive
+pub fn batch_archive_path(data_dir: &Path, window: &TimeWindow) -> PathBuf {
+    data_dir.join("batches").join(format!("{}-{}.json", window.start, window.end))
+}
+
+/// Migrate a v0 data directory to the current layout, once
+///
+/// Originals are copied to `legacy-v0/` first and left in place. Returns `true` if a
+/// migration ran; directories that are already versioned or hold no legacy files are
+/// only stamped with the current version.
+pub fn migrate_data_dir(data_dir: &Path) -> anyhow::Result<bool> {
+    let version_path = data_dir.join(VERSION_FILE);
+    if version_path.exists() {
+        return Ok(false);
+    }
+
+    let legacy: Vec<&str> = LEGACY_FILES
+        .into_iter()
+        .filter(|name| data_dir.join(name).is_file())
+        .collect();
+
+    if !legacy.is_empty() {
+        let backup_dir = data_dir.join(LEGACY_BACKUP_DIR);
+        fs::create_dir_all(&backup_dir)?;
+        for name in &legacy {
+            fs::copy(data_dir.join(name), backup_dir.join(name))?;
+        }
+
+        // Fail before stamping the version if the legacy samples can't be read
+        if let Ok(content) = fs::read_to_string(data_dir.join("samples.json")) {
+            serde_json::from_str::<Vec<Sample>>(&content)
+                .map_err(|e| anyhow::anyhow!("legacy samples.json is unreadable: {}", e))?;
+        }
+
+        if let Ok(content) = fs::read_to_string(data_dir.join("batch.json")) {
+            let batch: Batch = serde_json::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("legacy batch.json is unreadable: {}", e))?;
+            let path = batch_archive_path(data_dir, &batch.window);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, content)?;
+        }
+    }
+
+    fs::write(&version_path, LAYOUT_VERSION.to_string())?;
+    Ok(!legacy.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY_BATCH: &str = r#"{"n":2,"good":1,"threshold":2,"bitmap_hash":"abc",
+        "window":{"start":1729785600,"end":1729785630}}"#;
+    const LEGACY_SAMPLES: &str = r#"[{"timestamp":1729785600,"head":10,"headers":5,"ok":true,"reason":"first sample"},
+        {"timestamp":1729785630,"head":10,"headers":5,"ok":false,"reason":"head stuck at 10"}]"#;
+
+    #[test]
+    fn test_legacy_data_dir_migrated_to_current_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path();
+        fs::write(data.join("samples.json"), LEGACY_SAMPLES).unwrap();
+        fs::write(data.join("batch.json"), LEGACY_BATCH).unwrap();
+        fs::write(data.join("bitmap.hex"), "0100").unwrap();
+
+        assert!(migrate_data_dir(data).unwrap());
+
+        assert_eq!(fs::read_to_string(data.join(VERSION_FILE)).unwrap(), "1");
+        let archived = data.join("batches").join("1729785600-1729785630.json");
+        let batch: Batch = serde_json::from_str(&fs::read_to_string(archived).unwrap()).unwrap();
+        assert_eq!((batch.n, batch.good), (2, 1));
+        for name in LEGACY_FILES {
+            assert!(data.join(LEGACY_BACKUP_DIR).join(name).is_file());
+            assert!(data.join(name).is_file());
+        }
+
+        // Runs once
+        assert!(!migrate_data_dir(data).unwrap());
+    }
+
+    #[test]
+    fn test_fresh_data_dir_only_stamped() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!migrate_data_dir(dir.path()).unwrap());
+        assert!(dir.path().join(VERSION_FILE).is_file());
+        assert!(!dir.path().join(LEGACY_BACKUP_DIR).exists());
+    }
+
+    #[test]
+    fn test_unreadable_legacy_batch_leaves_dir_unversioned() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("batch.json"), "not json").unwrap();
+        assert!(migrate_data_dir(dir.path()).is_err());
+        assert!(!dir.path().join(VERSION_FILE).exists());
+    }
+}
@@ -1,24 +1,535 @@
+mod posted_windows;
+mod migration;
+mod proofs;
+mod sqlite;
+
+pub use posted_windows::{PostedWindows, POSTED_WINDOWS_PATH};
+pub use proofs::{load_proof, save_proof, ProofPublicInputs};
+pub use migration::{migrate_data_dir, LAYOUT_VERSION};
+pub use sqlite::{init_db, insert_sample, load_samples_db, SAMPLES_DB_PATH};
+#[cfg(test)]
+pub use sqlite::init_db_at;
+
+use std::ffi::OsString;
 use std::fs;
-use crate::types::{Sample, Batch};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+use crate::config::StorageConfig;
+use std::collections::BTreeMap;
+use crate::types::{Sample, SampleSummary, Batch};
+use crate::da::{ReplicaCursor, SampleChain};
+use crate::proofs::AccumulatorLeaf;
+
+/// Path of the persisted sample history
+pub const SAMPLES_PATH: &str = "data/samples.json";
+/// Path of the downsampled history (`storage.downsample_after_secs`)
+pub const SUMMARIES_PATH: &str = "data/sample_summaries.json";
+/// Path of the head of each per-sample DA layer's chain, by layer name
+pub const SAMPLE_CHAINS_PATH: &str = "data/sample_chains.json";
+/// Path of the Merkle accumulator's leaves, one JSON line per batch, oldest first
+pub const ACCUMULATOR_LEAVES_PATH: &str = "data/accumulator_leaves.jsonl";
+/// Path of a replica's read position (`mode = "replica"`)
+pub const REPLICA_CURSOR_PATH: &str = "data/replica_cursor.json";
+
+/// Whether a write failed because free space was below `storage.min_free_bytes`
+pub fn is_low_space(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<io::Error>())
+        .any(|e| e.kind() == ErrorKind::StorageFull)
+}
 
 /// Save samples to file
-pub fn save_samples(samples: &[Sample]) -> anyhow::Result<()> {
+pub fn save_samples(samples: &[Sample], storage: &StorageConfig) -> anyhow::Result<()> {
+    save_samples_to(Path::new(SAMPLES_PATH), samples, storage)
+}
+
+/// Save samples to the given file
+pub fn save_samples_to(path: &Path, samples: &[Sample], storage: &StorageConfig) -> anyhow::Result<()> {
     let json = serde_json::to_string_pretty(samples)?;
-    fs::write("data/samples.json", json)?;
+    write_atomic(path, json.as_bytes(), storage)?;
     Ok(())
 }
 
-/// Save batch to file
-pub fn save_batch(batch: &Batch) -> anyhow::Result<()> {
+/// Downsampled buckets as saved, with the horizon samples were compacted up to
+#[derive(Serialize)]
+struct SummariesFile<'a> {
+    compacted_before: u64,
+    buckets: Vec<&'a SampleSummary>,
+}
+
+/// On-disk forms: with the compaction watermark, or the bare bucket list written before it
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredSummaries {
+    WithWatermark { compacted_before: u64, buckets: Vec<SampleSummary> },
+    Legacy(Vec<SampleSummary>),
+}
+
+/// Save downsampled buckets, oldest first, and `compacted_before`: samples taken before it
+/// are already counted in them
+pub fn save_summaries(
+    path: &Path,
+    summaries: &BTreeMap<u64, SampleSummary>,
+    compacted_before: u64,
+    storage: &StorageConfig,
+) -> anyhow::Result<()> {
+    let file = SummariesFile { compacted_before, buckets: summaries.values().collect() };
+    let json = serde_json::to_string_pretty(&file)?;
+    write_atomic(path, json.as_bytes(), storage)?;
+    Ok(())
+}
+
+/// Load downsampled buckets and the compaction watermark saved by `save_summaries`, empty
+/// (and 0) if there are none yet
+///
+/// Files from before the watermark was saved use the end of their last bucket, so no
+/// sample they may already count is restored and counted again.
+pub fn load_summaries(path: &Path) -> anyhow::Result<(BTreeMap<u64, SampleSummary>, u64)> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok((BTreeMap::new(), 0)),
+        Err(e) => return Err(e.into()),
+    };
+    let (buckets, compacted_before) = match serde_json::from_str(&content)? {
+        StoredSummaries::WithWatermark { compacted_before, buckets } => (buckets, compacted_before),
+        StoredSummaries::Legacy(buckets) => {
+            let end = buckets.iter().map(|bucket| bucket.start + bucket.secs).max().unwrap_or(0);
+            (buckets, end)
+        }
+    };
+    Ok((buckets.into_iter().map(|bucket| (bucket.start, bucket)).collect(), compacted_before))
+}
+
+/// Load the persisted sample history, empty if there is none yet
+pub fn load_samples() -> anyhow::Result<Vec<Sample>> {
+    match load_samples_from(Path::new(SAMPLES_PATH)) {
+        Err(e) if e.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == ErrorKind::NotFound) => Ok(Vec::new()),
+        loaded => loaded,
+    }
+}
+
+/// Load samples from file
+pub fn load_samples_from(path: &Path) -> anyhow::Result<Vec<Sample>> {
+    let content = fs::read_to_string(path)?;
+    let samples: Vec<Sample> = serde_json::from_str(&content)?;
+    Ok(samples)
+}
+
+/// Save batch as the latest batch and into the per-batch archive
+pub fn save_batch(batch: &Batch, storage: &StorageConfig) -> anyhow::Result<()> {
     let json = serde_json::to_string_pretty(batch)?;
-    fs::write("data/batch.json", json)?;
+    let archive_path = migration::batch_archive_path(Path::new("data"), &batch.window);
+    fs::create_dir_all(archive_path.parent().unwrap())?;
+    write_atomic(&archive_path, json.as_bytes(), storage)?;
+    write_atomic(Path::new("data/batch.json"), json.as_bytes(), storage)?;
+    Ok(())
+}
+
+/// Record the DA commitment of a posted batch in its archive entry, and in data/batch.json
+/// while that is still the same window
+///
+/// Batches retried from the pending queue may be older than the latest batch, whose file
+/// is then left alone.
+pub fn record_batch_commitment(batch: &Batch, commitment: &str, storage: &StorageConfig) -> anyhow::Result<()> {
+    record_batch_commitment_in(Path::new("data"), batch, commitment, storage)
+}
+
+fn record_batch_commitment_in(data_dir: &Path, batch: &Batch, commitment: &str, storage: &StorageConfig) -> anyhow::Result<()> {
+    let posted = Batch { da_commitment: Some(commitment.to_string()), ..batch.clone() };
+    let json = serde_json::to_string_pretty(&posted)?;
+    let archive_path = migration::batch_archive_path(data_dir, &batch.window);
+    fs::create_dir_all(archive_path.parent().unwrap())?;
+    write_atomic(&archive_path, json.as_bytes(), storage)?;
+
+    let latest_path = data_dir.join("batch.json");
+    let latest: Option<Batch> = fs::read_to_string(&latest_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok());
+    if latest.is_some_and(|latest| latest.window == batch.window) {
+        write_atomic(&latest_path, json.as_bytes(), storage)?;
+    }
+    Ok(())
+}
+
+/// Append accumulator leaves to the leaf log, in order
+///
+/// An append rather than a rewrite, so each window costs the same however long the
+/// history is. Skipped like any other write when free space is low.
+pub fn append_accumulator_leaves(path: &Path, leaves: &[AccumulatorLeaf], storage: &StorageConfig) -> anyhow::Result<()> {
+    check_free_space(path, storage.min_free_bytes, |dir| fs2::available_space(dir))?;
+    let mut lines = Vec::new();
+    for leaf in leaves {
+        serde_json::to_writer(&mut lines, leaf)?;
+        lines.push(b'\n');
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::OpenOptions::new().create(true).append(true).open(path)?.write_all(&lines)?;
+    Ok(())
+}
+
+/// Load the accumulator's leaves, oldest first (none if the log doesn't exist yet)
+///
+/// A last line cut short by a crash mid-append is dropped and truncated away, so the
+/// next append starts on a clean line.
+pub fn load_accumulator_leaves(path: &Path) -> anyhow::Result<Vec<AccumulatorLeaf>> {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let complete = content.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    if complete < content.len() {
+        warn!("Dropping a torn last line from {}", path.display());
+        fs::OpenOptions::new().write(true).open(path)?.set_len(complete as u64)?;
+    }
+    content[..complete]
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| Ok(serde_json::from_slice(line)?))
+        .collect()
+}
+
+/// Save the chain head of each per-sample DA layer
+pub fn save_sample_chains(path: &Path, chains: &BTreeMap<String, SampleChain>, storage: &StorageConfig) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(chains)?;
+    write_atomic(path, json.as_bytes(), storage)?;
+    Ok(())
+}
+
+/// Load the per-layer chain heads, empty if no sample has been posted yet
+pub fn load_sample_chains(path: &Path) -> anyhow::Result<BTreeMap<String, SampleChain>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Save a replica's read position
+pub fn save_replica_cursor(path: &Path, cursor: &ReplicaCursor, storage: &StorageConfig) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(cursor)?;
+    write_atomic(path, json.as_bytes(), storage)?;
+    Ok(())
+}
+
+/// Load a replica's read position, `None` if it hasn't read anything yet
+pub fn load_replica_cursor(path: &Path) -> anyhow::Result<Option<ReplicaCursor>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Record the posted manifest and its blob commitment
+pub fn save_manifest<T: serde::Serialize>(manifest: &T, commitment: &str, storage: &StorageConfig) -> anyhow::Result<()> {
+    let record = serde_json::json!({ "commitment": commitment, "manifest": manifest });
+    let json = serde_json::to_string_pretty(&record)?;
+    write_atomic(Path::new("data/manifest.json"), json.as_bytes(), storage)?;
     Ok(())
 }
 
 /// Save bitmap to hex file
-pub fn save_bitmap(bitmap: &[u8]) -> anyhow::Result<()> {
+pub fn save_bitmap(bitmap: &[u8], storage: &StorageConfig) -> anyhow::Result<()> {
     let hex: String = bitmap.iter().map(|b| format!("{:02x}", b)).collect();
-    fs::write("data/bitmap.hex", hex)?;
+    write_atomic(Path::new("data/bitmap.hex"), hex.as_bytes(), storage)?;
     Ok(())
 }
 
+/// Run a storage write on tokio's blocking pool
+///
+/// Writes block (and `with_write_retry` sleeps between attempts), so async tasks hand
+/// them owned data through this rather than stall a runtime worker or hold a lock.
+pub async fn spawn_write<T: Send + 'static>(write: impl FnOnce() -> anyhow::Result<T> + Send + 'static) -> anyhow::Result<T> {
+    tokio::task::spawn_blocking(write)
+        .await
+        .map_err(|e| anyhow::anyhow!("storage write task failed: {}", e))?
+}
+
+/// Replace `path` with `contents` via a sibling temp file and a rename, so readers
+/// never see a half-written file
+///
+/// Transient failures are retried up to `storage.write_retries` times (see `with_write_retry`).
+/// Nothing is written if free space is below `storage.min_free_bytes` (see `check_free_space`).
+fn write_atomic(path: &Path, contents: &[u8], storage: &StorageConfig) -> io::Result<()> {
+    write_atomic_checked(path, contents, storage, |dir| fs2::available_space(dir))
+}
+
+fn write_atomic_checked(
+    path: &Path,
+    contents: &[u8],
+    storage: &StorageConfig,
+    available_space: impl Fn(&Path) -> io::Result<u64>,
+) -> io::Result<()> {
+    check_free_space(path, storage.min_free_bytes, available_space)?;
+    let tmp_path = temp_path_for(path);
+    with_write_retry(storage, || {
+        fs::write(&tmp_path, contents)?;
+        fs::rename(&tmp_path, path)
+    })
+    .inspect_err(|_| {
+        let _ = fs::remove_file(&tmp_path);
+    })
+}
+
+/// Refuse a write up front when the filesystem holding `path` has less than `min_free_bytes`
+/// free, rather than risk running out of space halfway through it
+///
+/// If free space can't be determined (e.g. the directory doesn't exist yet) the write is
+/// attempted anyway and fails or succeeds on its own.
+fn check_free_space(path: &Path, min_free_bytes: u64, available_space: impl Fn(&Path) -> io::Result<u64>) -> io::Result<()> {
+    if min_free_bytes == 0 {
+        return Ok(());
+    }
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match available_space(dir) {
+        Ok(free) if free < min_free_bytes => {
+            error!(
+                "💾 Skipping write of {}: {} bytes free, below storage.min_free_bytes = {}",
+                path.display(),
+                free,
+                min_free_bytes
+            );
+            Err(io::Error::new(
+                ErrorKind::StorageFull,
+                format!("{} bytes free, below storage.min_free_bytes = {}", free, min_free_bytes),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+/// Run `write`, retrying transient I/O errors with a doubling backoff starting at
+/// `storage.write_retry_backoff_ms`
+///
+/// Permanent errors (read-only filesystem, permissions, missing directory) are returned
+/// immediately. The backoff sleeps the calling thread, so async callers go through
+/// `spawn_write`.
+fn with_write_retry<T>(storage: &StorageConfig, mut write: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut attempt = 0;
+    loop {
+        match write() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < storage.write_retries && !is_permanent_write_error(&e) => {
+                let delay = Duration::from_millis(storage.write_retry_backoff_ms.saturating_mul(1 << attempt.min(10)));
+                attempt += 1;
+                warn!("💾 Write failed ({}), retry {}/{} in {:?}", e, attempt, storage.write_retries, delay);
+                std::thread::sleep(delay);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Errors that retrying can't fix (unlike e.g. a briefly full disk or an NFS hiccup)
+fn is_permanent_write_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::ReadOnlyFilesystem
+            | ErrorKind::PermissionDenied
+            | ErrorKind::NotFound
+            | ErrorKind::NotADirectory
+            | ErrorKind::IsADirectory
+            | ErrorKind::InvalidInput
+    )
+}
+
+/// Gzip `data` at `level` (0-9, see `storage.compression_level`)
+pub fn gzip_compress(data: &[u8], level: u32) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level.min(9)));
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use flate2::read::GzDecoder;
+
+    fn retrying(write_retries: u32) -> StorageConfig {
+        StorageConfig { write_retries, write_retry_backoff_ms: 1, ..Default::default() }
+    }
+
+    #[test]
+    fn test_summaries_watermark_saved_and_derived_for_legacy_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sample_summaries.json");
+        assert_eq!(load_summaries(&path).unwrap(), (BTreeMap::new(), 0));
+
+        let bucket = SampleSummary { start: 3600, secs: 3600, ok: 90, total: 120 };
+        let summaries = BTreeMap::from([(3600, bucket.clone())]);
+        save_summaries(&path, &summaries, 5400, &StorageConfig::default()).unwrap();
+        assert_eq!(load_summaries(&path).unwrap(), (summaries.clone(), 5400));
+
+        // A bare bucket list: nothing before the last bucket's end is trusted to be uncounted
+        fs::write(&path, serde_json::to_string(&[bucket]).unwrap()).unwrap();
+        assert_eq!(load_summaries(&path).unwrap(), (summaries, 7200));
+    }
+
+    #[test]
+    fn test_compression_levels_roundtrip_with_differing_sizes() {
+        let samples: Vec<Sample> = (0..500)
+            .map(|t| Sample {
+                timestamp: 1729785600 + t * 30,
+                head: Some(8549697 + t as i64 * 5),
+                headers: Some(100_000 + t as i64),
+                ok: t % 7 != 0,
+                reason: format!("+{} blocks", t % 5),
+                ..Default::default()
+            })
+            .collect();
+        let json = serde_json::to_vec(&samples).unwrap();
+
+        let sizes: Vec<usize> = [0, 1, 9]
+            .iter()
+            .map(|&level| {
+                let compressed = gzip_compress(&json, level).unwrap();
+                let mut decompressed = Vec::new();
+                GzDecoder::new(&compressed[..]).read_to_end(&mut decompressed).unwrap();
+                assert_eq!(decompressed, json);
+                compressed.len()
+            })
+            .collect();
+
+        // Level 0 only stores; higher levels shrink the output
+        assert!(sizes[0] > json.len());
+        assert!(sizes[1] < sizes[0]);
+        assert!(sizes[2] < sizes[1]);
+    }
+
+    #[test]
+    fn test_transient_write_failure_is_retried() {
+        let mut attempts = 0;
+        let result = with_write_retry(&retrying(3), || {
+            attempts += 1;
+            if attempts == 1 {
+                Err(io::Error::from(ErrorKind::StorageFull))
+            } else {
+                Ok(())
+            }
+        });
+        assert!(result.is_ok());
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_permanent_write_failure_is_not_retried() {
+        let mut attempts = 0;
+        let result: io::Result<()> = with_write_retry(&retrying(3), || {
+            attempts += 1;
+            Err(io::Error::from(ErrorKind::ReadOnlyFilesystem))
+        });
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::ReadOnlyFilesystem);
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_retries_are_bounded() {
+        let mut attempts = 0;
+        let result: io::Result<()> = with_write_retry(&retrying(2), || {
+            attempts += 1;
+            Err(io::Error::from(ErrorKind::StorageFull))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_backoff_does_not_stall_the_runtime() {
+        // A write backing off for 500ms on the single-threaded test runtime
+        let storage = StorageConfig { write_retries: 1, write_retry_backoff_ms: 500, ..Default::default() };
+        let write = tokio::spawn(spawn_write(move || {
+            let mut failed = false;
+            Ok(with_write_retry(&storage, || {
+                if std::mem::replace(&mut failed, true) { Ok(()) } else { Err(io::Error::from(ErrorKind::StorageFull)) }
+            })?)
+        }));
+
+        let started = std::time::Instant::now();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(started.elapsed() < Duration::from_millis(400), "runtime blocked by the write backoff");
+        write.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_commitment_recorded_for_latest_and_archived_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = retrying(0);
+        let batch = |start: u64| Batch { window: crate::types::TimeWindow { start, end: start + 600 }, ..Batch::sample() };
+        let read = |path: &Path| -> Batch { serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap() };
+        let latest_path = dir.path().join("batch.json");
+        fs::write(&latest_path, serde_json::to_string(&batch(600)).unwrap()).unwrap();
+
+        // A retried older batch only updates its archive entry
+        record_batch_commitment_in(dir.path(), &batch(0), "c0", &storage).unwrap();
+        assert_eq!(read(&migration::batch_archive_path(dir.path(), &batch(0).window)).da_commitment.as_deref(), Some("c0"));
+        assert_eq!(read(&latest_path).da_commitment, None);
+
+        record_batch_commitment_in(dir.path(), &batch(600), "c1", &storage).unwrap();
+        assert_eq!(read(&latest_path).da_commitment.as_deref(), Some("c1"));
+        assert!(fs::read_to_string(&latest_path).unwrap().contains("\"da_commitment\": \"c1\""));
+    }
+
+    #[test]
+    fn test_accumulator_leaves_appended_and_torn_line_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("accumulator_leaves.jsonl");
+        assert!(load_accumulator_leaves(&path).unwrap().is_empty());
+
+        let leaf = |start: u64| AccumulatorLeaf { window: crate::types::TimeWindow { start, end: start + 600 }, bitmap_hash: "ab".to_string() };
+        append_accumulator_leaves(&path, &[leaf(0), leaf(600)], &retrying(0)).unwrap();
+        append_accumulator_leaves(&path, &[leaf(1200)], &retrying(0)).unwrap();
+        assert_eq!(load_accumulator_leaves(&path).unwrap(), vec![leaf(0), leaf(600), leaf(1200)]);
+
+        // A crash mid-append leaves a partial line, which is cut off so appends resume cleanly
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"window\":{\"sta").unwrap();
+        assert_eq!(load_accumulator_leaves(&path).unwrap().len(), 3);
+        append_accumulator_leaves(&path, &[leaf(1800)], &retrying(0)).unwrap();
+        assert_eq!(load_accumulator_leaves(&path).unwrap().last(), Some(&leaf(1800)));
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file_without_leaving_temp() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.json");
+        fs::write(&path, "old").unwrap();
+        write_atomic(&path, b"new", &retrying(0)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(!temp_path_for(&path).exists());
+    }
+
+    #[test]
+    fn test_write_skipped_when_free_space_low() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.json");
+        fs::write(&path, "old").unwrap();
+        let storage = StorageConfig { min_free_bytes: 1024, ..retrying(3) };
+
+        let err = write_atomic_checked(&path, b"new", &storage, |_| Ok(100)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::StorageFull);
+        // The existing file is untouched and no temp file was started
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert!(!temp_path_for(&path).exists());
+        assert!(is_low_space(&anyhow::Error::from(err).context("saving samples")));
+        assert!(!is_low_space(&anyhow::Error::from(io::Error::from(ErrorKind::PermissionDenied))));
+
+        write_atomic_checked(&path, b"new", &storage, |_| Ok(4096)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    }
+}
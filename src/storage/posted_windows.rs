@@ -0,0 +1,136 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::config::StorageConfig;
+use crate::types::TimeWindow;
+use super::write_atomic;
+
+/// Path of the persisted set of batch windows already posted to DA
+pub const POSTED_WINDOWS_PATH: &str = "data/posted_windows.json";
+
+/// Namespace key under which windows recorded before they were tracked per namespace
+/// are kept; they count as posted to every namespace
+const ANY_NAMESPACE: &str = "";
+
+/// Batch windows already posted to DA, per namespace, persisted so restarts neither
+/// re-post an attested window nor skip one a batch layer hasn't posted yet
+#[derive(Debug)]
+pub struct PostedWindows {
+    path: PathBuf,
+    windows: BTreeMap<String, BTreeSet<(u64, u64)>>,
+}
+
+/// On-disk forms: by namespace, or the flat set written before batch layers had their own
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stored {
+    ByNamespace(BTreeMap<String, BTreeSet<(u64, u64)>>),
+    Legacy(BTreeSet<(u64, u64)>),
+}
+
+impl PostedWindows {
+    /// Load the set from `path`, starting empty if the file doesn't exist yet
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let windows = match fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content)? {
+                Stored::ByNamespace(windows) => windows,
+                Stored::Legacy(windows) => BTreeMap::from([(ANY_NAMESPACE.to_string(), windows)]),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, windows })
+    }
+
+    /// Start with an empty set persisted at `path`
+    pub fn empty(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            windows: BTreeMap::new(),
+        }
+    }
+
+    /// Whether this window has already been posted to `namespace`
+    pub fn contains(&self, namespace: &str, window: &TimeWindow) -> bool {
+        [namespace, ANY_NAMESPACE]
+            .iter()
+            .any(|ns| self.windows.get(*ns).is_some_and(|set| set.contains(&(window.start, window.end))))
+    }
+
+    /// Record a window as posted to `namespace` and persist the set
+    ///
+    /// Written atomically, so a crash or full disk mid-write leaves the previous set
+    /// rather than a truncated one.
+    pub fn mark_posted(&mut self, namespace: &str, window: &TimeWindow, storage: &StorageConfig) -> anyhow::Result<()> {
+        if self.windows.entry(namespace.to_string()).or_default().insert((window.start, window.end)) {
+            let json = serde_json::to_string_pretty(&self.windows)?;
+            write_atomic(&self.path, json.as_bytes(), storage)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posted_windows_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("posted_windows.json");
+        let storage = StorageConfig::default();
+        let posted = TimeWindow { start: 0, end: 600 };
+        let pending = TimeWindow { start: 600, end: 1200 };
+
+        let mut before = PostedWindows::load(&path).unwrap();
+        assert!(!before.contains("ns-a", &posted));
+        before.mark_posted("ns-a", &posted, &storage).unwrap();
+
+        // Simulated restart: reload from disk
+        let mut after = PostedWindows::load(&path).unwrap();
+        assert!(after.contains("ns-a", &posted));
+        assert!(!after.contains("ns-a", &pending));
+
+        after.mark_posted("ns-a", &pending, &storage).unwrap();
+        let reloaded = PostedWindows::load(&path).unwrap();
+        assert!(reloaded.contains("ns-a", &posted));
+        assert!(reloaded.contains("ns-a", &pending));
+    }
+
+    #[test]
+    fn test_windows_tracked_per_namespace() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("posted_windows.json");
+        let storage = StorageConfig::default();
+        let window = TimeWindow { start: 0, end: 600 };
+
+        // One batch layer posted the window, the other's post failed before a restart
+        PostedWindows::load(&path).unwrap().mark_posted("ns-a", &window, &storage).unwrap();
+        let reloaded = PostedWindows::load(&path).unwrap();
+        assert!(reloaded.contains("ns-a", &window));
+        assert!(!reloaded.contains("ns-b", &window));
+
+        // A file from before per-namespace tracking marks its windows posted everywhere
+        fs::write(&path, "[[0, 600]]").unwrap();
+        let legacy = PostedWindows::load(&path).unwrap();
+        assert!(legacy.contains("ns-a", &window) && legacy.contains("ns-b", &window));
+    }
+
+    #[test]
+    fn test_low_space_keeps_previous_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("posted_windows.json");
+        let first = TimeWindow { start: 0, end: 600 };
+        let mut posted = PostedWindows::load(&path).unwrap();
+        posted.mark_posted("ns-a", &first, &StorageConfig::default()).unwrap();
+
+        let full = StorageConfig { min_free_bytes: u64::MAX, ..Default::default() };
+        let err = posted.mark_posted("ns-a", &TimeWindow { start: 600, end: 1200 }, &full).unwrap_err();
+        assert!(crate::storage::is_low_space(&err));
+        // The file still parses, with the window recorded before
+        let reloaded = PostedWindows::load(&path).unwrap();
+        assert!(reloaded.contains("ns-a", &first));
+    }
+}
@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::config::StorageConfig;
+use crate::types::{Batch, TimeWindow};
+use super::write_atomic;
+
+/// What a batch proof attests to, written next to it as `<start>_<end>.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofPublicInputs {
+    pub window: TimeWindow,
+    pub n: usize,
+    pub threshold: usize,
+    pub bitmap_hash: String,
+}
+
+impl ProofPublicInputs {
+    pub fn from_batch(batch: &Batch) -> Self {
+        Self {
+            window: batch.window.clone(),
+            n: batch.n,
+            threshold: batch.threshold,
+            bitmap_hash: batch.bitmap_hash.clone(),
+        }
+    }
+}
+
+/// A proof loaded back from the export directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredProof {
+    pub public_inputs: ProofPublicInputs,
+    pub proof: Vec<u8>,
+}
+
+/// `<dir>/<window_start>_<window_end>.proof`
+pub fn proof_path(dir: &Path, window: &TimeWindow) -> PathBuf {
+    dir.join(format!("{}_{}.proof", window.start, window.end))
+}
+
+/// Write a batch's proof and its public-inputs sidecar into `dir` (`proofs.export_dir`)
+///
+/// Each window gets its own pair of files, so earlier proofs stay available for submission.
+pub fn save_proof(dir: &Path, batch: &Batch, proof: &[u8], storage: &StorageConfig) -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = proof_path(dir, &batch.window);
+    let inputs = serde_json::to_string_pretty(&ProofPublicInputs::from_batch(batch))?;
+    // Sidecar first: a proof file present on disk always has its inputs
+    write_atomic(&path.with_extension("json"), inputs.as_bytes(), storage)?;
+    write_atomic(&path, proof, storage)?;
+    Ok(path)
+}
+
+/// Load the proof of `window`, if one was exported
+pub fn load_proof(dir: &Path, window: &TimeWindow) -> anyhow::Result<Option<StoredProof>> {
+    let path = proof_path(dir, window);
+    let proof = match fs::read(&path) {
+        Ok(proof) => proof,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let inputs = fs::read_to_string(path.with_extension("json"))?;
+    Ok(Some(StoredProof { public_inputs: serde_json::from_str(&inputs)?, proof }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_proofs_are_kept_per_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Config::for_tests().storage;
+        let batch = |start: u64, end: u64| Batch { bitmap_hash: format!("hash-{}", end), window: TimeWindow { start, end }, ..Batch::sample() };
+
+        let path = save_proof(dir.path(), &batch(1000, 1600), b"proof-a", &storage).unwrap();
+        assert_eq!(path, dir.path().join("1000_1600.proof"));
+        assert!(dir.path().join("1000_1600.json").exists());
+        save_proof(dir.path(), &batch(1600, 2200), b"proof-b", &storage).unwrap();
+
+        let window = |start: u64, end: u64| TimeWindow { start, end };
+        let first = load_proof(dir.path(), &window(1000, 1600)).unwrap().unwrap();
+        assert_eq!(first.proof, b"proof-a");
+        assert_eq!(first.public_inputs, ProofPublicInputs::from_batch(&batch(1000, 1600)));
+        assert_eq!(load_proof(dir.path(), &window(1600, 2200)).unwrap().unwrap().proof, b"proof-b");
+        assert!(load_proof(dir.path(), &window(1001, 1600)).unwrap().is_none());
+        assert!(load_proof(&dir.path().join("missing"), &window(1000, 1600)).unwrap().is_none());
+    }
+}
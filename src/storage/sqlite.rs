@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use rusqlite::{params, Connection};
+use crate::types::{Sample, SampleReason};
+
+/// Path of the sample database used by `storage.backend = "sqlite"`
+pub const SAMPLES_DB_PATH: &str = "data/samples.db";
+
+/// Columns added after the first schema, with their definitions; missing ones are added
+/// on open so databases created by older versions keep working
+const ADDED_COLUMNS: &[(&str, &str)] = &[
+    ("warmup", "INTEGER NOT NULL DEFAULT 0"),
+    ("reason_code", "TEXT"),
+    ("score", "REAL"),
+    ("labels", "TEXT"),
+    ("attributes", "TEXT"),
+];
+
+/// Open (creating if needed) the sample database at `SAMPLES_DB_PATH`
+pub fn init_db() -> anyhow::Result<Connection> {
+    init_db_at(Path::new(SAMPLES_DB_PATH))
+}
+
+/// Open (creating if needed) the sample database at `path`
+pub fn init_db_at(path: &Path) -> anyhow::Result<Connection> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS samples (
+            timestamp INTEGER NOT NULL,
+            head INTEGER,
+            headers INTEGER,
+            ok INTEGER NOT NULL,
+            reason TEXT NOT NULL
+        )",
+        [],
+    )?;
+    let existing: Vec<String> = conn
+        .prepare("SELECT name FROM pragma_table_info('samples')")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for (column, definition) in ADDED_COLUMNS {
+        if !existing.iter().any(|name| name == column) {
+            conn.execute(&format!("ALTER TABLE samples ADD COLUMN {} {}", column, definition), [])?;
+        }
+    }
+    conn.execute("CREATE INDEX IF NOT EXISTS samples_timestamp ON samples (timestamp)", [])?;
+    Ok(conn)
+}
+
+/// Append one sample; unlike samples.json this costs the same however long the history is
+///
+/// Labels and attributes are stored as JSON objects (NULL when empty).
+pub fn insert_sample(conn: &Connection, sample: &Sample) -> anyhow::Result<()> {
+    let json_map = |map: &BTreeMap<String, String>| -> serde_json::Result<Option<String>> {
+        (!map.is_empty()).then(|| serde_json::to_string(map)).transpose()
+    };
+    conn.execute(
+        "INSERT INTO samples (timestamp, head, headers, ok, reason, warmup, reason_code, score, labels, attributes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            sample.timestamp as i64,
+            sample.head,
+            sample.headers,
+            sample.ok,
+            sample.reason,
+            sample.warmup,
+            serde_json::to_value(sample.reason_code)?.as_str(),
+            sample.score,
+            json_map(&sample.labels)?,
+            json_map(&sample.attributes)?,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Load the samples taken at or after `since`, oldest first
+///
+/// Rows written before `reason_code` was stored load with the default reason.
+pub fn load_samples_db(conn: &Connection, since: u64) -> anyhow::Result<Vec<Sample>> {
+    let mut stmt = conn.prepare(
+        "SELECT timestamp, head, headers, ok, reason, warmup, reason_code, score, labels, attributes
+         FROM samples WHERE timestamp >= ?1 ORDER BY timestamp, rowid",
+    )?;
+    let rows = stmt.query_map(params![since as i64], |row| {
+        Ok((
+            Sample {
+                timestamp: row.get::<_, i64>(0)? as u64,
+                head: row.get(1)?,
+                headers: row.get(2)?,
+                ok: row.get(3)?,
+                reason: row.get(4)?,
+                warmup: row.get(5)?,
+                score: row.get(7)?,
+                ..Default::default()
+            },
+            row.get::<_, Option<String>>(6)?,
+            row.get::<_, Option<String>>(8)?,
+            row.get::<_, Option<String>>(9)?,
+        ))
+    })?;
+    let mut samples = Vec::new();
+    for row in rows {
+        let (sample, reason_code, labels, attributes) = row?;
+        let reason_code = match reason_code {
+            Some(code) => serde_json::from_value::<SampleReason>(serde_json::Value::String(code))?,
+            None => SampleReason::default(),
+        };
+        let json_map = |json: Option<String>| -> serde_json::Result<BTreeMap<String, String>> {
+            json.map(|json| serde_json::from_str(&json)).transpose().map(Option::unwrap_or_default)
+        };
+        samples.push(Sample { reason_code, labels: json_map(labels)?, attributes: json_map(attributes)?, ..sample });
+    }
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples_inserted_incrementally() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data").join("samples.db");
+        let conn = init_db_at(&path).unwrap();
+        let labels = BTreeMap::from([("region".to_string(), "eu".to_string())]);
+        let attributes = BTreeMap::from([("service.name".to_string(), "celestia-light".to_string())]);
+        let first = Sample {
+            timestamp: 1729785600,
+            head: Some(100),
+            headers: Some(5),
+            ok: true,
+            reason: "+1 blocks".to_string(),
+            reason_code: SampleReason::Advancing,
+            score: Some(0.75),
+            labels,
+            attributes,
+            ..Default::default()
+        };
+        insert_sample(&conn, &first).unwrap();
+        insert_sample(&conn, &Sample { timestamp: 1729785630, ok: false, reason: "stale".to_string(), warmup: true, ..Default::default() }).unwrap();
+        drop(conn);
+
+        // Reopening keeps existing rows (the table is only created once)
+        let conn = init_db_at(&path).unwrap();
+        let rows = load_samples_db(&conn, 0).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(serde_json::to_value(&rows[0]).unwrap(), serde_json::to_value(&first).unwrap());
+        assert_eq!((rows[1].timestamp, rows[1].head, rows[1].headers, rows[1].ok), (1729785630, None, None, false));
+        assert_eq!(rows[1].reason, "stale");
+        assert!(rows[1].warmup && rows[1].labels.is_empty() && rows[1].score.is_none());
+
+        // Only samples from `since` on
+        let recent = load_samples_db(&conn, 1729785601).unwrap();
+        assert_eq!(recent.iter().map(|s| s.timestamp).collect::<Vec<_>>(), vec![1729785630]);
+    }
+
+    #[test]
+    fn test_databases_from_the_first_schema_are_upgraded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute("CREATE TABLE samples (timestamp INTEGER NOT NULL, head INTEGER, headers INTEGER, ok INTEGER NOT NULL, reason TEXT NOT NULL)", []).unwrap();
+        conn.execute("INSERT INTO samples VALUES (1729785600, 100, 5, 1, '+1 blocks')", []).unwrap();
+        drop(conn);
+
+        let conn = init_db_at(&path).unwrap();
+        insert_sample(&conn, &Sample { timestamp: 1729785630, warmup: true, reason_code: SampleReason::Stale, ..Default::default() }).unwrap();
+        let rows = load_samples_db(&conn, 0).unwrap();
+        assert_eq!((rows[0].head, rows[0].warmup, rows[0].reason_code), (Some(100), false, SampleReason::default()));
+        assert_eq!((rows[1].warmup, rows[1].reason_code), (true, SampleReason::Stale));
+    }
+}
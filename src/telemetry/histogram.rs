@@ -0,0 +1,90 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Fixed-bucket histogram of durations, safe to record from many tasks at once
+#[derive(Debug)]
+pub struct Histogram {
+    /// Bucket upper bounds in seconds, ascending
+    bounds: Vec<f64>,
+    /// Observations per bucket (not cumulative); the last slot is +Inf
+    counts: Vec<AtomicU64>,
+    sum_nanos: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &[f64]) -> Self {
+        Self {
+            bounds: bounds.to_vec(),
+            counts: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum_nanos: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|&bound| secs <= bound)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Append `_bucket` (cumulative), `_sum` and `_count` lines for `name`
+    pub fn render(&self, name: &str, out: &mut String) {
+        let mut cumulative = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            cumulative += count.load(Ordering::Relaxed);
+            let le = match self.bounds.get(i) {
+                Some(bound) => bound.to_string(),
+                None => "+Inf".to_string(),
+            };
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, le, cumulative);
+        }
+        let sum = self.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+        let _ = writeln!(out, "{}_sum {}", name, sum);
+        let _ = writeln!(out, "{}_count {}", name, self.count.load(Ordering::Relaxed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(histogram: &Histogram) -> String {
+        let mut out = String::new();
+        histogram.render("h", &mut out);
+        out
+    }
+
+    #[test]
+    fn test_observation_lands_in_right_bucket() {
+        let histogram = Histogram::new(&[0.001, 0.005, 0.01]);
+        histogram.observe(Duration::from_millis(3));
+
+        let out = rendered(&histogram);
+        assert!(out.contains("h_bucket{le=\"0.001\"} 0\n"));
+        assert!(out.contains("h_bucket{le=\"0.005\"} 1\n"));
+        assert!(out.contains("h_bucket{le=\"0.01\"} 1\n"));
+        assert!(out.contains("h_bucket{le=\"+Inf\"} 1\n"));
+        assert!(out.contains("h_sum 0.003\n"));
+        assert!(out.contains("h_count 1\n"));
+    }
+
+    #[test]
+    fn test_bucket_bounds_are_inclusive_and_overflow_goes_to_inf() {
+        let histogram = Histogram::new(&[0.001, 0.005]);
+        histogram.observe(Duration::from_millis(5));
+        histogram.observe(Duration::from_secs(2));
+
+        let out = rendered(&histogram);
+        assert!(out.contains("h_bucket{le=\"0.005\"} 1\n"));
+        assert!(out.contains("h_bucket{le=\"+Inf\"} 2\n"));
+        assert!(out.contains("h_count 2\n"));
+    }
+}
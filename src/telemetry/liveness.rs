@@ -0,0 +1,102 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Unix seconds each subsystem last made progress (0 = never), for GET /healthz/detailed
+#[derive(Debug)]
+pub struct Liveness {
+    /// When the reader started; subsystems that never reported are aged from here
+    pub started_at: u64,
+    /// Last POST /v1/metrics handled
+    pub last_ingest: AtomicU64,
+    /// Last sampler tick
+    pub last_tick: AtomicU64,
+    /// Last batch generated
+    pub last_batch: AtomicU64,
+    /// Last successful DA submission, and last failed one
+    pub last_da_success: AtomicU64,
+    pub last_da_failure: AtomicU64,
+    /// Last successful data/ write, and last failed one
+    pub last_storage_write: AtomicU64,
+    pub last_storage_failure: AtomicU64,
+}
+
+impl Default for Liveness {
+    fn default() -> Self {
+        Self {
+            started_at: unix_now(),
+            last_ingest: AtomicU64::new(0),
+            last_tick: AtomicU64::new(0),
+            last_batch: AtomicU64::new(0),
+            last_da_success: AtomicU64::new(0),
+            last_da_failure: AtomicU64::new(0),
+            last_storage_write: AtomicU64::new(0),
+            last_storage_failure: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Liveness {
+    /// Stamp `field` with the current time
+    pub fn mark(field: &AtomicU64) {
+        field.store(unix_now(), Ordering::Relaxed);
+    }
+
+    /// Record the outcome of a DA submission
+    pub fn record_da<T, E>(&self, result: &Result<T, E>) {
+        Self::mark(if result.is_ok() { &self.last_da_success } else { &self.last_da_failure });
+    }
+
+    /// Record the outcome of a data/ write
+    pub fn record_storage<T, E>(&self, result: &Result<T, E>) {
+        Self::mark(if result.is_ok() { &self.last_storage_write } else { &self.last_storage_failure });
+    }
+
+    /// Read a timestamp, `None` if it was never stamped
+    pub fn get(field: &AtomicU64) -> Option<u64> {
+        Some(field.load(Ordering::Relaxed)).filter(|&t| t > 0)
+    }
+
+    /// Last progress of each background task; a DA posting cycle counts whether it succeeded or not
+    pub fn task_progress(&self) -> [(&'static str, Option<u64>); 3] {
+        let da_cycle = Self::get(&self.last_da_success).max(Self::get(&self.last_da_failure));
+        [
+            ("sampler", Self::get(&self.last_tick)),
+            ("batch_generator", Self::get(&self.last_batch)),
+            ("da_posting", da_cycle),
+        ]
+    }
+
+    /// Render the task progress timestamps as Prometheus gauges (0 = never)
+    pub fn render(&self, out: &mut String) {
+        let name = "da_reader_task_last_progress_timestamp_seconds";
+        let _ = writeln!(out, "# HELP {} Unix time each background task last made progress", name);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        for (task, last) in self.task_progress() {
+            let _ = writeln!(out, "{}{{task=\"{}\"}} {}", name, task, last.unwrap_or(0));
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_progress_rendered_as_gauges() {
+        let live = Liveness::default();
+        live.last_tick.store(1000, Ordering::Relaxed);
+        live.last_da_success.store(900, Ordering::Relaxed);
+        live.last_da_failure.store(950, Ordering::Relaxed);
+
+        let mut out = String::new();
+        live.render(&mut out);
+        assert!(out.contains("da_reader_task_last_progress_timestamp_seconds{task=\"sampler\"} 1000\n"));
+        assert!(out.contains("da_reader_task_last_progress_timestamp_seconds{task=\"batch_generator\"} 0\n"));
+        assert!(out.contains("da_reader_task_last_progress_timestamp_seconds{task=\"da_posting\"} 950\n"));
+    }
+}
@@ -0,0 +1,268 @@
+// Metrics about the reader itself, exposed in Prometheus text format at GET /metrics
+//
+// Everything here is lock-free (atomics) so recording on the ingest hot path is cheap.
+// `Liveness` holds per-subsystem progress timestamps behind GET /healthz/detailed; the
+// background tasks' ones are also exported here and watched by `metrics::run_watchdog`.
+
+mod histogram;
+mod liveness;
+
+pub use histogram::Histogram;
+pub use liveness::Liveness;
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use axum::{extract::State, http::header, response::IntoResponse};
+use crate::types::{AppState, Batch, Sample};
+
+/// Upper bounds (seconds) for `da_reader_ingest_duration_seconds`
+const INGEST_DURATION_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+/// Reader self-metrics shared through `AppState`
+#[derive(Debug)]
+pub struct ReaderMetrics {
+    /// Time spent handling each POST /v1/metrics request
+    pub ingest_duration: Histogram,
+    /// Pending DA batches dropped for exceeding the queue's length or age limit
+    pub da_pending_evicted: AtomicU64,
+    /// data/ writes skipped because free space was below `storage.min_free_bytes`
+    storage_low_space_skipped: AtomicU64,
+    /// Samples recorded since startup, and how many of them were ok
+    samples_total: AtomicU64,
+    samples_ok_total: AtomicU64,
+    /// Batches completed since startup
+    batches_total: AtomicU64,
+    /// Chain head of the latest sample that had one (`HEAD_UNKNOWN` before that)
+    current_head: AtomicI64,
+    /// Reason tally of the latest completed batch, in `BATCH_REASONS` order
+    last_batch_reasons: [AtomicU64; 4],
+    /// Window of the latest completed batch (end 0 = no batch yet)
+    last_batch_window: [AtomicU64; 2],
+}
+
+/// `reason` label values of `da_reader_batch_reason_count`
+const BATCH_REASONS: [&str; 4] = ["ok", "stale", "stuck_head", "other"];
+
+/// `current_head` until a sample with a head is recorded (heads are never negative)
+const HEAD_UNKNOWN: i64 = -1;
+
+impl Default for ReaderMetrics {
+    fn default() -> Self {
+        Self {
+            ingest_duration: Histogram::new(INGEST_DURATION_BUCKETS),
+            da_pending_evicted: AtomicU64::new(0),
+            storage_low_space_skipped: AtomicU64::new(0),
+            samples_total: AtomicU64::new(0),
+            samples_ok_total: AtomicU64::new(0),
+            batches_total: AtomicU64::new(0),
+            current_head: AtomicI64::new(HEAD_UNKNOWN),
+            last_batch_reasons: Default::default(),
+            last_batch_window: Default::default(),
+        }
+    }
+}
+
+impl ReaderMetrics {
+    /// Render all metrics in Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP da_reader_ingest_duration_seconds Time spent handling OTLP ingest requests");
+        let _ = writeln!(out, "# TYPE da_reader_ingest_duration_seconds histogram");
+        self.ingest_duration.render("da_reader_ingest_duration_seconds", &mut out);
+        let _ = writeln!(out, "# HELP da_reader_da_pending_evicted_total Pending DA batches dropped unposted (queue full or too old)");
+        let _ = writeln!(out, "# TYPE da_reader_da_pending_evicted_total counter");
+        let _ = writeln!(out, "da_reader_da_pending_evicted_total {}", self.da_pending_evicted.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# HELP da_reader_storage_low_space_skipped_total data/ writes skipped for free space below storage.min_free_bytes");
+        let _ = writeln!(out, "# TYPE da_reader_storage_low_space_skipped_total counter");
+        let _ = writeln!(out, "da_reader_storage_low_space_skipped_total {}", self.storage_low_space_skipped.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# HELP da_reader_samples_total Samples recorded since startup");
+        let _ = writeln!(out, "# TYPE da_reader_samples_total counter");
+        let _ = writeln!(out, "da_reader_samples_total {}", self.samples_total.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# HELP da_reader_samples_ok_total Ok samples recorded since startup");
+        let _ = writeln!(out, "# TYPE da_reader_samples_ok_total counter");
+        let _ = writeln!(out, "da_reader_samples_ok_total {}", self.samples_ok_total.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# HELP da_reader_batches_total Batches completed since startup");
+        let _ = writeln!(out, "# TYPE da_reader_batches_total counter");
+        let _ = writeln!(out, "da_reader_batches_total {}", self.batches_total.load(Ordering::Relaxed));
+        let head = self.current_head.load(Ordering::Relaxed);
+        if head != HEAD_UNKNOWN {
+            let _ = writeln!(out, "# HELP da_reader_current_head DAS sampled chain head of the latest sample");
+            let _ = writeln!(out, "# TYPE da_reader_current_head gauge");
+            let _ = writeln!(out, "da_reader_current_head {}", head);
+        }
+        out
+    }
+
+    /// Samples recorded since startup, and how many were ok
+    pub fn sample_counts(&self) -> (u64, u64) {
+        (self.samples_total.load(Ordering::Relaxed), self.samples_ok_total.load(Ordering::Relaxed))
+    }
+
+    /// Count a recorded sample and remember its head
+    pub fn record_sample(&self, sample: &Sample) {
+        self.samples_total.fetch_add(1, Ordering::Relaxed);
+        if sample.ok {
+            self.samples_ok_total.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(head) = sample.head {
+            self.current_head.store(head, Ordering::Relaxed);
+        }
+    }
+
+    /// Count a data/ write that was skipped for low free space
+    pub fn record_storage(&self, result: &anyhow::Result<impl Sized>) {
+        if result.as_ref().is_err_and(crate::storage::is_low_space) {
+            self.storage_low_space_skipped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Remember a completed batch's reason breakdown for the window-scoped gauges
+    pub fn record_batch(&self, batch: &Batch) {
+        self.batches_total.fetch_add(1, Ordering::Relaxed);
+        let c = &batch.categories;
+        for (gauge, count) in self.last_batch_reasons.iter().zip([c.ok, c.stale, c.stuck_head, c.other]) {
+            gauge.store(count as u64, Ordering::Relaxed);
+        }
+        self.last_batch_window[0].store(batch.window.start, Ordering::Relaxed);
+        self.last_batch_window[1].store(batch.window.end, Ordering::Relaxed);
+    }
+
+    /// Render the latest batch's reason breakdown (nothing before the first batch)
+    ///
+    /// Unlike the cumulative counters these are replaced every window, so they show the
+    /// composition of the most recent attestation.
+    fn render_batch_reasons(&self, out: &mut String) {
+        let end = self.last_batch_window[1].load(Ordering::Relaxed);
+        if end == 0 {
+            return;
+        }
+        let _ = writeln!(out, "# HELP da_reader_batch_reason_count Samples per reason category in the latest completed batch");
+        let _ = writeln!(out, "# TYPE da_reader_batch_reason_count gauge");
+        for (reason, gauge) in BATCH_REASONS.iter().zip(&self.last_batch_reasons) {
+            let _ = writeln!(out, "da_reader_batch_reason_count{{reason=\"{}\"}} {}", reason, gauge.load(Ordering::Relaxed));
+        }
+        let _ = writeln!(out, "# HELP da_reader_batch_window_seconds Window bounds (Unix seconds) of the latest completed batch");
+        let _ = writeln!(out, "# TYPE da_reader_batch_window_seconds gauge");
+        let _ = writeln!(out, "da_reader_batch_window_seconds{{bound=\"start\"}} {}", self.last_batch_window[0].load(Ordering::Relaxed));
+        let _ = writeln!(out, "da_reader_batch_window_seconds{{bound=\"end\"}} {}", end);
+    }
+}
+
+/// Render the current batch window's length, ok/total counts and uptime ratio
+fn render_window(len: usize, good: usize, total: usize, out: &mut String) {
+    let _ = writeln!(out, "# HELP da_reader_ring_buffer_len Samples held for the current batch window, warmup included");
+    let _ = writeln!(out, "# TYPE da_reader_ring_buffer_len gauge");
+    let _ = writeln!(out, "da_reader_ring_buffer_len {}", len);
+    let _ = writeln!(out, "# HELP da_reader_window_samples Counted (non-warmup) samples in the current batch window");
+    let _ = writeln!(out, "# TYPE da_reader_window_samples gauge");
+    let _ = writeln!(out, "da_reader_window_samples{{result=\"ok\"}} {}", good);
+    let _ = writeln!(out, "da_reader_window_samples{{result=\"total\"}} {}", total);
+    if total > 0 {
+        let _ = writeln!(out, "# HELP da_reader_window_uptime_ratio Share of ok samples in the current batch window");
+        let _ = writeln!(out, "# TYPE da_reader_window_uptime_ratio gauge");
+        let _ = writeln!(out, "da_reader_window_uptime_ratio {}", good as f64 / total as f64);
+    }
+}
+
+/// GET /metrics: Prometheus scrape endpoint
+pub async fn handle_prometheus(State(state): State<AppState>) -> impl IntoResponse {
+    let mut out = state.telemetry.render();
+    state.liveness.render(&mut out);
+    let (len, (good, total)) = {
+        let ring_buffer = state.ring_buffer.lock().unwrap();
+        (ring_buffer.len(), ring_buffer.counts())
+    };
+    render_window(len, good, total, &mut out);
+    if state.config.server.export_batch_reasons {
+        state.telemetry.render_batch_reasons(&mut out);
+    }
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use axum::body::to_bytes;
+    use crate::config::Config;
+    use crate::types::{CategoryCounts, SampleBit, TimeWindow};
+
+    #[tokio::test]
+    async fn test_window_uptime_exported() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        for ok in [true, true, true, false] {
+            state.ring_buffer.lock().unwrap().push(SampleBit { ok, ..Default::default() }, 20);
+        }
+
+        let response = handle_prometheus(State(state)).await.into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("da_reader_window_samples{result=\"ok\"} 3\n"));
+        assert!(text.contains("da_reader_window_samples{result=\"total\"} 4\n"));
+        assert!(text.contains("da_reader_window_uptime_ratio 0.75\n"));
+        assert!(text.contains("da_reader_ring_buffer_len 4\n"));
+        assert!(!text.contains("da_reader_batch_reason_count"));
+    }
+
+    #[test]
+    fn test_sample_counters_and_head() {
+        let metrics = ReaderMetrics::default();
+        assert!(!metrics.render().contains("da_reader_current_head"));
+
+        metrics.record_sample(&Sample { ok: true, head: Some(100), ..Default::default() });
+        metrics.record_sample(&Sample { ok: false, head: Some(101), ..Default::default() });
+        // A sample without a head keeps the last known one
+        metrics.record_sample(&Sample { ok: true, ..Default::default() });
+        let text = metrics.render();
+        assert!(text.contains("da_reader_samples_total 3\n"));
+        assert!(text.contains("da_reader_samples_ok_total 2\n"));
+        assert!(text.contains("da_reader_current_head 101\n"));
+        assert!(text.contains("da_reader_batches_total 0\n"));
+    }
+
+    #[test]
+    fn test_low_space_skips_counted_per_instance() {
+        let metrics = ReaderMetrics::default();
+        let low_space = || -> anyhow::Result<()> { Err(std::io::Error::from(std::io::ErrorKind::StorageFull).into()) };
+        metrics.record_storage(&low_space());
+        metrics.record_storage(&low_space().map_err(|e| e.context("saving batch")));
+        metrics.record_storage(&anyhow::Result::<()>::Err(anyhow::anyhow!("permission denied")));
+        metrics.record_storage(&anyhow::Result::Ok(()));
+        assert!(metrics.render().contains("da_reader_storage_low_space_skipped_total 2\n"));
+        assert!(ReaderMetrics::default().render().contains("da_reader_storage_low_space_skipped_total 0\n"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_reason_gauges_follow_latest_batch() {
+        let state = AppState::new(Arc::new(Config::for_tests()));
+        let batch = |stale: usize, window: TimeWindow| Batch {
+            good: 20 - stale - 1,
+            window,
+            categories: CategoryCounts { ok: 20 - stale - 1, stale, stuck_head: 1, other: 0 },
+            ..Batch::sample()
+        };
+        let scrape = |state: AppState| async move {
+            let body = to_bytes(handle_prometheus(State(state)).await.into_response().into_body(), usize::MAX).await.unwrap();
+            String::from_utf8(body.to_vec()).unwrap()
+        };
+
+        state.telemetry.record_batch(&batch(3, TimeWindow { start: 1000, end: 1600 }));
+        state.telemetry.record_batch(&batch(0, TimeWindow { start: 1600, end: 2200 }));
+        let text = scrape(state.clone()).await;
+        assert!(text.contains("da_reader_batches_total 2\n"));
+        // Only the latest window counts, not a running total
+        assert!(text.contains("da_reader_batch_reason_count{reason=\"ok\"} 19\n"));
+        assert!(text.contains("da_reader_batch_reason_count{reason=\"stale\"} 0\n"));
+        assert!(text.contains("da_reader_batch_reason_count{reason=\"stuck_head\"} 1\n"));
+        assert!(text.contains("da_reader_batch_reason_count{reason=\"other\"} 0\n"));
+        assert!(text.contains("da_reader_batch_window_seconds{bound=\"end\"} 2200\n"));
+
+        let mut config = Config::for_tests();
+        config.server.export_batch_reasons = false;
+        let quiet = AppState::new(Arc::new(config));
+        quiet.telemetry.record_batch(&batch(3, TimeWindow { start: 1000, end: 1600 }));
+        assert!(!scrape(quiet).await.contains("da_reader_batch_reason_count"));
+    }
+}
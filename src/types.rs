@@ -1,14 +1,24 @@
+use rand::{rngs::StdRng, SeedableRng};
 use serde::{Serialize, Deserialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
 use crate::config::Config;
+use crate::metrics::{AlertState, SampleRing};
+use crate::otlp::PreviousValues;
+use crate::proofs::HashAlgo;
+use crate::da::{ChainAnchor, DaPoster, DaSigner};
+use crate::telemetry::{Liveness, ReaderMetrics};
 
 /// Stores the latest DAS metrics
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct DasMetrics {
     pub head: Option<i64>,
     pub headers: Option<i64>,
     pub last_update: Option<u64>, // Unix timestamp in seconds
+    /// Configured resource attributes of the source that last updated head/headers
+    pub source_attributes: BTreeMap<String, String>,
+    /// Labels mapped from the attributes of the metric that last updated head/headers
+    pub labels: BTreeMap<String, String>,
 }
 
 /// Application state shared across handlers and background tasks
@@ -16,26 +26,201 @@ pub struct DasMetrics {
 pub struct AppState {
     pub config: Arc<Config>,
     pub das_metrics: Arc<Mutex<DasMetrics>>,
-    pub ring_buffer: Arc<Mutex<VecDeque<SampleBit>>>,
+    pub ring_buffer: Arc<Mutex<SampleRing>>,
     pub samples: Arc<Mutex<Vec<Sample>>>,
+    /// Downsampled history older than `storage.downsample_after_secs`, by bucket start
+    pub sample_summaries: Arc<Mutex<BTreeMap<u64, SampleSummary>>>,
+    /// Samples taken before this are counted in `sample_summaries` (saved with them, so a
+    /// restart doesn't restore and count them again)
+    pub compacted_before: Arc<Mutex<u64>>,
+    pub alert_state: Arc<Mutex<AlertState>>,
+    /// Last value per ingested metric name (bounded LRU)
+    pub previous_values: Arc<Mutex<PreviousValues>>,
+    /// Where DA blobs are submitted (`celestia.poster_mode`)
+    pub da_poster: Arc<DaPoster>,
+    /// Key DA submissions are signed with (swapped by POST /admin/reload-key)
+    pub da_signer: Arc<DaSigner>,
+    /// Self-metrics exposed at GET /metrics
+    pub telemetry: Arc<ReaderMetrics>,
+    /// When each subsystem last made progress (GET /healthz/detailed)
+    pub liveness: Arc<Liveness>,
+    /// `proofs.threshold_percent` set at runtime via POST /admin/threshold (lost on restart)
+    pub threshold_override: Arc<Mutex<Option<f64>>>,
+    /// Source of jitter/randomness; seeded from entropy, fixed via `with_rng_seed` in tests
+    pub rng: Arc<Mutex<StdRng>>,
+}
+
+impl AppState {
+    /// Create empty state for the given config
+    pub fn new(config: Arc<Config>) -> Self {
+        let previous_values = PreviousValues::new(
+            config.metrics.max_tracked_metric_names,
+            config.metrics.head_metric.iter().chain(config.metrics.headers_metric.iter()).cloned(),
+        );
+        let da_signer = Arc::new(DaSigner::from_config(&config.celestia));
+        let da_poster = Arc::new(DaPoster::from_config(&config.celestia, da_signer.clone()));
+        Self {
+            config,
+            das_metrics: Arc::new(Mutex::new(DasMetrics::default())),
+            ring_buffer: Arc::new(Mutex::new(SampleRing::default())),
+            samples: Arc::new(Mutex::new(Vec::new())),
+            sample_summaries: Arc::new(Mutex::new(BTreeMap::new())),
+            compacted_before: Arc::new(Mutex::new(0)),
+            alert_state: Arc::new(Mutex::new(AlertState::default())),
+            previous_values: Arc::new(Mutex::new(previous_values)),
+            da_poster,
+            da_signer,
+            telemetry: Arc::new(ReaderMetrics::default()),
+            liveness: Arc::new(Liveness::default()),
+            threshold_override: Arc::new(Mutex::new(None)),
+            rng: Arc::new(Mutex::new(StdRng::from_entropy())),
+        }
+    }
+
+    /// Threshold batches are judged against: the runtime override, else `proofs.threshold_percent`
+    pub fn threshold_percent(&self) -> f64 {
+        self.threshold_override
+            .lock()
+            .unwrap()
+            .unwrap_or(self.config.proofs.threshold_percent)
+    }
+
+    /// Record the outcome of a data/ write (liveness, and low-space skips for GET /metrics)
+    pub fn record_storage<T>(&self, result: &anyhow::Result<T>) {
+        self.liveness.record_storage(result);
+        self.telemetry.record_storage(result);
+    }
+
+    /// Replace the RNG with a deterministic one
+    #[cfg(test)]
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng = Arc::new(Mutex::new(StdRng::seed_from_u64(seed)));
+        self
+    }
 }
 
 /// A single sample bit with metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SampleBit {
     pub timestamp: u64,
     pub ok: bool,
     pub reason: String,
+    /// Neither head nor headers had ever been received when this sample was taken
+    #[serde(default)]
+    pub no_data: bool,
+    /// Taken during the post-startup warmup; recorded but excluded from batches
+    #[serde(default)]
+    pub warmup: bool,
+    /// Machine-readable cause behind `ok`/`reason`
+    #[serde(default)]
+    pub reason_code: SampleReason,
+    /// Weighted health score in [0, 1] when `metrics.weights` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+}
+
+/// Machine-readable cause of a sample's verdict (`reason` strings are for humans)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleReason {
+    /// Head advanced by at least `min_increment`
+    Advancing,
+    /// Head didn't advance, but the data is within the grace period
+    FreshData,
+    /// First head reading since startup
+    FirstSample,
+    /// Telemetry stale, but the node's RPC head is advancing
+    NodeRpcAdvancing,
+    /// No DAS metrics received at all
+    NoData,
+    Stale,
+    StuckHead,
+    /// Head stuck while headers still advance (`sampling.head_stuck_headers_advancing_ok`)
+    HeadStuckHeadersAdvancing,
+    NoHead,
+    HeadersStalled,
+    /// Head trails the network head by more than `metrics.max_head_lag`
+    HeadLagging,
+    /// Failing tick kept ok shortly after a recovery (`sampling.recovery_cooldown_secs`)
+    RecoveryCooldown,
+    /// Recorded before reasons were classified
+    #[default]
+    Unclassified,
+}
+
+/// Failure category encoded as 2 bits per sample in the reason bitmap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ReasonCategory {
+    Ok = 0,
+    /// Stale telemetry or no data at all
+    Stale = 1,
+    /// Head not advancing or missing
+    StuckHead = 2,
+    /// Any other failure (e.g. headers not advancing)
+    Other = 3,
+}
+
+impl ReasonCategory {
+    pub fn of(ok: bool, reason: SampleReason) -> Self {
+        if ok {
+            return ReasonCategory::Ok;
+        }
+        match reason {
+            SampleReason::Stale | SampleReason::NoData => ReasonCategory::Stale,
+            SampleReason::StuckHead | SampleReason::HeadStuckHeadersAdvancing | SampleReason::NoHead => {
+                ReasonCategory::StuckHead
+            }
+            _ => ReasonCategory::Other,
+        }
+    }
 }
 
 /// Raw sample data point
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Sample {
     pub timestamp: u64,
     pub head: Option<i64>,
     pub headers: Option<i64>,
     pub ok: bool,
     pub reason: String,
+    /// Taken during the post-startup warmup; excluded from attestation
+    #[serde(default)]
+    pub warmup: bool,
+    #[serde(default)]
+    pub reason_code: SampleReason,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// Selected resource attributes of the source node (see `metrics.sample_resource_attributes`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+    /// Labels mapped from metric attributes (see `metrics.sample_labels`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl From<&Sample> for SampleBit {
+    fn from(sample: &Sample) -> Self {
+        Self {
+            timestamp: sample.timestamp,
+            ok: sample.ok,
+            reason: sample.reason.clone(),
+            no_data: sample.head.is_none() && sample.headers.is_none(),
+            warmup: sample.warmup,
+            reason_code: sample.reason_code,
+            score: sample.score,
+        }
+    }
+}
+
+/// Ok/total counts of the samples in one downsampled time bucket
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampleSummary {
+    /// Bucket start (Unix seconds, a multiple of `secs`)
+    pub start: u64,
+    pub secs: u64,
+    pub ok: usize,
+    pub total: usize,
 }
 
 /// Batch structure
@@ -44,11 +229,93 @@ pub struct Batch {
     pub n: usize,
     pub good: usize,
     pub threshold: usize,
+    /// Share of `n` that `threshold` was computed from: `proofs.threshold_percent`, or the
+    /// runtime override (POST /admin/threshold) in effect when the batch was built
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold_percent: Option<f64>,
     pub bitmap_hash: String,
+    /// The bitmap hashed with `hash_algo` and every `proofs.hash_algos` algorithm, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bitmap_hashes: BTreeMap<HashAlgo, String>,
     pub window: TimeWindow,
+    /// Samples per failure category, so proofs can bound each category separately
+    #[serde(default)]
+    pub categories: CategoryCounts,
+    /// Hash of the 2-bit-per-sample reason bitmap (`proofs.reason_bitmap`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason_bitmap_hash: Option<String>,
+    /// Sum of `proofs.reason_weights` if set, else of weighted sample scores (`metrics.weights`);
+    /// unweighted samples count as 1 or 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weighted_good: Option<f64>,
+    /// blake3 of the canonical DA payload (`da::batch_content_hash`); equal for identical batches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Minimum `good` to count as healthy rather than degraded (`proofs.warn_threshold_percent`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warn_threshold: Option<usize>,
+    /// Classification against `threshold` and `warn_threshold`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<BatchHealth>,
+    /// Commitment of the posted batch blob, recorded in data/batch.json once posted to DA
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub da_commitment: Option<String>,
+    /// Height and block time of the DA block that included the batch blob, recorded with
+    /// `da_commitment` when `da_posting.chain_time` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_anchor: Option<ChainAnchor>,
+    /// Root of the Merkle accumulator over every batch's window and `bitmap_hash` up to and including this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accumulator_root: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg(test)]
+impl Batch {
+    /// A 19-of-20 batch for tests; override fields with `..Batch::sample()`
+    pub fn sample() -> Self {
+        Self {
+            n: 20,
+            good: 19,
+            threshold: 19,
+            threshold_percent: None,
+            bitmap_hash: "abc".to_string(),
+            bitmap_hashes: BTreeMap::new(),
+            window: TimeWindow { start: 1729785600, end: 1729786200 },
+            categories: CategoryCounts::default(),
+            reason_bitmap_hash: None,
+            weighted_good: None,
+            content_hash: None,
+            warn_threshold: None,
+            health: None,
+            da_commitment: None,
+            chain_anchor: None,
+            accumulator_root: None,
+        }
+    }
+}
+
+/// Where a batch's uptime falls relative to the warn and target thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchHealth {
+    /// At or above the warn threshold (or the target, if no warn threshold is set)
+    Healthy,
+    /// Meets the target threshold but not the warn threshold
+    Degraded,
+    /// Below the target threshold: the attestation would fail
+    Failed,
+}
+
+/// Per-category sample counts within a batch
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryCounts {
+    pub ok: usize,
+    pub stale: usize,
+    pub stuck_head: usize,
+    pub other: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TimeWindow {
     pub start: u64,
     pub end: u64,
@@ -93,6 +360,21 @@ pub enum MetricValue {
     },
 }
 
+impl MetricValue {
+    /// Mean observed value: the value itself for numbers, `sum / count` for distributions
+    ///
+    /// `None` when it can't be known: a histogram exported without its optional `sum`
+    /// (never treated as 0), or a distribution with no observations.
+    pub fn average(&self) -> Option<f64> {
+        match self {
+            MetricValue::Int(value) => Some(*value as f64),
+            MetricValue::Double(value) => Some(*value),
+            MetricValue::Histogram { count, sum, .. } => sum.filter(|_| *count > 0).map(|sum| sum / *count as f64),
+            MetricValue::Summary { count, sum, .. } => (*count > 0).then(|| sum / *count as f64),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistogramBucket {
     pub count: u64,
@@ -105,3 +387,26 @@ pub struct SummaryQuantile {
     pub value: f64,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_without_sum_has_no_average() {
+        let histogram = |sum| MetricValue::Histogram { count: 4, sum, buckets: Vec::new() };
+        assert_eq!(histogram(None).average(), None);
+        assert_eq!(histogram(Some(10.0)).average(), Some(2.5));
+        // A sum of exactly zero is a real average, not "unavailable"
+        assert_eq!(histogram(Some(0.0)).average(), Some(0.0));
+
+        let empty = MetricValue::Histogram { count: 0, sum: Some(0.0), buckets: Vec::new() };
+        assert_eq!(empty.average(), None);
+    }
+
+    #[test]
+    fn test_average_of_numbers_and_summaries() {
+        assert_eq!(MetricValue::Int(7).average(), Some(7.0));
+        let summary = MetricValue::Summary { count: 2, sum: 3.0, quantiles: Vec::new() };
+        assert_eq!(summary.average(), Some(1.5));
+    }
+}
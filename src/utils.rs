@@ -1,4 +1,6 @@
 use chrono::{DateTime, Utc};
+use rand::Rng;
+use std::time::Duration;
 
 /// Format Unix timestamp to human-readable string
 pub fn format_timestamp(ts: u64) -> String {
@@ -7,3 +9,101 @@ pub fn format_timestamp(ts: u64) -> String {
     dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
 }
 
+/// Whether `name` is a valid Prometheus/OpenMetrics label name: `[a-zA-Z_][a-zA-Z0-9_]*`
+pub fn is_valid_label_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether DAS metrics last updated at `last_update` are too old at `now`
+///
+/// Never-updated metrics are stale; data exactly `max_staleness_secs` old is still fresh.
+pub fn is_stale(last_update: Option<u64>, now: u64, max_staleness_secs: u64) -> bool {
+    match last_update {
+        Some(update_time) => now.saturating_sub(update_time) > max_staleness_secs,
+        None => true,
+    }
+}
+
+/// Number of sampler ticks in a batch window, i.e. the ring buffer capacity
+///
+/// Rounds up, so a window that isn't a multiple of the tick still holds every tick that
+/// lands in it (600s at 45s ticks = 14 ticks). Errors on `tick_secs = 0`.
+pub fn window_size(window_secs: u64, tick_secs: u64) -> anyhow::Result<usize> {
+    anyhow::ensure!(tick_secs > 0, "sampling.tick_secs must be greater than 0");
+    Ok(window_secs.div_ceil(tick_secs) as usize)
+}
+
+/// Exponential backoff with full jitter: a random delay in `[0, min(max, base * 2^attempt)]`
+///
+/// Draw from `AppState::rng` so tests can seed it for reproducible sequences. Spaces out
+/// retries of failed DA sample submissions.
+pub fn backoff_with_jitter(rng: &mut impl Rng, attempt: u32, base: Duration, max: Duration) -> Duration {
+    let cap = base.saturating_mul(2u32.saturating_pow(attempt)).min(max);
+    Duration::from_millis(rng.gen_range(0..=cap.as_millis() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::types::AppState;
+
+    fn backoff_sequence(rng: &mut StdRng) -> Vec<Duration> {
+        (0..8)
+            .map(|attempt| backoff_with_jitter(rng, attempt, Duration::from_millis(100), Duration::from_secs(5)))
+            .collect()
+    }
+
+    #[test]
+    fn test_backoff_identical_under_fixed_seed() {
+        let config = std::sync::Arc::new(crate::config::Config::for_tests());
+        let first = AppState::new(config.clone()).with_rng_seed(42);
+        let second = AppState::new(config).with_rng_seed(42);
+        let first = backoff_sequence(&mut first.rng.lock().unwrap());
+        assert_eq!(first, backoff_sequence(&mut second.rng.lock().unwrap()));
+        assert_ne!(first, backoff_sequence(&mut StdRng::seed_from_u64(43)));
+    }
+
+    #[test]
+    fn test_backoff_bounded_by_exponential_cap() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for (attempt, delay) in backoff_sequence(&mut rng).into_iter().enumerate() {
+            let cap = (100u64 << attempt).min(5000);
+            assert!(delay <= Duration::from_millis(cap), "attempt {}: {:?}", attempt, delay);
+        }
+    }
+
+    #[test]
+    fn test_window_size_exact_and_non_divisible() {
+        assert_eq!(window_size(600, 30).unwrap(), 20);
+        assert_eq!(window_size(600, 45).unwrap(), 14);
+        assert_eq!(window_size(10, 30).unwrap(), 1);
+        assert_eq!(window_size(0, 30).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_window_size_zero_tick_is_an_error() {
+        assert!(window_size(600, 0).is_err());
+        assert!(window_size(0, 0).is_err());
+    }
+
+    #[test]
+    fn test_is_stale_never_updated() {
+        assert!(is_stale(None, 1000, 120));
+    }
+
+    #[test]
+    fn test_is_stale_boundary() {
+        assert!(!is_stale(Some(880), 1000, 120));
+        assert!(is_stale(Some(879), 1000, 120));
+        assert!(!is_stale(Some(1000), 1000, 120));
+    }
+
+    #[test]
+    fn test_is_stale_update_in_future() {
+        // Clock skew: an update "from the future" counts as fresh
+        assert!(!is_stale(Some(1010), 1000, 0));
+    }
+}